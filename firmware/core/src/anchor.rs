@@ -0,0 +1,71 @@
+//! The anchor watch: an alarm for when the boat drags its anchor, or
+//! breaks from its mooring, and moves further than a radius from where
+//! the watch was set.
+
+use crate::gps::{hypot, LocalProjection};
+use crate::settings::next_choice;
+
+/// The radii that can be chosen, in metres
+pub const RADIUS_M: [u8; 5] = [20, 30, 50, 75, 100];
+
+pub struct AnchorWatch {
+    projection: Option<LocalProjection>,
+    radius_m: u8,
+    // From the latest fix. Kept while the fix is lost, so that an alarm
+    // isn't cancelled by it.
+    distance_m: Option<f32>,
+}
+
+impl AnchorWatch {
+    pub fn new() -> Self {
+        AnchorWatch {
+            projection: None,
+            radius_m: 30,
+            distance_m: None,
+        }
+    }
+
+    /// Watch the distance from `position_raw`
+    pub fn set(&mut self, position_raw: (i32, i32)) {
+        self.projection = Some(LocalProjection::new(position_raw.0, position_raw.1));
+        self.distance_m = Some(0.0);
+    }
+
+    pub fn clear(&mut self) {
+        self.projection = None;
+        self.distance_m = None;
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.projection.is_some()
+    }
+
+    pub fn radius_m(&self) -> u8 {
+        self.radius_m
+    }
+
+    /// Step through the radii in `RADIUS_M`
+    pub fn next_radius(&mut self) {
+        self.radius_m = next_choice(&RADIUS_M, self.radius_m);
+    }
+
+    /// Take the latest fix, if there is one
+    pub fn update(&mut self, position_raw: Option<(i32, i32)>) {
+        if let (Some(projection), Some(p)) = (self.projection, position_raw) {
+            let (east, north) = projection.project(p.0, p.1);
+            self.distance_m = Some(hypot(east, north));
+        }
+    }
+
+    pub fn distance_m(&self) -> Option<f32> {
+        self.distance_m
+    }
+
+    /// Further than the radius from where the watch was set
+    pub fn is_alarm(&self) -> bool {
+        match self.distance_m {
+            Some(d) => d > self.radius_m as f32,
+            None => false,
+        }
+    }
+}
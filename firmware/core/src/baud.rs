@@ -0,0 +1,156 @@
+//! Finding the baud rate the receiver is talking at.
+//!
+//! At the wrong baud rate the bytes read are garbage, so the receiver is
+//! listened to at each rate in turn until a whole frame, UBX or NMEA,
+//! arrives with a good checksum.
+
+/// The rates listened at, most likely first: the receiver's default, and
+/// the rate it is switched to once set up
+pub const CANDIDATE_BAUDS: [u32; 6] = [9600, 115200, 38400, 57600, 19200, 4800];
+
+// Longer frames than these are taken for garbage
+const MAX_UBX_LEN: u16 = 1024;
+const MAX_NMEA_LEN: u16 = 82;
+
+const UBX_SYNC_1: u8 = 0xb5;
+const UBX_SYNC_2: u8 = 0x62;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum State {
+    Idle,
+    UbxSync,
+    // The count of class, id and length bytes read
+    UbxHeader(u8),
+    UbxPayload,
+    UbxChecksumA,
+    UbxChecksumB,
+    Nmea,
+    // With the first checksum digit, once read
+    NmeaChecksum(Option<u8>),
+}
+
+/// Watches a stream of bytes for a complete, valid frame
+pub struct FrameDetector {
+    state: State,
+    len: u16,
+    count: u16,
+    // The Fletcher checksum of a UBX frame, or the xor of an NMEA sentence
+    ck_a: u8,
+    ck_b: u8,
+}
+
+impl FrameDetector {
+    pub fn new() -> Self {
+        FrameDetector {
+            state: State::Idle,
+            len: 0,
+            count: 0,
+            ck_a: 0,
+            ck_b: 0,
+        }
+    }
+
+    /// Forget any frame part read, after a receive error
+    pub fn reset(&mut self) {
+        self.state = State::Idle;
+    }
+
+    /// Take the next byte received, returning true when it completes a
+    /// valid frame
+    pub fn feed(&mut self, b: u8) -> bool {
+        match self.state {
+            State::Idle => self.start(b),
+            State::UbxSync => {
+                if b == UBX_SYNC_2 {
+                    self.ck_a = 0;
+                    self.ck_b = 0;
+                    self.state = State::UbxHeader(0);
+                } else {
+                    self.start(b);
+                }
+            }
+            State::UbxHeader(read) => {
+                self.checksum(b);
+                match read {
+                    2 => self.len = b as u16,
+                    3 => self.len |= (b as u16) << 8,
+                    _ => (),
+                }
+                self.state = if read < 3 {
+                    State::UbxHeader(read + 1)
+                } else if self.len > MAX_UBX_LEN {
+                    State::Idle
+                } else if self.len == 0 {
+                    State::UbxChecksumA
+                } else {
+                    self.count = 0;
+                    State::UbxPayload
+                };
+            }
+            State::UbxPayload => {
+                self.checksum(b);
+                self.count += 1;
+                if self.count == self.len {
+                    self.state = State::UbxChecksumA;
+                }
+            }
+            State::UbxChecksumA => {
+                if b == self.ck_a {
+                    self.state = State::UbxChecksumB;
+                } else {
+                    self.start(b);
+                }
+            }
+            State::UbxChecksumB => {
+                self.state = State::Idle;
+                return b == self.ck_b;
+            }
+            State::Nmea => {
+                if b == b'*' {
+                    self.state = State::NmeaChecksum(None);
+                } else if b != b'$' && (0x20..0x7f).contains(&b) && self.count < MAX_NMEA_LEN {
+                    self.ck_a ^= b;
+                    self.count += 1;
+                } else {
+                    self.start(b);
+                }
+            }
+            State::NmeaChecksum(first) => match (first, hex_digit(b)) {
+                (None, Some(high)) => self.state = State::NmeaChecksum(Some(high)),
+                (Some(high), Some(low)) => {
+                    self.state = State::Idle;
+                    return ((high << 4) | low) == self.ck_a;
+                }
+                (_, None) => self.start(b),
+            },
+        }
+        false
+    }
+
+    // Look for the start of a frame at `b`
+    fn start(&mut self, b: u8) {
+        self.state = match b {
+            UBX_SYNC_1 => State::UbxSync,
+            b'$' => {
+                self.ck_a = 0;
+                self.count = 0;
+                State::Nmea
+            }
+            _ => State::Idle,
+        };
+    }
+
+    fn checksum(&mut self, b: u8) {
+        self.ck_a = self.ck_a.wrapping_add(b);
+        self.ck_b = self.ck_b.wrapping_add(self.ck_a);
+    }
+}
+
+fn hex_digit(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        _ => None,
+    }
+}
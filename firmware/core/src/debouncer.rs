@@ -0,0 +1,206 @@
+/// Reports a change of input once it has held for `wait_ms`, timed in
+/// milliseconds from any clock that wraps at `u32::MAX`, so that it
+/// doesn't depend on how often the input is read
+pub struct Debouncer {
+    state: State,
+    // When the input started changing
+    since_ms: u32,
+    wait_ms: u32,
+}
+
+impl Debouncer {
+    pub fn new(wait_ms: u32) -> Self {
+        Debouncer {
+            state: State::Init,
+            since_ms: 0,
+            wait_ms,
+        }
+    }
+
+    /// Whether the input has been stable for long enough, so that
+    /// `next` won't report a transition until it changes
+    pub fn is_settled(&self) -> bool {
+        matches!(self.state, State::High | State::Low)
+    }
+
+    /// Feed in the input at `now_ms`
+    pub fn next(&mut self, input: bool, now_ms: u32) -> Option<Transition> {
+        let held = now_ms.wrapping_sub(self.since_ms) >= self.wait_ms;
+        match (&self.state, input) {
+            (State::Init, true) => {
+                self.state = State::High;
+                Option::None
+            }
+            (State::Init, false) => {
+                self.state = State::Low;
+                Option::None
+            }
+            (State::Low, false) => Option::None,
+            (State::Low, true) => {
+                self.state = State::ToHigh;
+                self.since_ms = now_ms;
+                Option::None
+            }
+            (State::ToHigh, false) => {
+                self.state = State::Low;
+                Option::None
+            }
+            (State::ToHigh, true) => {
+                if held {
+                    self.state = State::High;
+                    Option::Some(Transition::ToHigh)
+                } else {
+                    Option::None
+                }
+            }
+            (State::High, true) => Option::None,
+            (State::High, false) => {
+                self.state = State::ToLow;
+                self.since_ms = now_ms;
+                Option::None
+            }
+            (State::ToLow, true) => {
+                self.state = State::High;
+                Option::None
+            }
+            (State::ToLow, false) => {
+                if held {
+                    self.state = State::Low;
+                    Option::Some(Transition::ToLow)
+                } else {
+                    Option::None
+                }
+            }
+        }
+    }
+}
+
+enum State {
+    Init,
+    ToHigh,
+    High,
+    ToLow,
+    Low,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    ToHigh,
+    ToLow,
+}
+
+/// A button gesture, recognised from the debounced presses and releases
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Press {
+    Short,
+    /// Reported once the button has been held for `long_ms`
+    Long,
+    /// A second press within `double_ms` of the first being released
+    Double,
+    /// Reported every `repeat_ms` while the button is held after a long press
+    Repeat,
+}
+
+#[derive(Clone, Copy)]
+pub struct GestureConfig {
+    pub long_ms: u32,
+    /// How long to wait for a second press. Zero reports short presses as
+    /// soon as the button is released, and never reports double presses.
+    pub double_ms: u32,
+    pub repeat_ms: u32,
+}
+
+/// Recognises gestures from a debounced button, timed in milliseconds
+/// from any clock that wraps at `u32::MAX`.
+pub struct Gestures {
+    debouncer: Debouncer,
+    config: GestureConfig,
+    state: GestureState,
+}
+
+#[derive(Clone, Copy)]
+enum GestureState {
+    Released,
+    Pressed { at_ms: u32 },
+    Held { next_repeat_ms: u32 },
+    WaitingForSecond { released_ms: u32 },
+    // Ignore the rest of a press that has already been reported
+    WaitingForRelease,
+}
+
+impl Gestures {
+    pub fn new(debouncer: Debouncer, config: GestureConfig) -> Self {
+        Gestures {
+            debouncer,
+            config,
+            state: GestureState::Released,
+        }
+    }
+
+    /// Whether nothing is pending: the button is released and settled,
+    /// and there's no short press waiting to see if it's a double
+    pub fn is_idle(&self) -> bool {
+        matches!(self.state, GestureState::Released) && self.debouncer.is_settled()
+    }
+
+    /// Feed in the button state, `pressed`, at `now_ms`. This must be
+    /// called often, even when the button isn't changing, for long presses
+    /// and repeats to be reported on time.
+    pub fn next(&mut self, pressed: bool, now_ms: u32) -> Option<Press> {
+        let transition = self.debouncer.next(pressed, now_ms);
+        let since = |t: u32| now_ms.wrapping_sub(t);
+        match (self.state, transition) {
+            (GestureState::Released, Some(Transition::ToHigh)) => {
+                self.state = GestureState::Pressed { at_ms: now_ms };
+                None
+            }
+            (GestureState::Pressed { .. }, Some(Transition::ToLow)) => {
+                if self.config.double_ms == 0 {
+                    self.state = GestureState::Released;
+                    Some(Press::Short)
+                } else {
+                    self.state = GestureState::WaitingForSecond {
+                        released_ms: now_ms,
+                    };
+                    None
+                }
+            }
+            (GestureState::Pressed { at_ms }, None) if since(at_ms) >= self.config.long_ms => {
+                self.state = GestureState::Held {
+                    next_repeat_ms: now_ms.wrapping_add(self.config.repeat_ms),
+                };
+                Some(Press::Long)
+            }
+            (GestureState::Held { .. }, Some(Transition::ToLow)) => {
+                self.state = GestureState::Released;
+                None
+            }
+            (GestureState::Held { next_repeat_ms }, None) => {
+                // Compared this way round to cope with the clock wrapping
+                if (now_ms.wrapping_sub(next_repeat_ms) as i32) >= 0 {
+                    self.state = GestureState::Held {
+                        next_repeat_ms: next_repeat_ms.wrapping_add(self.config.repeat_ms),
+                    };
+                    Some(Press::Repeat)
+                } else {
+                    None
+                }
+            }
+            (GestureState::WaitingForSecond { .. }, Some(Transition::ToHigh)) => {
+                self.state = GestureState::WaitingForRelease;
+                Some(Press::Double)
+            }
+            (GestureState::WaitingForSecond { released_ms }, None)
+                if since(released_ms) >= self.config.double_ms =>
+            {
+                self.state = GestureState::Released;
+                Some(Press::Short)
+            }
+            (GestureState::WaitingForRelease, Some(Transition::ToLow)) => {
+                self.state = GestureState::Released;
+                None
+            }
+            _ => None,
+        }
+    }
+}
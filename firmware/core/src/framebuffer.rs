@@ -0,0 +1,101 @@
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::{OriginDimensions, Point, Size};
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::primitives::Rectangle;
+use embedded_graphics::Pixel;
+
+pub fn new_ls027b7dh01() -> FrameBuffer<12000, 240> {
+    FrameBuffer::new()
+}
+
+/// An in memory display, for rendering screens without the hardware.
+///
+/// The pixels are laid out as in `MemoryDisplay`: a row of bytes per
+/// line, leftmost pixel in the msb, with the bit set for `BinaryColor::Off`
+/// (which the LCD shows as white).
+pub struct FrameBuffer<const N: usize, const H: usize> {
+    framebuf: [u8; N],
+}
+
+impl<const N: usize, const H: usize> FrameBuffer<N, H> {
+    pub fn new() -> Self {
+        FrameBuffer {
+            framebuf: [255; N],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        N / H * 8
+    }
+
+    pub fn height(&self) -> usize {
+        H
+    }
+
+    /// The bytes of each line, top to bottom
+    pub fn lines(&self) -> impl Iterator<Item = &[u8]> {
+        self.framebuf.chunks(N / H)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.framebuf
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> BinaryColor {
+        let byte_offset = y * (N / H) + (x / 8);
+        let bit = 128u8.rotate_right((x % 8) as u32);
+        if self.framebuf[byte_offset] & bit != 0 {
+            BinaryColor::Off
+        } else {
+            BinaryColor::On
+        }
+    }
+}
+
+impl<const N: usize, const H: usize> DrawTarget for FrameBuffer<N, H> {
+    type Color = BinaryColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let bounds = Rectangle::new(Point::new(0, 0), self.size());
+        let widthb = N / H;
+
+        for Pixel(coord, color) in pixels.into_iter() {
+            if !bounds.contains(coord) {
+                continue;
+            }
+
+            let x = coord.x as usize;
+            let y = coord.y as usize;
+            let byte_offset = y * widthb + (x / 8);
+            let bit = 128u8.rotate_right((x % 8) as u32);
+
+            if color == BinaryColor::Off {
+                self.framebuf[byte_offset] |= bit;
+            } else {
+                self.framebuf[byte_offset] &= !bit;
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        for b in self.framebuf.iter_mut() {
+            *b = if color == BinaryColor::Off {
+                0xff
+            } else {
+                0x00
+            };
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize, const H: usize> OriginDimensions for FrameBuffer<N, H> {
+    fn size(&self) -> Size {
+        Size::new(self.width() as u32, self.height() as u32)
+    }
+}
@@ -0,0 +1,707 @@
+use micromath::F32Ext;
+
+use crate::gps_setup::{Ack, DynModel, GpsPort, GpsSetup, GpsStatus, Nav5, Reply};
+use crate::history::{SpeedHistory, TrackHistory};
+use crate::manoeuvre::{ManoeuvreDetector, ManoeuvreStats};
+use crate::metrics::{Metrics, Rankings, MS_PER_WEEK};
+use crate::settings::Settings;
+use crate::track_log::{self, TrackPoint};
+use crate::units::SpeedUnit;
+
+// The shortest time between position updates, at the fastest rate in
+// settings::RATE_HZ
+const MIN_MESSAGE_MS: u16 = 100;
+
+// How many speed samples we need to smooth the speed
+// over the longest window in settings::SMOOTHING_S
+const MAX_SPEED_SAMPLES: usize = 5 * 1000 / (MIN_MESSAGE_MS as usize);
+
+// How many speed samples we need to average over the
+// longest window in settings::AVG_WINDOW_S
+const MAX_SPEED_AVG_SAMPLES: usize = 60 * 1000 / (MIN_MESSAGE_MS as usize);
+
+// The time between points in the speed history
+const HISTORY_POINT_S: u8 = 5;
+
+// The distance between breadcrumbs on the track
+const TRACK_SPACING_M: f32 = 10.0;
+
+#[derive(Clone)]
+pub struct GpsData {
+    pub sat_in_use: u8,
+    pub course: Option<f32>,
+    pub speed: f32,
+    pub max_speed: f32,
+    pub avg_speed: f32,
+    pub max_avg_speed: f32,
+    /// The window `avg_speed` is averaged over
+    pub avg_window_s: u8,
+    pub distance_m: u32,
+    pub hdop: Option<f32>,
+    pub latitude: Option<f32>,
+    pub longitude: Option<f32>,
+    /// The position in the receiver's units of 1e-7 degrees, for the
+    /// geometry that `latitude` and `longitude` are too coarse for
+    pub position_raw: Option<(i32, i32)>,
+    pub time: Option<GpsTime>,
+    pub rankings: Rankings,
+    /// The recent speeds, in knots
+    pub history: SpeedHistory,
+    /// The recent track
+    pub track: TrackHistory,
+    /// Tacks and gybes this trip
+    pub manoeuvres: ManoeuvreStats,
+    /// The unit the speeds above are shown in; they're stored in knots
+    pub units: SpeedUnit,
+    /// Whether the receiver has been found and set up
+    pub status: GpsStatus,
+    /// The fixes left out of the speeds and statistics for being too
+    /// inaccurate, since power up
+    pub rejected_samples: u32,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct GpsTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub min: u8,
+    pub sec: u8,
+}
+
+pub struct Gps {
+    parser: ublox::Parser<GpsBuffer>,
+
+    output: GpsData,
+
+    updated: Option<()>, // atomic bool???
+
+    track_point: Option<TrackPoint>,
+
+    // The time between measurements, and the windows the speed is
+    // smoothed and averaged over
+    measure_ms: u16,
+    smoothing_ms: u32,
+    avg_window_ms: u32,
+    speed_samples: AverageBuffer<MAX_SPEED_SAMPLES>,
+    avg_speed_samples: AverageBuffer<MAX_SPEED_AVG_SAMPLES>,
+    filter: SampleFilter,
+    // The time of week of the last speed added to the history
+    history_itow: Option<u32>,
+
+    // Finding and setting up the receiver, and whether it still needs
+    // telling about new settings once set up
+    setup: GpsSetup,
+    dyn_model: DynModel,
+    reconfigure: bool,
+
+    // The receiver's odometer, and its reading when the trip was reset
+    odo_m: u32,
+    trip_start_odo_m: u32,
+
+    metrics: Metrics,
+    manoeuvre_detector: ManoeuvreDetector,
+}
+
+impl Gps {
+    pub fn new() -> Gps {
+        let buf = GpsBuffer::new();
+        let parser = ublox::Parser::new(buf);
+
+        Gps {
+            parser,
+            output: GpsData {
+                sat_in_use: 0,
+                course: None,
+                hdop: None,
+                latitude: None,
+                longitude: None,
+                position_raw: None,
+                speed: 0f32,
+                distance_m: 0,
+                time: None,
+                max_speed: 0f32,
+                avg_speed: 0f32,
+                max_avg_speed: 0f32,
+                avg_window_s: 10,
+                rankings: Rankings::default(),
+                history: SpeedHistory::new(HISTORY_POINT_S),
+                track: TrackHistory::new(TRACK_SPACING_M),
+                manoeuvres: ManoeuvreStats::default(),
+                units: SpeedUnit::Knots,
+                status: GpsStatus::Searching,
+                rejected_samples: 0,
+            },
+
+            updated: Option::Some(()),
+            track_point: None,
+            measure_ms: 500,
+            smoothing_ms: 1000,
+            avg_window_ms: 10_000,
+            speed_samples: AverageBuffer::with_window(2),
+            avg_speed_samples: AverageBuffer::with_window(20),
+            filter: SampleFilter::from_settings(&Settings::default()),
+            history_itow: None,
+            setup: GpsSetup::new(500, DynModel::Sea),
+            dyn_model: DynModel::Sea,
+            reconfigure: false,
+            odo_m: 0,
+            trip_start_odo_m: 0,
+            metrics: Metrics::new(),
+            manoeuvre_detector: ManoeuvreDetector::new(),
+        }
+    }
+
+    /// Start looking for the receiver, to set it up
+    pub fn start<P: GpsPort>(&mut self, port: &mut P) {
+        self.setup.start(port);
+        self.update_status();
+    }
+
+    /// Take a byte from the receiver, which moves setup on until it is done
+    pub fn received<P: GpsPort>(&mut self, received: u8, port: &mut P) {
+        if self.setup.detecting() {
+            self.setup.received(received, port);
+        } else if let Some(reply) = self.parse(received) {
+            self.setup.reply(reply, port);
+        }
+        if self.reconfigure && self.setup.status() == GpsStatus::Ready {
+            self.setup.reconfigure(port);
+            self.reconfigure = false;
+        }
+        self.update_status();
+    }
+
+    /// The timer `id` started through the port has run out
+    pub fn timeout<P: GpsPort>(&mut self, id: u32, port: &mut P) {
+        self.setup.timeout(id, port);
+        self.update_status();
+    }
+
+    pub fn status(&self) -> GpsStatus {
+        self.setup.status()
+    }
+
+    fn update_status(&mut self) {
+        if self.output.status != self.setup.status() {
+            self.output.status = self.setup.status();
+            self.updated = Some(());
+        }
+    }
+
+    /// Forget any partly received message, after a receive error
+    pub fn parse_clear(&mut self) {
+        rprintln!("gps: parse clear");
+        let buf = GpsBuffer::new();
+        self.parser = ublox::Parser::new(buf);
+        self.setup.receive_error();
+    }
+
+    pub fn parse_u8(&mut self, received: u8) {
+        self.parse(received);
+    }
+
+    // Returns the answer to a setup command completed by `received`, if any
+    fn parse(&mut self, received: u8) -> Option<Reply> {
+        let nb = [received; 1];
+        let mut reply = None;
+
+        let mut it = self.parser.consume(&nb);
+        loop {
+            match it.next() {
+                Some(Ok(ublox::PacketRef::NavPosVelTime(sol))) => {
+                    rprintln!("gps: NavPosVelTime {}", sol.flags().bits());
+                    self.output.sat_in_use = sol.num_satellites();
+                    let fix_ok = sol.flags().contains(ublox::NavPosVelTimeFlags::GPS_FIX_OK);
+                    let rejected = fix_ok && !self.filter.accepts(&sol);
+                    if fix_ok {
+                        self.output.hdop = None;
+                        self.output.latitude = Some(degrees_from_raw(sol.lat_degrees_raw()));
+                        self.output.longitude = Some(degrees_from_raw(sol.lon_degrees_raw()));
+                        self.output.position_raw =
+                            Some((sol.lat_degrees_raw(), sol.lon_degrees_raw()));
+                        if !rejected {
+                            self.output
+                                .track
+                                .add((sol.lat_degrees_raw(), sol.lon_degrees_raw()));
+                        }
+                        self.output.course = Some(heading_from_raw(sol.heading_degrees_raw()));
+                        let time = GpsTime {
+                            year: sol.year(),
+                            month: sol.month(),
+                            day: sol.day(),
+                            hour: sol.hour(),
+                            min: sol.min(),
+                            sec: sol.sec(),
+                        };
+                        // Only log once the receiver has the date and time
+                        if time_valid(&sol) && !rejected {
+                            self.track_point = track_point_from_raw(&sol, &time);
+                        }
+                        self.output.time = Some(time);
+                        if rejected {
+                            self.output.rejected_samples =
+                                self.output.rejected_samples.saturating_add(1);
+                            // Runs and turns can't span a fix whose speed
+                            // isn't known
+                            self.metrics.interrupt();
+                            self.manoeuvre_detector.interrupt();
+                        } else {
+                            self.metrics.add(
+                                sol.itow(),
+                                sol.ground_speed_raw(),
+                                sol.lat_degrees_raw(),
+                                sol.lon_degrees_raw(),
+                            );
+                            self.output.rankings = self.metrics.rankings();
+                            if let Some(manoeuvre) = self.manoeuvre_detector.add(
+                                sol.itow(),
+                                knots_from_raw(sol.ground_speed_raw()),
+                                heading_from_raw(sol.heading_degrees_raw()),
+                            ) {
+                                self.output.manoeuvres.add(manoeuvre);
+                            }
+                        }
+                    } else {
+                        self.output.hdop = None;
+                        self.output.latitude = None;
+                        self.output.longitude = None;
+                        self.output.position_raw = None;
+                        self.output.course = None;
+                        self.output.time = None;
+                        self.speed_samples.clear();
+                        self.avg_speed_samples.clear();
+                        self.metrics.interrupt();
+                        self.manoeuvre_detector.interrupt();
+                    }
+                    if rejected {
+                        // The position is still shown, but kept off the
+                        // track and out of the log, and the speed could be
+                        // a spike
+                        continue;
+                    }
+                    let raw_speed = knots_from_raw(sol.ground_speed_raw());
+
+                    self.speed_samples.add(raw_speed);
+                    self.output.speed = self.speed_samples.avg_value();
+                    update_max(&mut self.output.max_speed, self.output.speed);
+
+                    self.avg_speed_samples.add(raw_speed);
+                    self.output.avg_speed = self.avg_speed_samples.avg_value();
+                    update_max(&mut self.output.max_avg_speed, self.output.avg_speed);
+
+                    // The history goes by the time since its last speed,
+                    // so that fixes left out don't shorten it
+                    let since_ms = match self.history_itow {
+                        Some(last) => (sol.itow() + MS_PER_WEEK - last) % MS_PER_WEEK,
+                        None => 0,
+                    };
+                    self.history_itow = Some(sol.itow());
+                    self.output
+                        .history
+                        .add(raw_speed, since_ms.max(self.measure_ms as u32));
+                }
+                Some(Ok(ublox::PacketRef::MonVer(monver))) => {
+                    rprintln!(
+                        "gps: MonVer {}/{}",
+                        monver.hardware_version(),
+                        monver.software_version()
+                    );
+                }
+                Some(Ok(ublox::PacketRef::AckAck(a))) => {
+                    reply = Some(Reply::Ack(Ack {
+                        class: a.class(),
+                        id: a.msg_id(),
+                        accepted: true,
+                    }));
+                }
+                Some(Ok(ublox::PacketRef::AckNak(nak))) => {
+                    reply = Some(Reply::Ack(Ack {
+                        class: nak.class(),
+                        id: nak.msg_id(),
+                        accepted: false,
+                    }));
+                }
+                Some(Ok(ublox::PacketRef::CfgNav5(nav5))) => {
+                    reply = Some(Reply::Nav5(Nav5 {
+                        dyn_model: nav5.dyn_model_raw(),
+                        fix_mode: nav5.fix_mode_raw(),
+                        static_hold_cm_s: nav5.static_hold_thresh_raw(),
+                    }));
+                }
+                Some(Ok(ublox::PacketRef::NavOdo(odo))) => {
+                    self.odo_m = odo.distance();
+                    if self.odo_m < self.trip_start_odo_m {
+                        // The receiver's odometer has been reset
+                        self.trip_start_odo_m = 0;
+                    }
+                    self.output.distance_m = self.odo_m - self.trip_start_odo_m;
+                    self.updated = Some(());
+                }
+                Some(Ok(_)) => {
+                    rprintln!("gps: ignored unused packet");
+                    // Recevied a valid packet not of interest, ignore it
+                }
+                Some(Err(e)) => {
+                    rprintln!("gps: ignored malformed packet {:#}", e);
+                    // Received a malformed packet, ignore it
+                }
+                None => {
+                    // We've eaten all the packets we have
+                    break;
+                }
+            }
+        }
+        reply
+    }
+
+    pub fn set_units(&mut self, units: SpeedUnit) {
+        self.output.units = units;
+        self.updated = Some(());
+    }
+
+    /// Use the units, averaging windows, measurement rate, dynamic model
+    /// and accuracy thresholds from `settings`. A new rate or model is sent
+    /// to the receiver once it is set up.
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.set_units(settings.units);
+        self.filter = SampleFilter::from_settings(settings);
+        let measure_ms = 1000 / settings.rate_hz.max(1) as u16;
+        if measure_ms != self.measure_ms || settings.gps_model != self.dyn_model {
+            self.measure_ms = measure_ms;
+            self.dyn_model = settings.gps_model;
+            self.setup.set_settings(measure_ms, settings.gps_model);
+            // A receiver still being looked for is sent them when found
+            self.reconfigure = !matches!(
+                self.setup.status(),
+                GpsStatus::Searching | GpsStatus::NotResponding
+            );
+        }
+        self.smoothing_ms = settings.smoothing_s as u32 * 1000;
+        self.avg_window_ms = settings.avg_window_s as u32 * 1000;
+        self.output.avg_window_s = settings.avg_window_s;
+        let window = self.samples_for(self.smoothing_ms);
+        if self.speed_samples.window() != window {
+            self.speed_samples.set_window(window);
+        }
+        let window = self.samples_for(self.avg_window_ms);
+        if self.avg_speed_samples.window() != window {
+            self.avg_speed_samples.set_window(window);
+        }
+    }
+
+    /// Whether new settings are waiting to be sent to the receiver
+    pub fn reconfigure_pending(&self) -> bool {
+        self.reconfigure
+    }
+
+    /// The number of measurements in `window_ms`
+    fn samples_for(&self, window_ms: u32) -> usize {
+        (window_ms / self.measure_ms as u32) as usize
+    }
+
+    /// Start a new trip: clear the maximum speeds, rankings, distance and
+    /// manoeuvres
+    pub fn reset_trip(&mut self) {
+        self.output.max_speed = 0f32;
+        self.output.max_avg_speed = 0f32;
+        self.metrics.reset();
+        self.output.rankings = self.metrics.rankings();
+        self.output.manoeuvres = ManoeuvreStats::default();
+        self.trip_start_odo_m = self.odo_m;
+        self.output.distance_m = 0;
+        self.updated = Some(());
+    }
+
+    /// The most recent fix, if not already taken, for logging
+    pub fn take_track_point(&mut self) -> Option<TrackPoint> {
+        self.track_point.take()
+    }
+
+    pub fn take(&mut self) -> Option<GpsData> {
+        let updated = self.updated.take();
+        if let Some(()) = updated {
+            Some(self.output.clone())
+        } else {
+            None
+        }
+    }
+}
+
+/// The accuracy a fix needs for its speed to be used
+struct SampleFilter {
+    max_speed_acc_mm_s: u32,
+    max_pos_acc_mm: u32,
+    min_sats: u8,
+}
+
+impl SampleFilter {
+    fn from_settings(settings: &Settings) -> Self {
+        SampleFilter {
+            max_speed_acc_mm_s: settings.speed_acc_cm_s as u32 * 10,
+            max_pos_acc_mm: settings.pos_acc_m as u32 * 1000,
+            min_sats: settings.min_sats,
+        }
+    }
+
+    // Only 3D fixes: a 2D fix assumes the height, which skews the speed
+    fn accepts(&self, sol: &ublox::NavPosVelTimeRef) -> bool {
+        matches!(
+            sol.fix_type(),
+            ublox::GpsFix::Fix3D | ublox::GpsFix::GPSPlusDeadReckoning
+        ) && sol.num_satellites() >= self.min_sats
+            && sol.horiz_accuracy() <= self.max_pos_acc_mm
+            && sol.speed_accuracy_estimate_raw() <= self.max_speed_acc_mm_s
+    }
+}
+
+fn update_max(max: &mut f32, v: f32) {
+    if v > *max {
+        *max = v;
+    }
+}
+
+struct GpsBuffer {
+    buffer: [u8; 192],
+    len: usize,
+}
+
+impl GpsBuffer {
+    fn new() -> Self {
+        GpsBuffer {
+            buffer: [0; 192],
+            len: 0,
+        }
+    }
+}
+
+impl core::ops::Index<usize> for GpsBuffer {
+    type Output = u8;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.buffer[index]
+    }
+}
+
+impl core::ops::Index<core::ops::Range<usize>> for GpsBuffer {
+    type Output = [u8];
+
+    fn index(&self, index: core::ops::Range<usize>) -> &Self::Output {
+        if index.end > self.len {
+            panic!("Index {} is outside of our length {}", index.end, self.len);
+        }
+        self.buffer.index(index)
+    }
+}
+
+impl ublox::UnderlyingBuffer for GpsBuffer {
+    fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn max_capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    fn extend_from_slice(&mut self, other: &[u8]) -> usize {
+        let to_copy = core::cmp::min(other.len(), self.buffer.len() - self.len);
+        let uncopyable = other.len() - to_copy;
+        self.buffer[self.len..self.len + to_copy].copy_from_slice(&other[..to_copy]);
+        self.len += to_copy;
+        uncopyable
+    }
+
+    fn drain(&mut self, count: usize) {
+        if count >= self.len {
+            self.len = 0;
+            return;
+        }
+
+        let new_size = self.len - count;
+        {
+            let bufptr = self.buffer.as_mut_ptr();
+            unsafe {
+                core::ptr::copy(bufptr.add(count), bufptr, new_size);
+            }
+        }
+        self.len = new_size;
+    }
+
+    fn find(&self, value: u8) -> Option<usize> {
+        for i in 0..self.len {
+            if self.buffer[i] == value {
+                return Some(i);
+            }
+        }
+        None
+    }
+}
+
+fn track_point_from_raw(sol: &ublox::NavPosVelTimeRef, time: &GpsTime) -> Option<TrackPoint> {
+    Some(TrackPoint {
+        time_s: track_log::seconds_since_2000(time)?,
+        time_ms: (sol.itow() % 1000) as u16,
+        sats: sol.num_satellites(),
+        session_start: false,
+        latitude_raw: sol.lat_degrees_raw(),
+        longitude_raw: sol.lon_degrees_raw(),
+        speed_mm_s: saturating_u16(sol.ground_speed_raw()),
+        course_cdeg: (sol.heading_degrees_raw() / 1000).rem_euclid(36000) as u16,
+        speed_acc_mm_s: saturating_u16(sol.speed_accuracy_estimate_raw()),
+    })
+}
+
+// Whether the receiver has the date and time, to the second
+fn time_valid(sol: &ublox::NavPosVelTimeRef) -> bool {
+    use ublox::NavPosVelTimeValidFlags as Valid;
+    sol.valid()
+        .contains(Valid::VALID_DATE | Valid::VALID_TIME | Valid::FULLY_RESOLVED)
+}
+
+fn saturating_u16(v: u32) -> u16 {
+    if v > u16::MAX as u32 {
+        u16::MAX
+    } else {
+        v as u16
+    }
+}
+
+fn degrees_from_raw(raw: i32) -> f32 {
+    raw as f32 * 1e-7
+}
+
+const EARTH_RADIUS_M: f32 = 6_371_000.0;
+
+// Metres per raw (1e-7 degree) unit of latitude
+const M_PER_RAW_LAT: f32 = EARTH_RADIUS_M * core::f32::consts::PI / 180.0 * 1e-7;
+
+/// A flat earth projection about a reference position, giving metres
+/// east and north of it. Good to a few metres over tens of kilometres.
+#[derive(Clone, Copy)]
+pub struct LocalProjection {
+    lat0_raw: i32,
+    lon0_raw: i32,
+    m_per_raw_lon: f32,
+}
+
+impl LocalProjection {
+    pub fn new(lat0_raw: i32, lon0_raw: i32) -> Self {
+        let lat0_rad = degrees_from_raw(lat0_raw) * core::f32::consts::PI / 180.0;
+        LocalProjection {
+            lat0_raw,
+            lon0_raw,
+            m_per_raw_lon: M_PER_RAW_LAT * lat0_rad.cos(),
+        }
+    }
+
+    /// (east, north) in metres. The differences are taken in the raw
+    /// integer units, to keep the precision that f32 degrees would lose.
+    pub fn project(&self, lat_raw: i32, lon_raw: i32) -> (f32, f32) {
+        let dlat = lat_raw.wrapping_sub(self.lat0_raw);
+        let dlon = lon_raw.wrapping_sub(self.lon0_raw);
+        (
+            dlon as f32 * self.m_per_raw_lon,
+            dlat as f32 * M_PER_RAW_LAT,
+        )
+    }
+}
+
+/// The (east, north) components of a velocity, from its speed and its
+/// course in degrees true
+pub fn enu_velocity(speed: f32, course: f32) -> (f32, f32) {
+    let course_rad = course * core::f32::consts::PI / 180.0;
+    (speed * course_rad.sin(), speed * course_rad.cos())
+}
+
+/// A point `east` and `north` of here, in a frame turned `degrees`
+/// clockwise so that the bearing `degrees` points along the y axis
+pub fn rotate(east: f32, north: f32, degrees: f32) -> (f32, f32) {
+    let rad = degrees * core::f32::consts::PI / 180.0;
+    let (sin, cos) = (rad.sin(), rad.cos());
+    (east * cos - north * sin, east * sin + north * cos)
+}
+
+/// The length of (x, y). micromath's square root is only good to a few
+/// percent, so it is refined with a couple of Newton steps.
+pub fn hypot(x: f32, y: f32) -> f32 {
+    let sq = x * x + y * y;
+    if sq == 0.0 {
+        return 0.0;
+    }
+    let mut r = sq.sqrt();
+    for _ in 0..2 {
+        r = 0.5 * (r + sq / r);
+    }
+    r
+}
+
+pub fn metres_per_s_from_knots(knots: f32) -> f32 {
+    knots / 1.943844
+}
+
+fn heading_from_raw(raw: i32) -> f32 {
+    raw as f32 * 1e-5
+}
+
+fn knots_from_raw(raw: u32) -> f32 {
+    raw as f32 * 1e-3 * 1.943844
+}
+
+/// The average of the last `len` samples added, where `len` is at most `N`.
+/// A running total is kept, so adding a sample and reading the average
+/// don't depend on the window.
+pub struct AverageBuffer<const N: usize> {
+    samples: [f32; N],
+    si: usize,
+    len: usize,
+    // The total of samples[..len]
+    sum: f32,
+}
+
+impl<const N: usize> AverageBuffer<N> {
+    pub fn new() -> Self {
+        AverageBuffer::with_window(N)
+    }
+
+    pub fn with_window(len: usize) -> Self {
+        AverageBuffer {
+            samples: [0f32; N],
+            si: 0,
+            len: len.clamp(1, N),
+            sum: 0f32,
+        }
+    }
+
+    pub fn window(&self) -> usize {
+        self.len
+    }
+
+    /// Change the number of samples averaged, clearing the buffer
+    pub fn set_window(&mut self, len: usize) {
+        *self = AverageBuffer::with_window(len);
+    }
+
+    pub fn clear(&mut self) {
+        self.set_window(self.len);
+    }
+
+    pub fn add(&mut self, v: f32) {
+        self.sum += v - self.samples[self.si];
+        self.samples[self.si] = v;
+        self.si = (self.si + 1) % self.len;
+        // Total up afresh once per window, so that rounding errors in the
+        // running total don't build up
+        if self.si == 0 {
+            self.sum = self.samples[..self.len].iter().sum();
+        }
+    }
+
+    pub fn avg_value(&self) -> f32 {
+        self.sum / (self.len as f32)
+    }
+}
@@ -0,0 +1,456 @@
+//! Finding and setting up the receiver, without blocking.
+//!
+//! Setup moves on as bytes arrive from the receiver and as timers run out,
+//! so the rest of the tracker runs meanwhile. First the receiver is
+//! listened for at each of the candidate baud rates, then it is switched to
+//! the link baud rate and sent each setup command in turn. The navigation
+//! settings are read back to check that they took. A command that is
+//! refused, not answered or didn't take is sent again, and after
+//! `MAX_RETRIES` the receiver is looked for afresh. Looking never stops,
+//! but after a whole round of baud rates without an answer the receiver is
+//! reported as not responding.
+
+use crate::baud::{FrameDetector, CANDIDATE_BAUDS};
+
+/// The baud rate the receiver is switched to. Each measurement is 128
+/// bytes, NavPosVelTime and NavOdo, so this leaves the link half idle even
+/// at the fastest rate.
+pub const LINK_BAUD: u32 = 115200;
+
+// How long to listen for a frame at each baud rate, to leave the receiver
+// to change baud rate, and to wait for an ack
+const DETECT_MS: u32 = 1500;
+const SWITCH_MS: u32 = 100;
+const ACK_MS: u32 = 1000;
+
+/// The times a command is sent again before starting over
+pub const MAX_RETRIES: u8 = 3;
+
+/// The speed below which the receiver holds the position still and reports
+/// no speed, in cm/s, so that the speed doesn't wander when moored
+pub const STATIC_HOLD_CM_S: u8 = 10;
+
+// Fix in 2D or 3D, whichever the satellites allow
+const FIX_MODE_AUTO: u8 = 3;
+
+/// The serial link to the receiver, and a timer
+pub trait GpsPort {
+    fn send(&mut self, bytes: &[u8]);
+
+    /// Change the baud rate, once anything sent has gone
+    fn set_baud(&mut self, baud: u32);
+
+    /// Call `Gps::timeout` with `id` after `ms`, unless it has been called
+    /// again meanwhile
+    fn start_timer(&mut self, id: u32, ms: u32);
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GpsStatus {
+    /// Listening for the receiver
+    Searching,
+    /// Found, and being set up
+    Configuring,
+    Ready,
+    /// No answer at any baud rate; still listening
+    NotResponding,
+}
+
+/// The receiver's dynamic platform model, which tunes its filtering to
+/// how the tracker is expected to move
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DynModel {
+    Sea,
+    Portable,
+    Automotive,
+    /// For accelerations under 1g
+    Airborne,
+}
+
+impl DynModel {
+    pub const ALL: [DynModel; 4] = [
+        DynModel::Sea,
+        DynModel::Portable,
+        DynModel::Automotive,
+        DynModel::Airborne,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DynModel::Sea => "sea",
+            DynModel::Portable => "portable",
+            DynModel::Automotive => "auto",
+            DynModel::Airborne => "airborne",
+        }
+    }
+
+    pub fn next(&self) -> DynModel {
+        match self {
+            DynModel::Sea => DynModel::Portable,
+            DynModel::Portable => DynModel::Automotive,
+            DynModel::Automotive => DynModel::Airborne,
+            DynModel::Airborne => DynModel::Sea,
+        }
+    }
+
+    pub fn to_u8(&self) -> u8 {
+        match self {
+            DynModel::Sea => 0,
+            DynModel::Portable => 1,
+            DynModel::Automotive => 2,
+            DynModel::Airborne => 3,
+        }
+    }
+
+    pub fn from_u8(v: u8) -> Option<DynModel> {
+        DynModel::ALL.get(v as usize).copied()
+    }
+
+    fn ubx(&self) -> ublox::CfgNav5DynModel {
+        use ublox::CfgNav5DynModel;
+        match self {
+            DynModel::Sea => CfgNav5DynModel::Sea,
+            DynModel::Portable => CfgNav5DynModel::Portable,
+            DynModel::Automotive => CfgNav5DynModel::Automotive,
+            DynModel::Airborne => CfgNav5DynModel::AirborneWithLess1gAcceleration,
+        }
+    }
+
+    // The value in CfgNav5
+    fn raw(&self) -> u8 {
+        match self {
+            DynModel::Sea => 5,
+            DynModel::Portable => 0,
+            DynModel::Automotive => 4,
+            DynModel::Airborne => 6,
+        }
+    }
+}
+
+/// An answer to a configuration message
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Ack {
+    pub class: u8,
+    pub id: u8,
+    /// False if the receiver refused the message
+    pub accepted: bool,
+}
+
+/// The navigation settings read back from the receiver, as raw CfgNav5
+/// values
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Nav5 {
+    pub dyn_model: u8,
+    pub fix_mode: u8,
+    pub static_hold_cm_s: u8,
+}
+
+/// What the receiver says to the setup commands
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Reply {
+    Ack(Ack),
+    Nav5(Nav5),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Command {
+    UseUbx,
+    Rate,
+    Nav5,
+    // Read back the navigation settings
+    CheckNav5,
+    EnablePvt,
+    EnableOdo,
+}
+
+const COMMANDS: [Command; 6] = [
+    Command::UseUbx,
+    Command::Rate,
+    Command::Nav5,
+    Command::CheckNav5,
+    Command::EnablePvt,
+    Command::EnableOdo,
+];
+
+// Where the commands changed by the settings start
+const SETTINGS_COMMAND: usize = 1;
+
+// The command setting what CheckNav5 reads back
+const NAV5_COMMAND: usize = 2;
+
+impl Command {
+    fn send<P: GpsPort>(&self, port: &mut P, measure_ms: u16, dyn_model: DynModel) {
+        use ublox::*;
+        match self {
+            Command::UseUbx => port.send(&cfg_prt_uart(LINK_BAUD)),
+            Command::Rate => port.send(&cfg_rate(measure_ms)),
+            Command::Nav5 => port.send(
+                &CfgNav5Builder {
+                    mask: CfgNav5Params::DYN
+                        | CfgNav5Params::POS_FIX_MODE
+                        | CfgNav5Params::STATIC_HOLD_MASK,
+                    dyn_model: dyn_model.ubx(),
+                    fix_mode: CfgNav5FixMode::Auto2D3D,
+                    static_hold_thresh: STATIC_HOLD_CM_S as f32 / 100.0,
+                    ..CfgNav5Builder::default()
+                }
+                .into_packet_bytes(),
+            ),
+            Command::CheckNav5 => {
+                port.send(&UbxPacketRequest::request_for::<CfgNav5>().into_packet_bytes())
+            }
+            Command::EnablePvt => port.send(
+                &CfgMsgAllPortsBuilder::set_rate_for::<NavPosVelTime>([0, 1, 0, 0, 0, 0])
+                    .into_packet_bytes(),
+            ),
+            Command::EnableOdo => port.send(
+                &CfgMsgAllPortsBuilder::set_rate_for::<NavOdo>([0, 1, 0, 0, 0, 0])
+                    .into_packet_bytes(),
+            ),
+        }
+    }
+
+    // Whether `ack` is the answer to this command; CheckNav5 is answered
+    // with the settings instead
+    fn answered_by(&self, ack: &Ack) -> bool {
+        use ublox::*;
+        let (class, id) = match self {
+            Command::UseUbx => (CfgPrtUart::CLASS, CfgPrtUart::ID),
+            Command::Rate => (CfgRate::CLASS, CfgRate::ID),
+            Command::Nav5 => (CfgNav5::CLASS, CfgNav5::ID),
+            Command::CheckNav5 => return false,
+            Command::EnablePvt | Command::EnableOdo => (CfgMsgAllPorts::CLASS, CfgMsgAllPorts::ID),
+        };
+        ack.class == class && ack.id == id
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Step {
+    // Listening at CANDIDATE_BAUDS[index]
+    Detect(usize),
+    // Told to change to the link baud rate, from the one it was found at
+    Switch,
+    // Waiting for the ack to COMMANDS[index]
+    Command(usize),
+    Done,
+}
+
+pub struct GpsSetup {
+    step: Step,
+    status: GpsStatus,
+    detector: FrameDetector,
+    retries: u8,
+    measure_ms: u16,
+    dyn_model: DynModel,
+    // Identifies the latest timer, so that earlier ones are ignored
+    timer_id: u32,
+}
+
+impl GpsSetup {
+    pub fn new(measure_ms: u16, dyn_model: DynModel) -> Self {
+        GpsSetup {
+            step: Step::Done,
+            status: GpsStatus::Searching,
+            detector: FrameDetector::new(),
+            retries: 0,
+            measure_ms,
+            dyn_model,
+            timer_id: 0,
+        }
+    }
+
+    pub fn status(&self) -> GpsStatus {
+        self.status
+    }
+
+    /// Whether received bytes are only being looked at for a valid frame,
+    /// rather than parsed
+    pub fn detecting(&self) -> bool {
+        matches!(self.step, Step::Detect(_))
+    }
+
+    /// Change the measurement rate and dynamic model to set. Once set up,
+    /// the receiver needs to be `reconfigure`d for them to take effect.
+    pub fn set_settings(&mut self, measure_ms: u16, dyn_model: DynModel) {
+        self.measure_ms = measure_ms;
+        self.dyn_model = dyn_model;
+    }
+
+    /// Send the receiver the settings again, once it is set up
+    pub fn reconfigure<P: GpsPort>(&mut self, port: &mut P) {
+        if self.step == Step::Done {
+            self.status = GpsStatus::Configuring;
+            self.retries = 0;
+            self.command(SETTINGS_COMMAND, port);
+        }
+    }
+
+    /// Start looking for the receiver
+    pub fn start<P: GpsPort>(&mut self, port: &mut P) {
+        self.status = GpsStatus::Searching;
+        self.detect(0, port);
+    }
+
+    /// Take a byte received while detecting
+    pub fn received<P: GpsPort>(&mut self, b: u8, port: &mut P) {
+        if let Step::Detect(index) = self.step {
+            if self.detector.feed(b) {
+                self.found(CANDIDATE_BAUDS[index], port);
+            }
+        }
+    }
+
+    /// Forget any frame part received, after a receive error
+    pub fn receive_error(&mut self) {
+        self.detector.reset();
+    }
+
+    /// Take an answer from the receiver
+    pub fn reply<P: GpsPort>(&mut self, reply: Reply, port: &mut P) {
+        let index = match self.step {
+            Step::Command(index) => index,
+            _ => return,
+        };
+        match reply {
+            Reply::Ack(ack) if COMMANDS[index].answered_by(&ack) => {
+                if ack.accepted {
+                    // The navigation settings are only done with once read
+                    // back, so their retries count on until then
+                    if index != NAV5_COMMAND {
+                        self.retries = 0;
+                    }
+                    self.command(index + 1, port);
+                } else {
+                    rprintln!("gps: nak for command {}", index);
+                    self.retry(index, port);
+                }
+            }
+            Reply::Nav5(nav5) if COMMANDS[index] == Command::CheckNav5 => {
+                let expected = Nav5 {
+                    dyn_model: self.dyn_model.raw(),
+                    fix_mode: FIX_MODE_AUTO,
+                    static_hold_cm_s: STATIC_HOLD_CM_S,
+                };
+                if nav5 == expected {
+                    self.retries = 0;
+                    self.command(index + 1, port);
+                } else {
+                    // Set them again
+                    rprintln!("gps: navigation settings didn't take");
+                    self.retry(NAV5_COMMAND, port);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// The timer `id` has run out
+    pub fn timeout<P: GpsPort>(&mut self, id: u32, port: &mut P) {
+        if id != self.timer_id {
+            return;
+        }
+        match self.step {
+            Step::Detect(index) if index + 1 < CANDIDATE_BAUDS.len() => {
+                self.detect(index + 1, port)
+            }
+            Step::Detect(_) => {
+                rprintln!("gps: not responding");
+                self.status = GpsStatus::NotResponding;
+                self.detect(0, port);
+            }
+            Step::Switch => {
+                port.set_baud(LINK_BAUD);
+                self.command(0, port);
+            }
+            Step::Command(index) => self.retry(index, port),
+            Step::Done => (),
+        }
+    }
+
+    fn detect<P: GpsPort>(&mut self, index: usize, port: &mut P) {
+        let baud = CANDIDATE_BAUDS[index];
+        rprintln!("gps: listening at {}", baud);
+        self.step = Step::Detect(index);
+        self.detector.reset();
+        port.set_baud(baud);
+        // Ask for the version, in case the receiver isn't sending anything
+        // otherwise
+        port.send(&ublox::UbxPacketRequest::request_for::<ublox::MonVer>().into_packet_bytes());
+        self.start_timer(DETECT_MS, port);
+    }
+
+    fn found<P: GpsPort>(&mut self, baud: u32, port: &mut P) {
+        rprintln!("gps: found at {}", baud);
+        self.status = GpsStatus::Configuring;
+        self.retries = 0;
+        if baud == LINK_BAUD {
+            self.command(0, port);
+        } else {
+            // The receiver changes baud rate as soon as it takes this, so
+            // it's sent again at the new rate, to be acked
+            Command::UseUbx.send(port, self.measure_ms, self.dyn_model);
+            self.step = Step::Switch;
+            self.start_timer(SWITCH_MS, port);
+        }
+    }
+
+    fn command<P: GpsPort>(&mut self, index: usize, port: &mut P) {
+        match COMMANDS.get(index) {
+            Some(command) => {
+                self.step = Step::Command(index);
+                command.send(port, self.measure_ms, self.dyn_model);
+                self.start_timer(ACK_MS, port);
+            }
+            None => {
+                rprintln!("gps: ready");
+                self.step = Step::Done;
+                self.status = GpsStatus::Ready;
+                port.send(
+                    &ublox::UbxPacketRequest::request_for::<ublox::MonVer>().into_packet_bytes(),
+                );
+            }
+        }
+    }
+
+    fn retry<P: GpsPort>(&mut self, index: usize, port: &mut P) {
+        self.retries += 1;
+        if self.retries > MAX_RETRIES {
+            rprintln!("gps: giving up on command {}", index);
+            self.start(port);
+        } else {
+            self.command(index, port);
+        }
+    }
+
+    fn start_timer<P: GpsPort>(&mut self, ms: u32, port: &mut P) {
+        self.timer_id = self.timer_id.wrapping_add(1);
+        port.start_timer(self.timer_id, ms);
+    }
+}
+
+fn cfg_prt_uart(baud: u32) -> [u8; 28] {
+    use ublox::*;
+    CfgPrtUartBuilder {
+        portid: UartPortId::Uart1,
+        reserved0: 0,
+        tx_ready: 0,
+        mode: UartMode::new(DataBits::Eight, Parity::None, StopBits::One),
+        baud_rate: baud,
+        in_proto_mask: InProtoMask::all(),
+        out_proto_mask: OutProtoMask::UBLOX,
+        flags: 0,
+        reserved5: 0,
+    }
+    .into_packet_bytes()
+}
+
+fn cfg_rate(measure_ms: u16) -> [u8; 14] {
+    use ublox::*;
+    CfgRateBuilder {
+        measure_rate_ms: measure_ms,
+        nav_rate: 1,
+        time_ref: AlignmentToReferenceTime::Utc,
+    }
+    .into_packet_bytes()
+}
@@ -0,0 +1,87 @@
+//! GPX 1.1 output of logged sessions, written a point at a time so that
+//! it can be streamed out of the device without buffering.
+//!
+//! Speed and course aren't part of GPX 1.1, so they go in the Garmin
+//! TrackPointExtension, which the usual analysis tools understand.
+
+use core::fmt::{self, Write};
+
+use crate::storage::Flash;
+use crate::track_log::{Session, TrackLog, TrackPoint};
+
+pub fn write_header<W: Write>(out: &mut W) -> fmt::Result {
+    write!(
+        out,
+        concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<gpx version=\"1.1\" creator=\"gpstracker\"\n",
+            "  xmlns=\"http://www.topografix.com/GPX/1/1\"\n",
+            "  xmlns:gpxtpx=\"http://www.garmin.com/xmlschemas/TrackPointExtension/v2\"\n",
+            "  xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\"\n",
+            "  xsi:schemaLocation=\"http://www.topografix.com/GPX/1/1 ",
+            "http://www.topografix.com/GPX/1/1/gpx.xsd\">\n"
+        )
+    )
+}
+
+pub fn write_footer<W: Write>(out: &mut W) -> fmt::Result {
+    writeln!(out, "</gpx>")
+}
+
+/// A session as a track, named for its start time
+pub fn write_session<W: Write, F: Flash>(out: &mut W, session: &Session<F>) -> fmt::Result {
+    write!(out, "<trk><name>")?;
+    write_time(out, session.first())?;
+    writeln!(out, "</name><trkseg>")?;
+    for point in session.points() {
+        write_point(out, &point)?;
+    }
+    writeln!(out, "</trkseg></trk>")
+}
+
+/// Every session in the log, as a complete document
+pub fn write_log<W: Write, F: Flash>(out: &mut W, log: &TrackLog<F>) -> fmt::Result {
+    write_header(out)?;
+    for session in log.sessions() {
+        write_session(out, &session)?;
+    }
+    write_footer(out)
+}
+
+pub fn write_point<W: Write>(out: &mut W, point: &TrackPoint) -> fmt::Result {
+    write!(out, "<trkpt lat=\"")?;
+    write_degrees(out, point.latitude_raw)?;
+    write!(out, "\" lon=\"")?;
+    write_degrees(out, point.longitude_raw)?;
+    write!(out, "\"><time>")?;
+    write_time(out, point)?;
+    write!(out, "</time><sat>{}</sat>", point.sats)?;
+    write!(
+        out,
+        "<extensions><gpxtpx:TrackPointExtension><gpxtpx:speed>{}.{:03}</gpxtpx:speed>",
+        point.speed_mm_s / 1000,
+        point.speed_mm_s % 1000
+    )?;
+    writeln!(
+        out,
+        "<gpxtpx:course>{}.{:02}</gpxtpx:course></gpxtpx:TrackPointExtension></extensions></trkpt>",
+        point.course_cdeg / 100,
+        point.course_cdeg % 100
+    )
+}
+
+// Degrees from their raw 1e-7 scaled value, without going via floats
+fn write_degrees<W: Write>(out: &mut W, raw: i32) -> fmt::Result {
+    let sign = if raw < 0 { "-" } else { "" };
+    let abs = raw.unsigned_abs();
+    write!(out, "{}{}.{:07}", sign, abs / 10_000_000, abs % 10_000_000)
+}
+
+fn write_time<W: Write>(out: &mut W, point: &TrackPoint) -> fmt::Result {
+    let t = point.time();
+    write!(
+        out,
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        t.year, t.month, t.day, t.hour, t.min, t.sec, point.time_ms
+    )
+}
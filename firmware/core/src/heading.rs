@@ -0,0 +1,104 @@
+//! Wind shifts, read from the course against a reference heading.
+//!
+//! Sailing to windward, the reference is set while settled on a tack. A
+//! later course closer to the wind than the reference is a lift, and one
+//! further from it a header. Which way is closer depends on the tack.
+
+/// Changes smaller than this, in degrees, are put down to steering
+pub const STEADY_DEG: f32 = 3.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Tack {
+    /// The wind coming over the port side, so a lift turns the boat to port
+    Port,
+    /// The wind coming over the starboard side, so a lift turns the boat
+    /// to starboard
+    Starboard,
+}
+
+impl Tack {
+    pub fn other(&self) -> Tack {
+        match self {
+            Tack::Port => Tack::Starboard,
+            Tack::Starboard => Tack::Port,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Tack::Port => "port",
+            Tack::Starboard => "stbd",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Shift {
+    /// Degrees closer to the wind than the reference
+    Lift(f32),
+    /// Degrees further from the wind than the reference
+    Header(f32),
+    Steady,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct HeadingReference {
+    heading: Option<f32>,
+    tack: Tack,
+}
+
+impl HeadingReference {
+    pub fn new() -> Self {
+        HeadingReference {
+            heading: None,
+            tack: Tack::Starboard,
+        }
+    }
+
+    /// Take `heading` as the reference, in degrees true
+    pub fn set(&mut self, heading: f32) {
+        self.heading = Some(heading);
+    }
+
+    pub fn heading(&self) -> Option<f32> {
+        self.heading
+    }
+
+    pub fn tack(&self) -> Tack {
+        self.tack
+    }
+
+    pub fn change_tack(&mut self) {
+        self.tack = self.tack.other();
+    }
+
+    /// The shift shown by sailing `course`, once the reference is set
+    pub fn shift(&self, course: f32) -> Option<Shift> {
+        let delta = heading_delta(self.heading?, course);
+        // Turning to starboard, clockwise, is toward the wind on starboard
+        let toward_wind = match self.tack {
+            Tack::Starboard => delta,
+            Tack::Port => -delta,
+        };
+        Some(if toward_wind >= STEADY_DEG {
+            Shift::Lift(toward_wind)
+        } else if toward_wind <= -STEADY_DEG {
+            Shift::Header(-toward_wind)
+        } else {
+            Shift::Steady
+        })
+    }
+}
+
+/// The turn from heading `from` to `to`, in degrees, positive to starboard
+/// and between -180 and 180
+pub fn heading_delta(from: f32, to: f32) -> f32 {
+    let delta = (to - from) % 360.0;
+    if delta > 180.0 {
+        delta - 360.0
+    } else if delta <= -180.0 {
+        delta + 360.0
+    } else {
+        delta
+    }
+}
@@ -0,0 +1,210 @@
+//! The recent history of the speed and track, for graphing and plotting.
+//!
+//! Speed samples are averaged into points, several seconds apart however
+//! often the samples come, and the latest `HISTORY_LEN` points are kept in
+//! a ring. The track keeps a breadcrumb every few metres travelled, the
+//! latest `TRACK_LEN` of them.
+
+use crate::gps::{hypot, LocalProjection};
+use micromath::F32Ext;
+
+/// The number of points kept
+pub const HISTORY_LEN: usize = 120;
+
+/// The number of breadcrumbs kept
+pub const TRACK_LEN: usize = 200;
+
+/// The latest `N` values pushed
+#[derive(Clone)]
+struct Ring<T, const N: usize> {
+    values: [T; N],
+    // The index of the oldest value, once the ring is full
+    head: usize,
+    len: usize,
+}
+
+impl<T: Copy, const N: usize> Ring<T, N> {
+    fn new(empty: T) -> Self {
+        Ring {
+            values: [empty; N],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, value: T) {
+        if self.len < N {
+            self.values[self.len] = value;
+            self.len += 1;
+        } else {
+            self.values[self.head] = value;
+            self.head = (self.head + 1) % N;
+        }
+    }
+
+    fn last(&self) -> Option<T> {
+        match self.len {
+            0 => None,
+            len => Some(self.values[(self.head + len - 1) % N]),
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        (0..self.len).map(move |i| self.values[(self.head + i) % N])
+    }
+}
+
+#[derive(Clone)]
+pub struct SpeedHistory {
+    points: Ring<f32, HISTORY_LEN>,
+    // The samples averaged into the next point so far, and the time they
+    // cover
+    sum: f32,
+    samples: usize,
+    elapsed_ms: u32,
+    point_s: u8,
+    // How many points have ever been added, to tell when it changes
+    added: u32,
+}
+
+impl SpeedHistory {
+    /// A history with a point every `point_s` seconds
+    pub fn new(point_s: u8) -> Self {
+        SpeedHistory {
+            points: Ring::new(0f32),
+            sum: 0f32,
+            samples: 0,
+            elapsed_ms: 0,
+            point_s,
+            added: 0,
+        }
+    }
+
+    /// Add a speed sample, covering `sample_ms` since the one before,
+    /// returning true when it completes a point
+    pub fn add(&mut self, speed: f32, sample_ms: u32) -> bool {
+        self.sum += speed;
+        self.samples += 1;
+        self.elapsed_ms += sample_ms;
+        if self.elapsed_ms < self.point_s as u32 * 1000 {
+            return false;
+        }
+        self.points.push(self.sum / self.samples as f32);
+        self.sum = 0f32;
+        self.samples = 0;
+        self.elapsed_ms = 0;
+        self.added = self.added.wrapping_add(1);
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.len == 0
+    }
+
+    /// The seconds between points
+    pub fn point_s(&self) -> u8 {
+        self.point_s
+    }
+
+    /// Changes whenever a point is added
+    pub fn added(&self) -> u32 {
+        self.added
+    }
+
+    /// The points, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = f32> + '_ {
+        self.points.iter()
+    }
+
+    pub fn max(&self) -> f32 {
+        self.iter().fold(0f32, f32::max)
+    }
+}
+
+/// Positions along the recent track, in the receiver's units of 1e-7
+/// degrees
+#[derive(Clone)]
+pub struct TrackHistory {
+    points: Ring<(i32, i32), TRACK_LEN>,
+    spacing_m: f32,
+    added: u32,
+}
+
+impl TrackHistory {
+    /// A track with breadcrumbs at least `spacing_m` apart
+    pub fn new(spacing_m: f32) -> Self {
+        TrackHistory {
+            points: Ring::new((0, 0)),
+            spacing_m,
+            added: 0,
+        }
+    }
+
+    /// Add a fix, returning true if it is far enough from the last
+    /// breadcrumb to make a new one
+    pub fn add(&mut self, position_raw: (i32, i32)) -> bool {
+        if let Some(last) = self.points.last() {
+            let (east, north) =
+                LocalProjection::new(last.0, last.1).project(position_raw.0, position_raw.1);
+            if hypot(east, north) < self.spacing_m {
+                return false;
+            }
+        }
+        self.points.push(position_raw);
+        self.added = self.added.wrapping_add(1);
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.len == 0
+    }
+
+    /// Changes whenever a breadcrumb is added
+    pub fn added(&self) -> u32 {
+        self.added
+    }
+
+    pub fn last(&self) -> Option<(i32, i32)> {
+        self.points.last()
+    }
+
+    /// The breadcrumbs, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.points.iter()
+    }
+}
+
+// Axis steps, in display units
+const STEPS: [u16; 7] = [1, 2, 5, 10, 20, 50, 100];
+
+/// The top of a speed axis reaching `max`, and the step between its
+/// ticks, keeping to five steps or fewer
+pub fn axis_scale(max: f32) -> (u16, u16) {
+    let step = STEPS
+        .iter()
+        .copied()
+        .find(|&step| max <= 5.0 * step as f32)
+        .unwrap_or(STEPS[STEPS.len() - 1]);
+    let steps = ((max / step as f32).ceil() as u16).max(1);
+    (steps * step, step)
+}
+
+// Map ranges, in metres from the boat to the edge
+const ZOOM_M: [u32; 9] = [50, 100, 200, 500, 1000, 2000, 5000, 10_000, 20_000];
+
+/// The smallest map range that reaches `extent_m`
+pub fn zoom_m(extent_m: f32) -> u32 {
+    ZOOM_M
+        .iter()
+        .copied()
+        .find(|&zoom| extent_m <= zoom as f32)
+        .unwrap_or(ZOOM_M[ZOOM_M.len() - 1])
+}
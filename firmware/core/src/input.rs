@@ -0,0 +1,142 @@
+//! The reed switch inputs, turned into events for the screens.
+//!
+//! There's always the push button. A rotary switch, with a reed switch
+//! for each position, can select a page directly, and a flip switch can
+//! pause logging. Each switch closes its pin to ground, so pins should be
+//! pulled up.
+
+use embedded_hal::digital::v2::InputPin;
+
+use crate::debouncer::{Debouncer, GestureConfig, Gestures, Press, Transition};
+use crate::screens::Page;
+
+// How long a switch must stay put for a change to count
+const DEBOUNCE_MS: u32 = 10;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Input {
+    Button(Press),
+    /// The rotary switch has been turned to this page
+    SelectPage(Page),
+    /// Whether to log fixes, from the flip switch
+    Logging(bool),
+}
+
+/// A switch that stays where it's put, debounced
+pub struct Switch<P: InputPin> {
+    pin: P,
+    debouncer: Debouncer,
+    reported: bool,
+}
+
+impl<P: InputPin> Switch<P> {
+    pub fn new(pin: P) -> Self {
+        Switch {
+            pin,
+            debouncer: Debouncer::new(DEBOUNCE_MS),
+            reported: false,
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        self.pin.is_low().unwrap_or(false)
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.reported && self.debouncer.is_settled()
+    }
+
+    /// Whether the switch is closed, when that changes, and at first,
+    /// reading it at `now_ms`
+    pub fn poll(&mut self, now_ms: u32) -> Option<bool> {
+        let closed = self.is_closed();
+        let transition = self.debouncer.next(closed, now_ms);
+        if !self.reported {
+            self.reported = true;
+            return Some(closed);
+        }
+        transition.map(|t| t == Transition::ToHigh)
+    }
+}
+
+/// A rotary switch with a reed switch for each of `N` positions, and the
+/// page each position selects
+pub struct RotarySwitch<P: InputPin, const N: usize> {
+    positions: [Switch<P>; N],
+    pages: [Page; N],
+}
+
+impl<P: InputPin, const N: usize> RotarySwitch<P, N> {
+    pub fn new(pins: [P; N], pages: [Page; N]) -> Self {
+        RotarySwitch {
+            positions: pins.map(Switch::new),
+            pages,
+        }
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.positions.iter().all(|p| p.is_settled())
+    }
+
+    /// The page for the position the switch has just moved to. Between
+    /// positions, when no reed switch is closed, the page stays put.
+    pub fn poll(&mut self, now_ms: u32) -> Option<Page> {
+        let mut selected = None;
+        for (switch, page) in self.positions.iter_mut().zip(self.pages.iter()) {
+            if switch.poll(now_ms) == Some(true) {
+                selected = Some(*page);
+            }
+        }
+        selected
+    }
+}
+
+pub struct Inputs<P: InputPin, const N: usize> {
+    button: P,
+    gestures: Gestures,
+    rotary: Option<RotarySwitch<P, N>>,
+    // Closed pauses logging, so that logging carries on if the switch
+    // isn't fitted
+    flip: Option<Switch<P>>,
+}
+
+impl<P: InputPin, const N: usize> Inputs<P, N> {
+    pub fn new(
+        button: P,
+        gestures: GestureConfig,
+        rotary: Option<RotarySwitch<P, N>>,
+        flip: Option<Switch<P>>,
+    ) -> Self {
+        Inputs {
+            button,
+            gestures: Gestures::new(Debouncer::new(DEBOUNCE_MS), gestures),
+            rotary,
+            flip,
+        }
+    }
+
+    /// Whether polling can stop until an input changes, as nothing is
+    /// changing or being timed
+    pub fn is_idle(&self) -> bool {
+        self.gestures.is_idle()
+            && self.rotary.as_ref().map(|r| r.is_settled()).unwrap_or(true)
+            && self.flip.as_ref().map(|f| f.is_settled()).unwrap_or(true)
+    }
+
+    /// Read the inputs at `now_ms`, returning at most one event. This
+    /// must be called regularly while the inputs aren't idle, for the
+    /// debouncing and the button gestures to be timed.
+    pub fn poll(&mut self, now_ms: u32) -> Option<Input> {
+        let pressed = self.button.is_low().unwrap_or(false);
+        if let Some(press) = self.gestures.next(pressed, now_ms) {
+            return Some(Input::Button(press));
+        }
+        if let Some(page) = self.rotary.as_mut().and_then(|r| r.poll(now_ms)) {
+            return Some(Input::SelectPage(page));
+        }
+        if let Some(closed) = self.flip.as_mut().and_then(|f| f.poll(now_ms)) {
+            return Some(Input::Logging(!closed));
+        }
+        None
+    }
+}
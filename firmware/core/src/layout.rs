@@ -53,31 +53,40 @@ pub struct Layout {
 
 impl Layout {
     pub fn new() -> Layout {
+        Layout::with_colors(WHITE, BLACK)
+    }
+
+    /// Black on white rather than white on black
+    pub fn new_inverted() -> Layout {
+        Layout::with_colors(BLACK, WHITE)
+    }
+
+    fn with_colors(fg: DPixelColor, bg: DPixelColor) -> Layout {
         let char_18 = MonoTextStyleBuilder::new()
             .font(&profont::PROFONT_18_POINT)
-            .text_color(WHITE)
-            .background_color(BLACK)
+            .text_color(fg)
+            .background_color(bg)
             .build();
         let char_24 = MonoTextStyleBuilder::new()
             .font(&profont::PROFONT_24_POINT)
-            .text_color(WHITE)
-            .background_color(BLACK)
+            .text_color(fg)
+            .background_color(bg)
             .build();
         let char_78 = MonoTextStyleBuilder::new()
             .font(&MEDNUMBER_FONT)
-            .text_color(WHITE)
-            .background_color(BLACK)
+            .text_color(fg)
+            .background_color(bg)
             .build();
         let char_156 = MonoTextStyleBuilder::new()
             .font(&BIGNUMBER_FONT)
-            .text_color(WHITE)
-            .background_color(BLACK)
+            .text_color(fg)
+            .background_color(bg)
             .build();
         let text_style = TextStyle::with_baseline(Baseline::Top);
-        let bg_fill_style = PrimitiveStyle::with_fill(BLACK);
+        let bg_fill_style = PrimitiveStyle::with_fill(bg);
         let fg_fill_style = PrimitiveStyleBuilder::new()
-            .fill_color(WHITE)
-            .stroke_color(WHITE)
+            .fill_color(fg)
+            .stroke_color(fg)
             .stroke_width(2)
             .build();
         return Layout {
@@ -203,6 +212,21 @@ impl<'a> Font<'a> {
         Result::Ok(loc + Point::new(kern * 2 + diam, 0))
     }
 
+    /// A colon between digits, as narrow as the kerned decimal point
+    pub fn write_kerned_colon<D>(&self, display: &mut D, loc: Point) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        let char_height = self.char_style.font.character_size.height as i32;
+        let diam = char_height / 7;
+        for y in [char_height / 3, char_height * 2 / 3] {
+            Circle::new(loc + Point::new(0, y - diam / 2), diam as u32)
+                .into_styled(*self.fg_fill_style)
+                .draw(display)?;
+        }
+        Result::Ok(loc + Point::new(diam, 0))
+    }
+
     pub fn render_field<D, const N: usize>(
         &self,
         display: &mut D,
@@ -0,0 +1,45 @@
+//! Hardware independent parts of the gps tracker: the UBX parser and
+//! statistics, the screens and their layout. This crate is `no_std` and
+//! builds for both the firmware target and the host, so that it can be
+//! tested with `cargo test`.
+#![no_std]
+
+// Diagnostic output goes over RTT on the device, and nowhere on the host.
+#[cfg(feature = "rtt")]
+macro_rules! rprintln {
+    ($($arg:tt)*) => {
+        rtt_target::rprintln!($($arg)*)
+    };
+}
+
+#[cfg(not(feature = "rtt"))]
+macro_rules! rprintln {
+    ($($arg:tt)*) => {{
+        let _ = core::format_args!($($arg)*);
+    }};
+}
+
+pub mod anchor;
+pub mod baud;
+pub mod debouncer;
+pub mod framebuffer;
+pub mod gps;
+pub mod gps_setup;
+pub mod gpx;
+pub mod heading;
+pub mod history;
+pub mod input;
+pub mod layout;
+pub mod manoeuvre;
+pub mod menu;
+pub mod metrics;
+pub mod screens;
+pub mod settings;
+pub mod start_line;
+pub mod start_timer;
+pub mod storage;
+pub mod track_log;
+pub mod u8writer;
+pub mod units;
+pub mod uptime;
+pub mod waypoints;
@@ -0,0 +1,221 @@
+//! Tack and gybe detection, and how much each one cost.
+//!
+//! While sailing steadily the course and speed are smoothed, to give the
+//! entry course and speed. A turn starts when the course strays from the
+//! smoothed course, and ends once it has held steady again for a couple of
+//! seconds. Turns of at least `MANOEUVRE_DEG` are manoeuvres, which are
+//! then followed until the speed recovers.
+//!
+//! Without the wind direction, tacks are told from gybes by the speed: a
+//! tack, turning through the wind, stalls the boat far more than a gybe.
+
+use crate::gps::metres_per_s_from_knots;
+use crate::heading::heading_delta;
+use crate::metrics::MS_PER_WEEK;
+
+/// The smallest turn counted as a manoeuvre, in degrees
+pub const MANOEUVRE_DEG: f32 = 60.0;
+
+// The course change that starts a turn, and the turn rate, in degrees a
+// second, below which the course counts as steady again
+const TURN_START_DEG: f32 = 20.0;
+const STEADY_DEG_S: f32 = 8.0;
+const STEADY_MS: u32 = 2_000;
+
+// Turns taking longer than this are just changes of course
+const MAX_TURN_MS: u32 = 20_000;
+
+// Manoeuvres are only looked for above this speed, in knots
+const MIN_SPEED: f32 = 2.0;
+
+// The speed counts as recovered at this fraction of the entry speed, and
+// a manoeuvre slowing the boat below `TACK_SPEED` of it is a tack
+const RECOVERED_SPEED: f32 = 0.9;
+const TACK_SPEED: f32 = 0.6;
+const MAX_RECOVER_MS: u32 = 30_000;
+
+// Longer than this between fixes and the turn is lost
+const MAX_FIX_GAP_MS: u32 = 2_000;
+
+// How quickly the entry course and speed follow the boat, per second
+const SMOOTHING: f32 = 0.3;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Kind {
+    Tack,
+    Gybe,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Manoeuvre {
+    pub kind: Kind,
+    /// Degrees, positive for a turn to starboard
+    pub turn_deg: f32,
+    /// Knots, between the entry speed and the slowest point
+    pub speed_loss: f32,
+    /// From the start of the turn until the speed recovered, if it did
+    pub recover_ms: Option<u32>,
+    /// Against carrying on at the entry speed until recovered, or given up
+    /// on
+    pub distance_lost_m: f32,
+}
+
+/// The last manoeuvre, and how many of each there have been
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct ManoeuvreStats {
+    pub last: Option<Manoeuvre>,
+    pub tacks: u16,
+    pub gybes: u16,
+}
+
+impl ManoeuvreStats {
+    pub fn add(&mut self, manoeuvre: Manoeuvre) {
+        match manoeuvre.kind {
+            Kind::Tack => self.tacks = self.tacks.saturating_add(1),
+            Kind::Gybe => self.gybes = self.gybes.saturating_add(1),
+        }
+        self.last = Some(manoeuvre);
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Turn {
+    start_ms: u32,
+    entry_course: f32,
+    entry_speed: f32,
+    min_speed: f32,
+    // Travelled since the start of the turn
+    distance_m: f32,
+    // The turn once the course steadied, and how long it has been steady
+    turn_deg: Option<f32>,
+    steady_ms: u32,
+}
+
+enum State {
+    // With the smoothed course and speed, once there is one
+    Steady(Option<(f32, f32)>),
+    Turning(Turn),
+    Recovering(Turn),
+}
+
+pub struct ManoeuvreDetector {
+    state: State,
+    // Elapsed time, and the time of week and course of the last fix
+    now_ms: u32,
+    last: Option<(u32, f32)>,
+}
+
+impl ManoeuvreDetector {
+    pub fn new() -> Self {
+        ManoeuvreDetector {
+            state: State::Steady(None),
+            now_ms: 0,
+            last: None,
+        }
+    }
+
+    /// The fix was lost: forget the turn in progress
+    pub fn interrupt(&mut self) {
+        self.state = State::Steady(None);
+        self.last = None;
+    }
+
+    /// Take a fix at GPS time of week `itow`, with `speed` in knots and
+    /// `course` in degrees, returning a manoeuvre when one is complete
+    pub fn add(&mut self, itow: u32, speed: f32, course: f32) -> Option<Manoeuvre> {
+        let (dt_ms, turn_rate) = match self.last {
+            Some((last_itow, last_course)) => {
+                let dt_ms = (itow + MS_PER_WEEK - last_itow) % MS_PER_WEEK;
+                if dt_ms == 0 || dt_ms > MAX_FIX_GAP_MS {
+                    self.interrupt();
+                    (0, 0f32)
+                } else {
+                    let rate = heading_delta(last_course, course).abs() * 1000.0 / dt_ms as f32;
+                    (dt_ms, rate)
+                }
+            }
+            None => (0, 0f32),
+        };
+        self.last = Some((itow, course));
+        self.now_ms = self.now_ms.wrapping_add(dt_ms);
+        let now_ms = self.now_ms;
+        let travelled_m = metres_per_s_from_knots(speed) * dt_ms as f32 / 1000.0;
+
+        match &mut self.state {
+            State::Steady(smoothed) => {
+                let (entry_course, entry_speed) = match *smoothed {
+                    Some(s) => s,
+                    None => {
+                        *smoothed = Some((course, speed));
+                        return None;
+                    }
+                };
+                let delta = heading_delta(entry_course, course);
+                if delta.abs() >= TURN_START_DEG && entry_speed >= MIN_SPEED {
+                    self.state = State::Turning(Turn {
+                        start_ms: now_ms.wrapping_sub(dt_ms),
+                        entry_course,
+                        entry_speed,
+                        min_speed: speed,
+                        distance_m: travelled_m,
+                        turn_deg: None,
+                        steady_ms: 0,
+                    });
+                } else {
+                    let k = (SMOOTHING * dt_ms as f32 / 1000.0).min(1.0);
+                    *smoothed = Some((
+                        entry_course + delta * k,
+                        entry_speed + (speed - entry_speed) * k,
+                    ));
+                }
+                None
+            }
+            State::Turning(turn) => {
+                turn.min_speed = turn.min_speed.min(speed);
+                turn.distance_m += travelled_m;
+                if turn_rate < STEADY_DEG_S {
+                    turn.steady_ms += dt_ms;
+                } else {
+                    turn.steady_ms = 0;
+                }
+                let elapsed_ms = now_ms.wrapping_sub(turn.start_ms);
+                if turn.steady_ms >= STEADY_MS {
+                    let turn_deg = heading_delta(turn.entry_course, course);
+                    if turn_deg.abs() >= MANOEUVRE_DEG {
+                        turn.turn_deg = Some(turn_deg);
+                        self.state = State::Recovering(*turn);
+                    } else {
+                        self.state = State::Steady(Some((course, speed)));
+                    }
+                } else if elapsed_ms > MAX_TURN_MS {
+                    self.state = State::Steady(Some((course, speed)));
+                }
+                None
+            }
+            State::Recovering(turn) => {
+                turn.min_speed = turn.min_speed.min(speed);
+                turn.distance_m += travelled_m;
+                let elapsed_ms = now_ms.wrapping_sub(turn.start_ms);
+                let recovered = speed >= turn.entry_speed * RECOVERED_SPEED;
+                if !recovered && elapsed_ms < MAX_RECOVER_MS {
+                    return None;
+                }
+                let turn = *turn;
+                self.state = State::Steady(Some((course, speed)));
+                let expected_m =
+                    metres_per_s_from_knots(turn.entry_speed) * elapsed_ms as f32 / 1000.0;
+                Some(Manoeuvre {
+                    kind: if turn.min_speed < turn.entry_speed * TACK_SPEED {
+                        Kind::Tack
+                    } else {
+                        Kind::Gybe
+                    },
+                    turn_deg: turn.turn_deg.unwrap_or(0f32),
+                    speed_loss: turn.entry_speed - turn.min_speed,
+                    recover_ms: if recovered { Some(elapsed_ms) } else { None },
+                    distance_lost_m: expected_m - turn.distance_m,
+                })
+            }
+        }
+    }
+}
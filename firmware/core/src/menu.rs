@@ -0,0 +1,183 @@
+//! The settings menu, driven by the single button.
+//!
+//! A long press on the pages opens the menu. Short presses move down the
+//! items, holding the button keeps moving down, a double press moves back
+//! up, and a long press selects one. A selected setting is changed with
+//! short presses, or held to keep changing, and a long press confirms it.
+//! Long pressing "exit" leaves the menu.
+
+use core::fmt::{self, Write};
+
+pub use crate::debouncer::Press;
+use crate::settings::{self, Settings};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Item {
+    Units,
+    Smoothing,
+    Average,
+    Screen,
+    Invert,
+    Rate,
+    Model,
+    SpeedAcc,
+    PosAcc,
+    MinSats,
+    ResetTrip,
+    Exit,
+}
+
+impl Item {
+    pub const ALL: [Item; 12] = [
+        Item::Units,
+        Item::Smoothing,
+        Item::Average,
+        Item::Screen,
+        Item::Invert,
+        Item::Rate,
+        Item::Model,
+        Item::SpeedAcc,
+        Item::PosAcc,
+        Item::MinSats,
+        Item::ResetTrip,
+        Item::Exit,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Item::Units => "units",
+            Item::Smoothing => "smoothing",
+            Item::Average => "average",
+            Item::Screen => "screen",
+            Item::Invert => "invert",
+            Item::Rate => "gps rate",
+            Item::Model => "gps model",
+            Item::SpeedAcc => "speed acc",
+            Item::PosAcc => "pos acc",
+            Item::MinSats => "min sats",
+            Item::ResetTrip => "reset trip",
+            Item::Exit => "exit",
+        }
+    }
+}
+
+/// What the rest of the system needs to do in response to a press
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    /// The menu has closed, with these settings
+    Exit(Settings),
+    /// Clear the maximum speeds, rankings and distance
+    ResetTrip,
+}
+
+pub struct Menu {
+    settings: Settings,
+    cursor: usize,
+    editing: bool,
+    trip_reset: bool,
+}
+
+impl Menu {
+    pub fn new(settings: Settings) -> Self {
+        Menu {
+            settings,
+            cursor: 0,
+            editing: false,
+            trip_reset: false,
+        }
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn selected(&self) -> Item {
+        Item::ALL[self.cursor]
+    }
+
+    /// Whether the selected item's value is being changed
+    pub fn editing(&self) -> bool {
+        self.editing
+    }
+
+    pub fn press(&mut self, press: Press) -> Option<Action> {
+        match (self.editing, press) {
+            (false, Press::Short) | (false, Press::Repeat) => {
+                self.cursor = (self.cursor + 1) % Item::ALL.len();
+                None
+            }
+            (false, Press::Double) => {
+                self.cursor = (self.cursor + Item::ALL.len() - 1) % Item::ALL.len();
+                None
+            }
+            (false, Press::Long) => match self.selected() {
+                Item::ResetTrip => {
+                    self.trip_reset = true;
+                    Some(Action::ResetTrip)
+                }
+                Item::Exit => Some(Action::Exit(self.settings)),
+                _ => {
+                    self.editing = true;
+                    None
+                }
+            },
+            (true, Press::Short) | (true, Press::Repeat) => {
+                self.step(self.selected());
+                None
+            }
+            // Two presses step twice
+            (true, Press::Double) => {
+                self.step(self.selected());
+                self.step(self.selected());
+                None
+            }
+            (true, Press::Long) => {
+                self.editing = false;
+                None
+            }
+        }
+    }
+
+    fn step(&mut self, item: Item) {
+        let s = &mut self.settings;
+        match item {
+            Item::Units => s.units = s.units.next(),
+            Item::Smoothing => {
+                s.smoothing_s = settings::next_choice(&settings::SMOOTHING_S, s.smoothing_s)
+            }
+            Item::Average => {
+                s.avg_window_s = settings::next_choice(&settings::AVG_WINDOW_S, s.avg_window_s)
+            }
+            Item::Screen => s.page = s.page.next(),
+            Item::Invert => s.invert = !s.invert,
+            Item::Rate => s.rate_hz = settings::next_choice(&settings::RATE_HZ, s.rate_hz),
+            Item::Model => s.gps_model = s.gps_model.next(),
+            Item::SpeedAcc => {
+                s.speed_acc_cm_s =
+                    settings::next_choice(&settings::SPEED_ACC_CM_S, s.speed_acc_cm_s)
+            }
+            Item::PosAcc => s.pos_acc_m = settings::next_choice(&settings::POS_ACC_M, s.pos_acc_m),
+            Item::MinSats => s.min_sats = settings::next_choice(&settings::MIN_SATS, s.min_sats),
+            Item::ResetTrip | Item::Exit => (),
+        }
+    }
+
+    /// Write the current value of `item`
+    pub fn write_value<W: Write>(&self, w: &mut W, item: Item) -> fmt::Result {
+        let s = &self.settings;
+        match item {
+            Item::Units => w.write_str(s.units.label()),
+            Item::Smoothing => write!(w, "{}s", s.smoothing_s),
+            Item::Average => write!(w, "{}s", s.avg_window_s),
+            Item::Screen => w.write_str(s.page.name()),
+            Item::Invert => w.write_str(if s.invert { "on" } else { "off" }),
+            Item::Rate => write!(w, "{}Hz", s.rate_hz),
+            Item::Model => w.write_str(s.gps_model.label()),
+            Item::SpeedAcc => write!(w, "{}cm/s", s.speed_acc_cm_s),
+            Item::PosAcc => write!(w, "{}m", s.pos_acc_m),
+            Item::MinSats => write!(w, "{}", s.min_sats),
+            Item::ResetTrip => w.write_str(if self.trip_reset { "done" } else { "" }),
+            Item::Exit => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,403 @@
+//! Speedsailing ranking metrics, as used by GPS-Speedsurfing and the GPS
+//! Team Challenge, computed incrementally from each fix:
+//!
+//! * 2 second peak: the best average speed over 2 seconds
+//! * 5x10 seconds: the average of the five best non overlapping 10 second runs
+//! * nautical mile: the best average speed over 1852m
+//! * 1 hour: the best average speed over an hour
+//! * alpha 500: the best average speed over a 500m run that ends within
+//!   50m of where it started, ie one including a gybe
+//!
+//! Averages are of the receiver's doppler speeds, weighted by time. The
+//! windows only cover continuous runs: losing the fix starts them again.
+
+use crate::gps::LocalProjection;
+
+// Longer than this between fixes and a run is broken
+const MAX_FIX_GAP_MS: u32 = 2000;
+
+// The GPS time of week wraps around each week
+pub(crate) const MS_PER_WEEK: u32 = 7 * 24 * 3600 * 1000;
+
+const NAUTICAL_MILE_MM: u32 = 1_852_000;
+const ALPHA_DISTANCE_MM: u32 = 500_000;
+const ALPHA_RADIUS_M: f32 = 50.0;
+
+const TEN_SECOND_RUNS: usize = 5;
+
+/// The best speeds so far, in knots.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct Rankings {
+    pub peak_2s: Option<f32>,
+    pub avg_5x10s: Option<f32>,
+    pub nautical_mile: Option<f32>,
+    pub one_hour: Option<f32>,
+    pub alpha_500: Option<f32>,
+}
+
+pub struct Metrics {
+    // Time elapsed while the fix was held, and the time of week of the
+    // previous fix
+    now_ms: u32,
+    last_itow: Option<u32>,
+    // Distance travelled while the fix was held
+    path_mm: u32,
+
+    window_2s: Window<32>,
+    window_10s: Window<128>,
+    window_1h: Window<400>,
+    window_nm: Window<1024>,
+    runs_10s: [Option<Run>; TEN_SECOND_RUNS],
+    alpha: AlphaTrack<256>,
+
+    rankings: Rankings,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics {
+            now_ms: 0,
+            last_itow: None,
+            path_mm: 0,
+            window_2s: Window::new(Span::Time(2_000), 100),
+            window_10s: Window::new(Span::Time(10_000), 100),
+            window_1h: Window::new(Span::Time(3_600_000), 10_000),
+            window_nm: Window::new(Span::Distance(NAUTICAL_MILE_MM), 1_000),
+            runs_10s: [None; TEN_SECOND_RUNS],
+            alpha: AlphaTrack::new(),
+            rankings: Rankings::default(),
+        }
+    }
+
+    pub fn rankings(&self) -> Rankings {
+        self.rankings
+    }
+
+    /// Discard the results, as well as any runs in progress
+    pub fn reset(&mut self) {
+        *self = Metrics::new();
+    }
+
+    /// Break the current runs, eg when the fix is lost. The results so far
+    /// are kept.
+    pub fn interrupt(&mut self) {
+        self.last_itow = None;
+        self.window_2s.clear();
+        self.window_10s.clear();
+        self.window_1h.clear();
+        self.window_nm.clear();
+        self.alpha.clear();
+    }
+
+    /// Add a fix: the GPS time of week, the ground speed in mm/s, and
+    /// the raw position
+    pub fn add(&mut self, itow_ms: u32, speed_mm_s: u32, lat_raw: i32, lon_raw: i32) {
+        let dt_ms = match self.last_itow {
+            Some(last) if itow_ms >= last => itow_ms - last,
+            Some(last) => itow_ms + MS_PER_WEEK - last,
+            None => 0,
+        };
+        if dt_ms == 0 || dt_ms > MAX_FIX_GAP_MS {
+            // The first fix of a new run
+            self.interrupt();
+            self.last_itow = Some(itow_ms);
+            self.alpha.add(self.now_ms, self.path_mm, lat_raw, lon_raw);
+            return;
+        }
+        self.last_itow = Some(itow_ms);
+
+        let start_ms = self.now_ms;
+        self.now_ms += dt_ms;
+        let now_ms = self.now_ms;
+        let dist_mm = (speed_mm_s as u64 * dt_ms as u64 / 1000) as u32;
+        self.path_mm += dist_mm;
+
+        self.window_2s.add(start_ms, now_ms, dist_mm);
+        self.window_10s.add(start_ms, now_ms, dist_mm);
+        self.window_1h.add(start_ms, now_ms, dist_mm);
+        self.window_nm.add(start_ms, now_ms, dist_mm);
+        self.alpha.add(now_ms, self.path_mm, lat_raw, lon_raw);
+
+        update_best(&mut self.rankings.peak_2s, self.window_2s.speed(now_ms));
+        update_best(&mut self.rankings.one_hour, self.window_1h.speed(now_ms));
+        update_best(
+            &mut self.rankings.nautical_mile,
+            self.window_nm.speed(now_ms),
+        );
+        update_best(&mut self.rankings.alpha_500, self.alpha.speed());
+
+        if let Some(speed) = self.window_10s.speed(now_ms) {
+            let run = Run {
+                speed,
+                start_ms: self.window_10s.start_ms,
+                end_ms: now_ms,
+            };
+            if offer_run(&mut self.runs_10s, run) {
+                self.rankings.avg_5x10s = average_runs(&self.runs_10s);
+            }
+        }
+    }
+}
+
+fn update_best(best: &mut Option<f32>, speed: Option<f32>) {
+    if let Some(speed) = speed {
+        match best {
+            Some(b) if *b >= speed => {}
+            _ => *best = Some(speed),
+        }
+    }
+}
+
+fn knots_from_mm_s(mm_s: f32) -> f32 {
+    mm_s * 1e-3 * 1.943844
+}
+
+#[derive(Clone, Copy)]
+enum Span {
+    Time(u32),
+    Distance(u32),
+}
+
+/// The shortest stretch of recent fixes that covers a time or distance,
+/// kept as the distance travelled in each of a ring of time buckets.
+/// Fixes that end in the same bucket are merged, so that long windows
+/// fit in a modest buffer.
+struct Window<const N: usize> {
+    span: Span,
+    bucket_ms: u32,
+    // End time and distance travelled for each bucket, oldest at head
+    ends_ms: [u32; N],
+    dists_mm: [u32; N],
+    head: usize,
+    len: usize,
+    sum_mm: u32,
+    // Start of the oldest bucket
+    start_ms: u32,
+}
+
+impl<const N: usize> Window<N> {
+    fn new(span: Span, bucket_ms: u32) -> Self {
+        Window {
+            span,
+            bucket_ms,
+            ends_ms: [0; N],
+            dists_mm: [0; N],
+            head: 0,
+            len: 0,
+            sum_mm: 0,
+            start_ms: 0,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+        self.sum_mm = 0;
+    }
+
+    fn add(&mut self, start_ms: u32, end_ms: u32, dist_mm: u32) {
+        if self.len == 0 {
+            self.start_ms = start_ms;
+        }
+
+        let last = (self.head + self.len + N - 1) % N;
+        if self.len > 0 && self.ends_ms[last] / self.bucket_ms == end_ms / self.bucket_ms {
+            self.ends_ms[last] = end_ms;
+            self.dists_mm[last] += dist_mm;
+        } else {
+            if self.len == N {
+                // Out of room, so the window may not cover its span
+                self.drop_oldest();
+            }
+            let i = (self.head + self.len) % N;
+            self.ends_ms[i] = end_ms;
+            self.dists_mm[i] = dist_mm;
+            self.len += 1;
+        }
+        self.sum_mm += dist_mm;
+
+        // Drop old buckets that aren't needed to cover the span
+        while self.len > 1
+            && self.covers(
+                self.ends_ms[self.head],
+                end_ms,
+                self.sum_mm - self.dists_mm[self.head],
+            )
+        {
+            self.drop_oldest();
+        }
+    }
+
+    fn drop_oldest(&mut self) {
+        self.start_ms = self.ends_ms[self.head];
+        self.sum_mm -= self.dists_mm[self.head];
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+    }
+
+    fn covers(&self, start_ms: u32, end_ms: u32, sum_mm: u32) -> bool {
+        match self.span {
+            Span::Time(ms) => end_ms - start_ms >= ms,
+            Span::Distance(mm) => sum_mm >= mm,
+        }
+    }
+
+    /// The average speed in knots over the window, once it covers its span
+    fn speed(&self, now_ms: u32) -> Option<f32> {
+        if self.len > 0 && self.covers(self.start_ms, now_ms, self.sum_mm) {
+            let mm_s = self.sum_mm as f32 * 1000.0 / (now_ms - self.start_ms) as f32;
+            Some(knots_from_mm_s(mm_s))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Run {
+    speed: f32,
+    start_ms: u32,
+    end_ms: u32,
+}
+
+/// Offer a run for the best non overlapping runs. Runs arrive in order of
+/// their end times, so a new run can only overlap the latest ones. If it
+/// beats all that it overlaps, it replaces them. Returns true if the
+/// runs changed.
+fn offer_run(runs: &mut [Option<Run>], run: Run) -> bool {
+    let mut overlapped = false;
+    for r in runs.iter().flatten() {
+        if r.end_ms > run.start_ms {
+            if r.speed >= run.speed {
+                return false;
+            }
+            overlapped = true;
+        }
+    }
+    if overlapped {
+        for r in runs.iter_mut() {
+            if matches!(r, Some(o) if o.end_ms > run.start_ms) {
+                *r = None;
+            }
+        }
+    }
+
+    // Take an empty slot, or else the slowest run if this one is faster
+    let mut slot = 0;
+    let mut slot_speed = f32::INFINITY;
+    for (i, r) in runs.iter().enumerate() {
+        let speed = r.map_or(f32::NEG_INFINITY, |r| r.speed);
+        if speed < slot_speed {
+            slot = i;
+            slot_speed = speed;
+        }
+    }
+    if slot_speed >= run.speed {
+        return false;
+    }
+    runs[slot] = Some(run);
+    true
+}
+
+/// The average of the runs, once there is a full set
+fn average_runs(runs: &[Option<Run>]) -> Option<f32> {
+    let mut sum = 0.0;
+    for r in runs {
+        sum += r.as_ref()?.speed;
+    }
+    Some(sum / runs.len() as f32)
+}
+
+#[derive(Clone, Copy)]
+struct AlphaPoint {
+    time_ms: u32,
+    path_mm: u32,
+    east_m: f32,
+    north_m: f32,
+}
+
+/// Recent positions, at most one a second, for finding alpha runs
+struct AlphaTrack<const N: usize> {
+    projection: Option<LocalProjection>,
+    points: [AlphaPoint; N],
+    head: usize,
+    len: usize,
+    // The latest fix, which may not have made it into points
+    latest: AlphaPoint,
+}
+
+impl<const N: usize> AlphaTrack<N> {
+    fn new() -> Self {
+        let zero = AlphaPoint {
+            time_ms: 0,
+            path_mm: 0,
+            east_m: 0.0,
+            north_m: 0.0,
+        };
+        AlphaTrack {
+            projection: None,
+            points: [zero; N],
+            head: 0,
+            len: 0,
+            latest: zero,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+    }
+
+    fn add(&mut self, time_ms: u32, path_mm: u32, lat_raw: i32, lon_raw: i32) {
+        let projection = *self
+            .projection
+            .get_or_insert_with(|| LocalProjection::new(lat_raw, lon_raw));
+        let (east_m, north_m) = projection.project(lat_raw, lon_raw);
+        self.latest = AlphaPoint {
+            time_ms,
+            path_mm,
+            east_m,
+            north_m,
+        };
+
+        let newest = (self.head + self.len + N - 1) % N;
+        if self.len == 0 || time_ms - self.points[newest].time_ms >= 1000 {
+            if self.len == N {
+                self.head = (self.head + 1) % N;
+                self.len -= 1;
+            }
+            self.points[(self.head + self.len) % N] = self.latest;
+            self.len += 1;
+        }
+
+        // Only the most recent point at least the alpha distance back is
+        // needed
+        while self.len > 1
+            && path_mm - self.points[(self.head + 1) % N].path_mm >= ALPHA_DISTANCE_MM
+        {
+            self.head = (self.head + 1) % N;
+            self.len -= 1;
+        }
+    }
+
+    /// The speed in knots of the run ending at the latest fix, if it is
+    /// an alpha
+    fn speed(&self) -> Option<f32> {
+        if self.len == 0 {
+            return None;
+        }
+        let start = &self.points[self.head];
+        let end = &self.latest;
+        let path_mm = end.path_mm - start.path_mm;
+        if path_mm < ALPHA_DISTANCE_MM {
+            return None;
+        }
+        let de = end.east_m - start.east_m;
+        let dn = end.north_m - start.north_m;
+        if de * de + dn * dn > ALPHA_RADIUS_M * ALPHA_RADIUS_M {
+            return None;
+        }
+        let mm_s = path_mm as f32 * 1000.0 / (end.time_ms - start.time_ms) as f32;
+        Some(knots_from_mm_s(mm_s))
+    }
+}
@@ -0,0 +1,2411 @@
+use crate::anchor::AnchorWatch;
+use crate::debouncer::Press;
+use crate::gps::{rotate, GpsData, GpsTime, LocalProjection};
+use crate::gps_setup::GpsStatus;
+use crate::heading::{HeadingReference, Shift};
+use crate::history::{axis_scale, zoom_m, SpeedHistory, TrackHistory};
+use crate::input::Input;
+use crate::layout::{DPixelColor, DisplayField, Layout};
+use crate::manoeuvre::{Kind, ManoeuvreStats};
+use crate::menu::{Action, Item, Menu};
+use crate::metrics::Rankings;
+use crate::settings::Settings;
+use crate::start_line::{LineStatus, StartLine};
+use crate::start_timer::{next_signal, RaceClock, StartTimer, SEQUENCE_MS};
+use crate::u8writer::U8Writer;
+use crate::units::SpeedUnit;
+use crate::waypoints::{Leg, Waypoints};
+use crate::write_field;
+
+use core::fmt::Write;
+use embedded_graphics::{
+    prelude::*,
+    primitives::{Circle, Line, PrimitiveStyle, Rectangle, Triangle},
+};
+use micromath::F32Ext;
+
+// How fast the display flashes while the anchor alarm is on
+const ALARM_FLASH_MS: u64 = 500;
+
+pub enum Update<'a> {
+    Gps(&'a GpsData),
+    Vbat(u16),
+}
+
+pub struct Screens {
+    layout: Layout,
+    screens: AnyScreen,
+    // Shown over the page while open
+    menu: Option<MenuScreen>,
+    // The start countdown keeps running while other pages are shown
+    clock: RaceClock,
+    start_timer: StartTimer,
+    start_line: StartLine,
+    waypoints: Waypoints,
+    // The waypoint being navigated to
+    mark: usize,
+    anchor: AnchorWatch,
+    // The track page is turned to the course, rather than north up
+    course_up: bool,
+    // For reading wind shifts on the heading page
+    heading_ref: HeadingReference,
+    // The latest updates, to show a new page straight away, and the fix
+    // for pinging the start line and setting the anchor watch. The speed
+    // history and track aren't kept, see `without_history`.
+    gps: Option<GpsData>,
+    vbat_mv: Option<u16>,
+    // The inverted setting, which the anchor alarm flips while flashing
+    inverted: bool,
+    flashing: bool,
+}
+impl Screens {
+    pub fn new() -> Self {
+        Screens {
+            layout: Layout::new(),
+            screens: AnyScreen::Speed(SpeedScreen::new()),
+            menu: None,
+            clock: RaceClock::new(),
+            start_timer: StartTimer::new(),
+            start_line: StartLine::new(),
+            waypoints: Waypoints::new(),
+            mark: 0,
+            anchor: AnchorWatch::new(),
+            course_up: false,
+            heading_ref: HeadingReference::new(),
+            gps: None,
+            vbat_mv: None,
+            inverted: false,
+            flashing: false,
+            //screens: AnyScreen::SpeedDetails(SpeedDetailsScreen::new()),
+        }
+    }
+
+    pub fn page(&self) -> Page {
+        match &self.screens {
+            AnyScreen::Speed(_) => Page::Speed,
+            AnyScreen::Stats(_) => Page::Stats,
+            AnyScreen::Ranking(_) => Page::Ranking,
+            AnyScreen::Cog(_) => Page::Cog,
+            AnyScreen::Misc(_) => Page::Misc,
+            AnyScreen::Start(_) => Page::Start,
+            AnyScreen::Line(_) => Page::Line,
+            AnyScreen::Nav(_) => Page::Nav,
+            AnyScreen::Anchor(_) => Page::Anchor,
+            AnyScreen::Graph(_) => Page::Graph,
+            AnyScreen::Track(_) => Page::Track,
+            AnyScreen::Heading(_) => Page::Heading,
+            AnyScreen::Manoeuvre(_) => Page::Manoeuvre,
+        }
+    }
+
+    pub fn next_page<D>(&mut self, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        self.set_page(self.page().next(), display)
+    }
+
+    pub fn set_page<D>(&mut self, page: Page, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        self.screens = match page {
+            Page::Speed => AnyScreen::Speed(SpeedScreen::new()),
+            Page::Stats => AnyScreen::Stats(StatsScreen::new()),
+            Page::Ranking => AnyScreen::Ranking(RankingScreen::new()),
+            Page::Cog => AnyScreen::Cog(CogScreen::new()),
+            Page::Misc => AnyScreen::Misc(MiscScreen::new()),
+            Page::Start => AnyScreen::Start(StartScreen::new()),
+            Page::Line => AnyScreen::Line(LineScreen::new()),
+            Page::Nav => AnyScreen::Nav(NavScreen::new()),
+            Page::Anchor => AnyScreen::Anchor(AnchorScreen::new()),
+            Page::Graph => AnyScreen::Graph(GraphScreen::new()),
+            Page::Track => AnyScreen::Track(TrackScreen::new()),
+            Page::Heading => AnyScreen::Heading(HeadingScreen::new()),
+            Page::Manoeuvre => AnyScreen::Manoeuvre(ManoeuvreScreen::new()),
+        };
+        self.update_start();
+        self.update_line_ends();
+        self.update_mark();
+        self.update_track_mode();
+        self.update_heading_ref();
+        if let Some(gps) = self.gps.take() {
+            self.update_screen(&Update::Gps(&gps));
+            self.gps = Some(gps);
+        }
+        if let Some(mv) = self.vbat_mv {
+            self.update_screen(&Update::Vbat(mv));
+        }
+        self.layout.clear(display)?;
+        self.render(display)
+    }
+
+    /// Redraw the current page in inverted colours, or not
+    pub fn set_inverted<D>(&mut self, inverted: bool, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        self.inverted = inverted;
+        self.layout = layout(inverted != self.flashing);
+        self.set_page(self.page(), display)
+    }
+
+    /// Handle an input: button presses, or a page chosen with the rotary
+    /// switch. Logging isn't a matter for the screens, and is ignored.
+    pub fn input<D>(
+        &mut self,
+        input: Input,
+        settings: &Settings,
+        display: &mut D,
+    ) -> Result<Option<Action>, D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        match input {
+            Input::Button(press) => self.press(press, settings, display),
+            // The menu keeps the display until it's closed
+            Input::SelectPage(page) if self.menu.is_none() && page != self.page() => {
+                self.set_page(page, display)?;
+                Ok(None)
+            }
+            Input::SelectPage(_) | Input::Logging(_) => Ok(None),
+        }
+    }
+
+    /// Handle a button press: short presses turn the page, double presses
+    /// turn it back, and a long press opens the settings menu, which then
+    /// takes the presses. On the start page, a double press starts or syncs
+    /// the countdown instead. On the line page, a double press pings the
+    /// pin end of the line and a long press the committee boat. On the nav
+    /// page, a double press moves on to the next waypoint. On the anchor
+    /// page, a double press sets or clears the anchor watch, and a long
+    /// press changes its radius. On the track page, a double press turns
+    /// it between north up and course up. On the heading page, a double
+    /// press takes the course as the reference heading, and a long press
+    /// changes tack.
+    pub fn press<D>(
+        &mut self,
+        press: Press,
+        settings: &Settings,
+        display: &mut D,
+    ) -> Result<Option<Action>, D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        let action = match &mut self.menu {
+            Some(menu) => menu.press(press),
+            None => {
+                match press {
+                    Press::Short => self.next_page(display)?,
+                    Press::Double if self.page() == Page::Start => {
+                        self.start_timer.start_or_sync(self.clock.now_ms());
+                        self.update_start();
+                        self.render(display)?;
+                    }
+                    Press::Double | Press::Long if self.page() == Page::Line => {
+                        if let Some(position_raw) = self.position_raw() {
+                            if press == Press::Double {
+                                self.start_line.set_pin(position_raw);
+                            } else {
+                                self.start_line.set_boat(position_raw);
+                            }
+                        }
+                        self.update_line_ends();
+                        self.render(display)?;
+                    }
+                    Press::Double if self.page() == Page::Nav => {
+                        if !self.waypoints.is_empty() {
+                            self.mark = (self.mark + 1) % self.waypoints.len();
+                        }
+                        self.update_mark();
+                        self.render(display)?;
+                    }
+                    Press::Double if self.page() == Page::Heading => {
+                        if let Some(course) = self.gps.as_ref().and_then(|gps| gps.course) {
+                            self.heading_ref.set(course);
+                        }
+                        self.update_heading_ref();
+                        self.render(display)?;
+                    }
+                    Press::Long if self.page() == Page::Heading => {
+                        self.heading_ref.change_tack();
+                        self.update_heading_ref();
+                        self.render(display)?;
+                    }
+                    Press::Double if self.page() == Page::Track => {
+                        self.course_up = !self.course_up;
+                        self.update_track_mode();
+                        self.render(display)?;
+                    }
+                    Press::Double if self.page() == Page::Anchor => {
+                        if self.anchor.is_set() {
+                            self.anchor.clear();
+                        } else if let Some(position_raw) = self.position_raw() {
+                            self.anchor.set(position_raw);
+                        }
+                        self.update_anchor();
+                        self.render(display)?;
+                    }
+                    Press::Long if self.page() == Page::Anchor => {
+                        self.anchor.next_radius();
+                        self.update_anchor();
+                        self.render(display)?;
+                    }
+                    Press::Double => self.set_page(self.page().previous(), display)?,
+                    Press::Long => {
+                        self.menu = Some(MenuScreen::new(Menu::new(*settings)));
+                        self.layout.clear(display)?;
+                        self.render(display)?;
+                    }
+                    Press::Repeat => (),
+                }
+                return Ok(None);
+            }
+        };
+        if let Some(Action::Exit(new)) = action {
+            self.menu = None;
+            // Show the newly chosen start page, otherwise go back to the
+            // page the menu was opened from
+            let page = if new.page != settings.page {
+                new.page
+            } else {
+                self.page()
+            };
+            self.inverted = new.invert;
+            self.layout = layout(new.invert != self.flashing);
+            self.set_page(page, display)?;
+        } else {
+            self.render(display)?;
+        }
+        Ok(action)
+    }
+
+    pub fn render<D>(&mut self, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        if let Some(menu) = &mut self.menu {
+            return menu.render(&self.layout, display);
+        }
+        match &mut self.screens {
+            AnyScreen::Speed(s) => s.render(&self.layout, display),
+            AnyScreen::Stats(s) => s.render(&self.layout, display),
+            AnyScreen::Ranking(s) => s.render(&self.layout, display),
+            AnyScreen::Cog(s) => s.render(&self.layout, display),
+            AnyScreen::Misc(s) => s.render(&self.layout, display),
+            AnyScreen::Start(s) => s.render(&self.layout, display),
+            AnyScreen::Line(s) => s.render(&self.layout, display),
+            AnyScreen::Nav(s) => s.render(&self.layout, display),
+            AnyScreen::Anchor(s) => s.render(&self.layout, display),
+            AnyScreen::Graph(s) => s.render(&self.layout, display),
+            AnyScreen::Track(s) => s.render(&self.layout, display),
+            AnyScreen::Heading(s) => s.render(&self.layout, display),
+            AnyScreen::Manoeuvre(s) => s.render(&self.layout, display),
+        }
+    }
+
+    /// Advance the start countdown to the monotonic time `mono_ms`, and
+    /// show the speed at the gun. Flashes the display while the anchor
+    /// alarm is on.
+    pub fn tick<D>(&mut self, mono_ms: u64, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        self.clock.tick(mono_ms);
+        let gun = self.start_timer.gun(self.clock.now_ms());
+        if gun && self.page() == Page::Start && self.menu.is_none() {
+            return self.set_page(Page::Speed, display);
+        }
+        self.update_start();
+
+        let flash = self.anchor.is_alarm() && mono_ms / ALARM_FLASH_MS % 2 == 1;
+        if flash != self.flashing && self.menu.is_none() {
+            self.flashing = flash;
+            self.layout = layout(self.inverted != flash);
+            return self.set_page(self.page(), display);
+        }
+        Ok(())
+    }
+
+    /// The boat has moved outside the anchor watch radius
+    pub fn alarm(&self) -> bool {
+        self.anchor.is_alarm()
+    }
+
+    /// The reference the heading page reads wind shifts against
+    pub fn heading_reference(&self) -> HeadingReference {
+        self.heading_ref
+    }
+
+    fn position_raw(&self) -> Option<(i32, i32)> {
+        self.gps.as_ref().and_then(|gps| gps.position_raw)
+    }
+
+    fn update_start(&mut self) {
+        if let AnyScreen::Start(s) = &mut self.screens {
+            s.update_countdown(self.start_timer.remaining_ms(self.clock.now_ms()));
+        }
+    }
+
+    pub fn update_gps(&mut self, gps: &GpsData) {
+        self.update(&Update::Gps(gps));
+    }
+
+    pub fn update_vbat(&mut self, mv: u16) {
+        self.update(&Update::Vbat(mv));
+    }
+
+    pub fn update(&mut self, update: &Update) {
+        match update {
+            Update::Gps(gps) => {
+                if let Some(time) = &gps.time {
+                    self.clock.update_gps(time);
+                }
+                self.anchor.update(gps.position_raw);
+                self.gps = Some(without_history(gps));
+            }
+            Update::Vbat(mv) => self.vbat_mv = Some(*mv),
+        }
+        self.update_screen(update);
+    }
+
+    fn update_screen(&mut self, update: &Update) {
+        match &mut self.screens {
+            AnyScreen::Speed(s) => s.update(update),
+            AnyScreen::Stats(s) => s.update(update),
+            AnyScreen::Ranking(s) => s.update(update),
+            AnyScreen::Cog(s) => s.update(update),
+            AnyScreen::Misc(s) => s.update(update),
+            AnyScreen::Start(s) => s.update(update),
+            AnyScreen::Line(s) => s.update(update),
+            AnyScreen::Nav(s) => s.update(update),
+            AnyScreen::Anchor(s) => s.update(update),
+            AnyScreen::Graph(s) => s.update(update),
+            AnyScreen::Track(s) => s.update(update),
+            AnyScreen::Heading(s) => s.update(update),
+            AnyScreen::Manoeuvre(s) => s.update(update),
+        }
+        if let (AnyScreen::Line(s), Update::Gps(gps)) = (&mut self.screens, update) {
+            let status = gps
+                .position_raw
+                .and_then(|p| self.start_line.status(p, gps.speed, gps.course));
+            s.update_status(status, self.start_timer.is_running());
+        }
+        if let (AnyScreen::Nav(s), Update::Gps(gps)) = (&mut self.screens, update) {
+            let mark = self.waypoints.as_slice().get(self.mark);
+            let leg = gps
+                .position_raw
+                .zip(mark)
+                .map(|(p, mark)| mark.navigate(p, gps.speed, gps.course));
+            s.update_leg(leg, gps.units);
+        }
+        self.update_anchor();
+    }
+
+    fn update_anchor(&mut self) {
+        if let AnyScreen::Anchor(s) = &mut self.screens {
+            s.update_watch(&self.anchor);
+        }
+    }
+
+    /// Navigate to `waypoints`, starting from the first
+    pub fn set_waypoints(&mut self, waypoints: Waypoints) {
+        self.waypoints = waypoints;
+        self.mark = 0;
+        self.update_mark();
+    }
+
+    fn update_mark(&mut self) {
+        if let AnyScreen::Nav(s) = &mut self.screens {
+            let mark = self.waypoints.as_slice().get(self.mark);
+            s.update_mark(mark.map(|w| w.name()), self.mark, self.waypoints.len());
+        }
+    }
+
+    fn update_track_mode(&mut self) {
+        if let AnyScreen::Track(s) = &mut self.screens {
+            s.set_course_up(self.course_up);
+        }
+    }
+
+    fn update_heading_ref(&mut self) {
+        if let AnyScreen::Heading(s) = &mut self.screens {
+            s.set_reference(self.heading_ref);
+        }
+    }
+
+    fn update_line_ends(&mut self) {
+        if let AnyScreen::Line(s) = &mut self.screens {
+            s.update_ends(self.start_line.has_pin(), self.start_line.has_boat());
+        }
+    }
+}
+
+/// The fields of `gps` that the pages draw, without the speed history and
+/// track, which would otherwise be copied at every fix. The graph and
+/// track pages fill in with the next fix instead.
+fn without_history(gps: &GpsData) -> GpsData {
+    GpsData {
+        sat_in_use: gps.sat_in_use,
+        course: gps.course,
+        speed: gps.speed,
+        max_speed: gps.max_speed,
+        avg_speed: gps.avg_speed,
+        max_avg_speed: gps.max_avg_speed,
+        avg_window_s: gps.avg_window_s,
+        distance_m: gps.distance_m,
+        hdop: gps.hdop,
+        latitude: gps.latitude,
+        longitude: gps.longitude,
+        position_raw: gps.position_raw,
+        time: gps.time.clone(),
+        rankings: gps.rankings,
+        history: SpeedHistory::new(gps.history.point_s()),
+        track: TrackHistory::new(0.0),
+        manoeuvres: gps.manoeuvres,
+        units: gps.units,
+        status: gps.status,
+        rejected_samples: gps.rejected_samples,
+    }
+}
+
+fn layout(inverted: bool) -> Layout {
+    if inverted {
+        Layout::new_inverted()
+    } else {
+        Layout::new()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Page {
+    Speed,
+    Stats,
+    Ranking,
+    Cog,
+    Misc,
+    Start,
+    Line,
+    Nav,
+    Anchor,
+    Graph,
+    Track,
+    Heading,
+    Manoeuvre,
+}
+
+impl Page {
+    // The index is stored in the settings, so new pages go on the end
+    pub const ALL: [Page; 13] = [
+        Page::Speed,
+        Page::Stats,
+        Page::Ranking,
+        Page::Cog,
+        Page::Misc,
+        Page::Start,
+        Page::Line,
+        Page::Nav,
+        Page::Anchor,
+        Page::Graph,
+        Page::Track,
+        Page::Heading,
+        Page::Manoeuvre,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Page::Speed => "speed",
+            Page::Stats => "stats",
+            Page::Ranking => "ranking",
+            Page::Cog => "cog",
+            Page::Misc => "misc",
+            Page::Start => "start",
+            Page::Line => "line",
+            Page::Nav => "nav",
+            Page::Anchor => "anchor",
+            Page::Graph => "graph",
+            Page::Track => "track",
+            Page::Heading => "heading",
+            Page::Manoeuvre => "manoeuvre",
+        }
+    }
+
+    pub fn next(&self) -> Page {
+        match self {
+            Page::Speed => Page::Stats,
+            Page::Stats => Page::Ranking,
+            Page::Ranking => Page::Cog,
+            Page::Cog => Page::Heading,
+            Page::Heading => Page::Manoeuvre,
+            Page::Manoeuvre => Page::Graph,
+            Page::Graph => Page::Track,
+            Page::Track => Page::Misc,
+            Page::Misc => Page::Start,
+            Page::Start => Page::Line,
+            Page::Line => Page::Nav,
+            Page::Nav => Page::Anchor,
+            Page::Anchor => Page::Speed,
+        }
+    }
+
+    pub fn previous(&self) -> Page {
+        let i = Page::ALL.iter().position(|p| p == self).unwrap();
+        Page::ALL[(i + Page::ALL.len() - 1) % Page::ALL.len()]
+    }
+
+    pub fn to_u8(&self) -> u8 {
+        Page::ALL.iter().position(|p| p == self).unwrap() as u8
+    }
+
+    pub fn from_u8(v: u8) -> Option<Page> {
+        Page::ALL.get(v as usize).copied()
+    }
+}
+
+enum AnyScreen {
+    Speed(SpeedScreen),
+    Stats(StatsScreen),
+    Ranking(RankingScreen),
+    Cog(CogScreen),
+    Misc(MiscScreen),
+    Start(StartScreen),
+    Line(LineScreen),
+    Nav(NavScreen),
+    Anchor(AnchorScreen),
+    Graph(GraphScreen),
+    Track(TrackScreen),
+    Heading(HeadingScreen),
+    Manoeuvre(ManoeuvreScreen),
+}
+
+pub struct StatusLine {
+    sats_field: DisplayField<8>,
+    sats_blink: bool,
+    bat_percent: Option<u32>,
+    label: DisplayField<4>,
+}
+
+impl StatusLine {
+    pub fn new(label: &str) -> Self {
+        StatusLine {
+            sats_field: DisplayField::new(),
+            sats_blink: false,
+            bat_percent: None,
+            label: DisplayField::from_str(label),
+        }
+    }
+}
+
+impl StatusLine {
+    pub fn render<D>(&mut self, layout: &Layout, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        let font = layout.font_18();
+        font.render_field(
+            display,
+            Point::new(font.char_width() * 2, font.char_height() / 2),
+            &mut self.sats_field,
+        )?;
+        if let Some(bat_percent) = self.bat_percent {
+            render_battery_top_centre(display, layout, bat_percent)?;
+        }
+        font.render_field(
+            display,
+            Point::new(font.char_width() * 29, font.char_height() / 2),
+            &mut self.label,
+        )?;
+        Result::Ok(())
+    }
+
+    pub fn update(&mut self, update: &Update) {
+        match update {
+            Update::Gps(gps) => self.update_gps(gps),
+            Update::Vbat(vbat) => self.update_vbat(*vbat),
+        }
+    }
+
+    pub fn set_label(&mut self, label: &str) {
+        write_field!(self.label, "{}", label).unwrap();
+    }
+
+    pub fn update_vbat(&mut self, mv: u16) {
+        self.bat_percent = Some(battery_percent(mv as u32));
+    }
+
+    pub fn update_gps(&mut self, gps: &GpsData) {
+        self.sats_blink = !self.sats_blink;
+        if gps.sat_in_use == 0 && self.sats_blink {
+            self.sats_field.clear();
+        } else if gps.status == GpsStatus::NotResponding {
+            write_field!(self.sats_field, "no GPS").unwrap();
+        } else {
+            write_field!(self.sats_field, "Sats: {}", gps.sat_in_use).unwrap();
+        }
+    }
+}
+
+pub struct SpeedScreen {
+    status_line: StatusLine,
+    speed_digits: [Updateable<u8>; 3],
+}
+
+impl SpeedScreen {
+    pub fn new() -> Self {
+        SpeedScreen {
+            status_line: StatusLine::new("kt"),
+            speed_digits: [Updateable::new(0), Updateable::new(0), Updateable::new(0)],
+        }
+    }
+
+    pub fn render<D>(&mut self, layout: &Layout, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        self.status_line.render(layout, display)?;
+        self.render_speed(layout, display, layout.font_18().char_point(0, 2))?;
+        Result::Ok(())
+    }
+
+    fn render_speed<D>(
+        &mut self,
+        layout: &Layout,
+        display: &mut D,
+        loc: Point,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        let font = layout.font_156();
+        let mut cursor = loc;
+        let nextc = Point::new(font.char_width(), 0);
+
+        if let Some(d) = self.speed_digits[0].updated() {
+            let mut c = char::from_digit(*d as u32, 10).unwrap();
+            c = if c == '0' { ' ' } else { c };
+            cursor = font.write_char(display, cursor, c)?;
+        } else {
+            cursor = cursor + nextc;
+        }
+        if let Some(d) = self.speed_digits[1].updated() {
+            let c = char::from_digit(*d as u32, 10).unwrap();
+            cursor = font.write_char(display, cursor, c)?;
+        } else {
+            cursor = cursor + nextc;
+        }
+        cursor = font.write_kerned_dp(display, cursor)?;
+        if let Some(d) = self.speed_digits[2].updated() {
+            let c = char::from_digit(*d as u32, 10).unwrap();
+            font.write_char(display, cursor, c)?;
+        }
+        Result::Ok(())
+    }
+
+    pub fn update(&mut self, update: &Update) {
+        self.status_line.update(update);
+        match update {
+            Update::Gps(gps) => self.update_gps(gps),
+            _ => (),
+        }
+    }
+
+    fn update_gps(&mut self, gps: &GpsData) {
+        self.status_line.set_label(gps.units.label());
+        let speed = (gps.units.from_knots(gps.speed) * 10.0).round() as u32;
+        self.speed_digits[0].set(((speed / 100) % 10) as u8);
+        self.speed_digits[1].set(((speed / 10) % 10) as u8);
+        self.speed_digits[2].set((speed % 10) as u8);
+    }
+}
+
+pub struct StatsScreen {
+    max_speed: Updateable<f32>,
+    max_avg_speed: Updateable<f32>,
+    distance: Updateable<f32>,
+    time: Updateable<Option<GpsTime>>,
+    // Tacks and gybes
+    manoeuvres: Updateable<(u16, u16)>,
+    // The units and average window, for the labels
+    labels: Updateable<(SpeedUnit, u8)>,
+}
+
+impl StatsScreen {
+    pub fn new() -> Self {
+        StatsScreen {
+            max_speed: Updateable::new(0.0),
+            max_avg_speed: Updateable::new(0.0),
+            distance: Updateable::new(0.0),
+            time: Updateable::new(None),
+            manoeuvres: Updateable::new((0, 0)),
+            labels: Updateable::new((SpeedUnit::Knots, 10)),
+        }
+    }
+
+    pub fn render<D>(&mut self, layout: &Layout, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        let font_18 = layout.font_18();
+        let (x0, x1, y0, y1) = (0, 205, 35, 155);
+        let tl = Point::new(x0, y0);
+        let tr = Point::new(x1, y0);
+        let bl = Point::new(x0, y1);
+        let br = Point::new(x1, y1);
+        let labeld = Point::new(10, -30);
+
+        Line::new(Point::new(0, 120), Point::new(400, 120))
+            .into_styled(layout.fg_fill_style)
+            .draw(display)?;
+        Line::new(Point::new(200, 0), Point::new(200, 240))
+            .into_styled(layout.fg_fill_style)
+            .draw(display)?;
+
+        if let Some((units, avg_window_s)) = self.labels.updated() {
+            let mut buf: [u8; 20] = [0; 20];
+            let mut w = U8Writer::new(&mut buf);
+            write!(w, "max {:4}", units.label()).unwrap();
+            font_18.write_str(display, tl + labeld, w.as_str())?;
+
+            let mut w = U8Writer::new(&mut buf);
+            write!(w, "max {:4} avg{}", units.label(), avg_window_s).unwrap();
+            font_18.write_str(display, tr + labeld, w.as_str())?;
+
+            let mut w = U8Writer::new(&mut buf);
+            write!(w, "dist {}", units.distance_label()).unwrap();
+            font_18.write_str(display, bl + labeld, w.as_str())?;
+        }
+        Self::render_f32_dd_d(layout, display, tl, &mut self.max_speed)?;
+        Self::render_f32_dd_d(layout, display, tr, &mut self.max_avg_speed)?;
+        Self::render_f32_dd_d(layout, display, bl, &mut self.distance)?;
+
+        font_18.write_str(display, br + labeld, "time")?;
+        Self::render_time(layout, display, br, &mut self.time)?;
+        if let Some(&(tacks, gybes)) = self.manoeuvres.updated() {
+            let mut buf: [u8; 20] = [0; 20];
+            let mut w = U8Writer::new(&mut buf);
+            write!(w, "tk {:<3} gy {:<3}", tacks, gybes).unwrap();
+            font_18.write_str(display, br + Point::new(10, 58), w.as_str())?;
+        }
+
+        Result::Ok(())
+    }
+
+    fn render_time<D>(
+        layout: &Layout,
+        display: &mut D,
+        loc: Point,
+        value: &mut Updateable<Option<GpsTime>>,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        if let Some(otime) = value.updated() {
+            let font = layout.font_24();
+            let mut buf: [u8; 20] = [0; 20];
+            let mut w = U8Writer::new(&mut buf);
+            if let Some(time) = otime {
+                write!(w, "{:02}:{:02}:{:02}", time.hour, time.min, time.sec).unwrap();
+            } else {
+                write!(w, "--:--:--").unwrap();
+            }
+            let dloc = Point::new(40, 20);
+            font.write_str(display, loc + dloc, w.as_str())?;
+        }
+        Ok(())
+    }
+
+    fn render_f32_dd_d<D>(
+        layout: &Layout,
+        display: &mut D,
+        loc: Point,
+        value: &mut Updateable<f32>,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        if let Some(value) = value.updated() {
+            let font = layout.font_78();
+            let mut buf: [u8; 8] = [0; 8];
+            let mut w = U8Writer::new(&mut buf);
+
+            let value = (value * 10.0).round() as u32;
+            w.write_number(2, ' ', value / 10).unwrap();
+            w.write_char('.').unwrap();
+            w.write_number(1, '0', value % 10).unwrap();
+            font.write_kerned_str(display, loc, w.as_str())?;
+        }
+        Result::Ok(())
+    }
+
+    pub fn update(&mut self, update: &Update) {
+        match update {
+            Update::Gps(gps) => {
+                self.labels.set((gps.units, gps.avg_window_s));
+                self.max_speed.set(gps.units.from_knots(gps.max_speed));
+                self.max_avg_speed
+                    .set(gps.units.from_knots(gps.max_avg_speed));
+                self.distance
+                    .set(gps.units.distance_from_metres(gps.distance_m));
+                self.time.set(gps.time.clone());
+                self.manoeuvres
+                    .set((gps.manoeuvres.tacks, gps.manoeuvres.gybes));
+            }
+            _ => (),
+        }
+    }
+}
+
+/// The speedsailing ranking results
+pub struct RankingScreen {
+    status_line: StatusLine,
+    rows: [DisplayField<12>; 5],
+}
+
+impl RankingScreen {
+    const LABELS: [&'static str; 5] = ["2s", "5x10", "NM", "1h", "a500"];
+
+    pub fn new() -> Self {
+        RankingScreen {
+            status_line: StatusLine::new("kt"),
+            rows: [
+                DisplayField::from_str(Self::LABELS[0]),
+                DisplayField::from_str(Self::LABELS[1]),
+                DisplayField::from_str(Self::LABELS[2]),
+                DisplayField::from_str(Self::LABELS[3]),
+                DisplayField::from_str(Self::LABELS[4]),
+            ],
+        }
+    }
+
+    pub fn render<D>(&mut self, layout: &Layout, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        self.status_line.render(layout, display)?;
+        let font = layout.font_24();
+
+        let mut cursor = Point::new(font.char_width() * 2, layout.font_18().char_height() * 2);
+        let down = Point::new(0, font.char_height());
+        for row in self.rows.iter_mut() {
+            font.render_field(display, cursor, row)?;
+            cursor = cursor + down;
+        }
+        Result::Ok(())
+    }
+
+    pub fn update(&mut self, update: &Update) {
+        self.status_line.update(update);
+        match update {
+            Update::Gps(gps) => {
+                self.status_line.set_label(gps.units.label());
+                self.update_rankings(&gps.rankings, gps.units);
+            }
+            _ => (),
+        }
+    }
+
+    fn update_rankings(&mut self, rankings: &Rankings, units: SpeedUnit) {
+        let values = [
+            rankings.peak_2s,
+            rankings.avg_5x10s,
+            rankings.nautical_mile,
+            rankings.one_hour,
+            rankings.alpha_500,
+        ];
+        for ((row, label), value) in self
+            .rows
+            .iter_mut()
+            .zip(Self::LABELS.iter())
+            .zip(values.iter())
+        {
+            match value {
+                Some(v) => write_field!(row, "{:5} {:5.1}", label, units.from_knots(*v)).unwrap(),
+                None => write_field!(row, "{:5}   -", label).unwrap(),
+            }
+        }
+    }
+}
+
+/// The settings menu, one item per row
+pub struct MenuScreen {
+    menu: Menu,
+    title: DisplayField<24>,
+    // The items that fit on the display, scrolling to keep the selected
+    // one in view
+    rows: [DisplayField<24>; 7],
+}
+
+impl MenuScreen {
+    pub fn new(menu: Menu) -> Self {
+        let mut screen = MenuScreen {
+            menu,
+            title: DisplayField::from_str("Settings"),
+            rows: [
+                DisplayField::new(),
+                DisplayField::new(),
+                DisplayField::new(),
+                DisplayField::new(),
+                DisplayField::new(),
+                DisplayField::new(),
+                DisplayField::new(),
+            ],
+        };
+        screen.update_rows();
+        screen
+    }
+
+    pub fn press(&mut self, press: Press) -> Option<Action> {
+        let action = self.menu.press(press);
+        self.update_rows();
+        action
+    }
+
+    pub fn render<D>(&mut self, layout: &Layout, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        let font = layout.font_24();
+        let mut cursor = Point::new(font.char_width(), 0);
+        let down = Point::new(0, font.char_height());
+        font.render_field(display, cursor, &mut self.title)?;
+        for row in self.rows.iter_mut() {
+            cursor = cursor + down;
+            font.render_field(display, cursor, row)?;
+        }
+        Result::Ok(())
+    }
+
+    fn update_rows(&mut self) {
+        let selected = Item::ALL
+            .iter()
+            .position(|item| *item == self.menu.selected())
+            .unwrap();
+        let first = (selected + 1).saturating_sub(self.rows.len());
+        for (row, item) in self.rows.iter_mut().zip(Item::ALL[first..].iter()) {
+            let selected = *item == self.menu.selected();
+            let marker = if selected { '>' } else { ' ' };
+            let mut value: [u8; 12] = [0; 12];
+            let mut w = U8Writer::new(&mut value);
+            self.menu.write_value(&mut w, *item).unwrap();
+            if selected && self.menu.editing() {
+                write_field!(row, "{} {:10} <{}>", marker, item.label(), w.as_str()).unwrap();
+            } else {
+                write_field!(row, "{} {:10}  {}", marker, item.label(), w.as_str()).unwrap();
+            }
+        }
+    }
+}
+
+pub struct CogScreen {
+    status_line: StatusLine,
+    cog_digits: [Updateable<Option<u8>>; 3],
+}
+
+impl CogScreen {
+    pub fn new() -> Self {
+        CogScreen {
+            status_line: StatusLine::new("COG"),
+            cog_digits: [
+                Updateable::new(None),
+                Updateable::new(None),
+                Updateable::new(None),
+            ],
+        }
+    }
+
+    pub fn render<D>(&mut self, layout: &Layout, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        self.status_line.render(layout, display)?;
+        self.render_cog(layout, display, layout.font_18().char_point(0, 2))?;
+        Result::Ok(())
+    }
+
+    fn render_cog<D>(
+        &mut self,
+        layout: &Layout,
+        display: &mut D,
+        loc: Point,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        let font = layout.font_156();
+        let mut cursor = loc;
+        let nextc = Point::new(layout.char_156.font.character_size.width as i32, 0);
+        for i in 0..3 {
+            if let Some(od) = self.cog_digits[i].updated() {
+                let c = match *od {
+                    Some(d) => char::from_digit(d as u32, 10).unwrap(),
+                    None => '-',
+                };
+                cursor = font.write_char(display, cursor, c)?
+            } else {
+                cursor = cursor + nextc;
+            }
+        }
+        Result::Ok(())
+    }
+
+    pub fn update(&mut self, update: &Update) {
+        self.status_line.update(update);
+        match update {
+            Update::Gps(gps) => self.update_gps(gps),
+            _ => (),
+        }
+    }
+
+    fn update_gps(&mut self, gps: &GpsData) {
+        self.status_line.update_gps(gps);
+
+        if let Some(course) = gps.course {
+            let cog = course.round() as u32;
+            self.cog_digits[0].set(Some(((cog / 100) % 10) as u8));
+            self.cog_digits[1].set(Some(((cog / 10) % 10) as u8));
+            self.cog_digits[2].set(Some((cog % 10) as u8));
+        } else {
+            self.cog_digits[0].set(None);
+            self.cog_digits[1].set(None);
+            self.cog_digits[2].set(None);
+        }
+    }
+}
+
+pub struct MiscScreen {
+    status_line: StatusLine,
+    gps_field: DisplayField<18>,
+    hdop_field: DisplayField<18>,
+    lat_field: DisplayField<18>,
+    lng_field: DisplayField<18>,
+    speed_field: DisplayField<18>,
+    max_speed_field: DisplayField<18>,
+    rejected_field: DisplayField<18>,
+    vbat_field: DisplayField<18>,
+}
+
+impl MiscScreen {
+    pub fn new() -> Self {
+        MiscScreen {
+            status_line: StatusLine::new(""),
+            gps_field: DisplayField::new(),
+            hdop_field: DisplayField::from_str("Hdop:"),
+            lat_field: DisplayField::from_str("Lat :"),
+            lng_field: DisplayField::from_str("Lng :"),
+            speed_field: DisplayField::from_str("Spd :"),
+            max_speed_field: DisplayField::from_str("Max :"),
+            rejected_field: DisplayField::from_str("Rej :"),
+            vbat_field: DisplayField::from_str("Vbat:"),
+        }
+    }
+
+    pub fn render<D>(&mut self, layout: &Layout, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        self.status_line.render(layout, display)?;
+        let font = layout.font_18();
+
+        let mut cursor = Point::new(font.char_width() * 2, font.char_height() * 3 / 2);
+        let down = Point::new(0, font.char_height());
+        font.render_field(display, cursor, &mut self.gps_field)?;
+        cursor = cursor + down;
+        font.render_field(display, cursor, &mut self.hdop_field)?;
+        cursor = cursor + down;
+        font.render_field(display, cursor, &mut self.lat_field)?;
+        cursor = cursor + down;
+        font.render_field(display, cursor, &mut self.lng_field)?;
+        cursor = cursor + down;
+        font.render_field(display, cursor, &mut self.speed_field)?;
+        cursor = cursor + down;
+        font.render_field(display, cursor, &mut self.max_speed_field)?;
+        cursor = cursor + down;
+        font.render_field(display, cursor, &mut self.rejected_field)?;
+        cursor = cursor + down;
+        font.render_field(display, cursor, &mut self.vbat_field)?;
+
+        Result::Ok(())
+    }
+
+    pub fn update(&mut self, update: &Update) {
+        self.status_line.update(update);
+        match update {
+            Update::Gps(gps) => self.update_gps(gps),
+            Update::Vbat(mv) => self.update_vbat(*mv),
+        }
+    }
+
+    fn update_gps(&mut self, gps: &GpsData) {
+        self.status_line.update_gps(gps);
+        let status = match gps.status {
+            GpsStatus::Searching => "GPS searching",
+            GpsStatus::Configuring => "GPS setting up",
+            GpsStatus::Ready => "GPS ok",
+            GpsStatus::NotResponding => "GPS not responding",
+        };
+        write_field!(self.gps_field, "{}", status).unwrap();
+        let units = gps.units;
+        write_field!(
+            self.speed_field,
+            "Spd : {:3.1} {}",
+            units.from_knots(gps.speed),
+            units.label()
+        )
+        .unwrap();
+        write_field!(
+            self.max_speed_field,
+            "Max : {:3.1} {}",
+            units.from_knots(gps.max_speed),
+            units.label()
+        )
+        .unwrap();
+        write_field!(self.rejected_field, "Rej : {}", gps.rejected_samples).unwrap();
+        match gps.hdop {
+            Some(hdop) => write_field!(self.hdop_field, "Hdop: {:5.1}", hdop).unwrap(),
+            None => write_field!(self.hdop_field, "Hdop: -    ").unwrap(),
+        }
+        match gps.latitude {
+            Some(latitude) => write_field!(self.lat_field, "Lat : {:12.6}", latitude).unwrap(),
+            None => write_field!(self.lat_field, "Lat : -           ").unwrap(),
+        }
+        match gps.longitude {
+            Some(longitude) => write_field!(self.lng_field, "Lat : {:12.6}", longitude).unwrap(),
+            None => write_field!(self.lng_field, "Lat : -           ").unwrap(),
+        }
+    }
+
+    fn update_vbat(&mut self, mv: u16) {
+        self.status_line.update_vbat(mv);
+        write_field!(self.vbat_field, "Vbat: {}mV", mv).unwrap();
+    }
+}
+
+/// The countdown to a race start, in minutes and seconds, and the signal
+/// to come
+pub struct StartScreen {
+    status_line: StatusLine,
+    digits: [Updateable<u8>; 3],
+    signal: DisplayField<24>,
+}
+
+impl StartScreen {
+    pub fn new() -> Self {
+        let mut screen = StartScreen {
+            status_line: StatusLine::new("race"),
+            digits: [Updateable::new(0), Updateable::new(0), Updateable::new(0)],
+            signal: DisplayField::new(),
+        };
+        screen.update_countdown(None);
+        screen
+    }
+
+    pub fn render<D>(&mut self, layout: &Layout, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        self.status_line.render(layout, display)?;
+
+        let font = layout.font_156();
+        let mut cursor = layout.font_18().char_point(0, 2);
+        let nextc = Point::new(font.char_width(), 0);
+        for (i, digit) in self.digits.iter_mut().enumerate() {
+            if i == 1 {
+                cursor = font.write_kerned_colon(display, cursor)?;
+            }
+            if let Some(d) = digit.updated() {
+                let c = char::from_digit(*d as u32, 10).unwrap();
+                cursor = font.write_char(display, cursor, c)?;
+            } else {
+                cursor = cursor + nextc;
+            }
+        }
+
+        let font = layout.font_24();
+        let bottom = display.bounding_box().size.height as i32 - font.char_height();
+        font.render_field(
+            display,
+            Point::new(font.char_width(), bottom),
+            &mut self.signal,
+        )?;
+        Result::Ok(())
+    }
+
+    pub fn update(&mut self, update: &Update) {
+        self.status_line.update(update);
+    }
+
+    /// Show `remaining_ms` to the start, or the full sequence when the
+    /// countdown isn't running
+    pub fn update_countdown(&mut self, remaining_ms: Option<u64>) {
+        // Count whole seconds down, so the start shows as 0:00
+        let s = (remaining_ms.unwrap_or(SEQUENCE_MS) + 999) / 1000;
+        self.digits[0].set((s / 60 % 10) as u8);
+        self.digits[1].set((s % 60 / 10) as u8);
+        self.digits[2].set((s % 10) as u8);
+        match remaining_ms {
+            Some(ms) => write_field!(self.signal, "next: {}", next_signal(ms)).unwrap(),
+            None => write_field!(self.signal, "double press to start").unwrap(),
+        }
+    }
+}
+
+/// Distance and time to the start line, and whether we're over it
+pub struct LineScreen {
+    status_line: StatusLine,
+    distance_field: DisplayField<16>,
+    time_field: DisplayField<16>,
+    over_field: DisplayField<16>,
+    ends_field: DisplayField<24>,
+}
+
+impl LineScreen {
+    pub fn new() -> Self {
+        let mut screen = LineScreen {
+            status_line: StatusLine::new("line"),
+            distance_field: DisplayField::new(),
+            time_field: DisplayField::new(),
+            over_field: DisplayField::new(),
+            ends_field: DisplayField::new(),
+        };
+        screen.update_status(None, false);
+        screen
+    }
+
+    pub fn render<D>(&mut self, layout: &Layout, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        self.status_line.render(layout, display)?;
+        let font = layout.font_24();
+
+        let mut cursor = Point::new(font.char_width() * 2, layout.font_18().char_height() * 2);
+        let down = Point::new(0, font.char_height());
+        font.render_field(display, cursor, &mut self.distance_field)?;
+        cursor = cursor + down;
+        font.render_field(display, cursor, &mut self.time_field)?;
+        cursor = cursor + down;
+        font.render_field(display, cursor, &mut self.over_field)?;
+        cursor = cursor + down * 2;
+        font.render_field(display, cursor, &mut self.ends_field)?;
+        Result::Ok(())
+    }
+
+    pub fn update(&mut self, update: &Update) {
+        self.status_line.update(update);
+    }
+
+    /// Show which ends of the line have been pinged
+    pub fn update_ends(&mut self, pin: bool, boat: bool) {
+        let mark = |set| if set { "set" } else { "-" };
+        write_field!(self.ends_field, "pin {:4} boat {}", mark(pin), mark(boat)).unwrap();
+    }
+
+    /// Show the `status` relative to the line, which is over early while
+    /// the start countdown is `running`
+    pub fn update_status(&mut self, status: Option<LineStatus>, running: bool) {
+        match status {
+            Some(status) => {
+                write_field!(self.distance_field, "dist {:6.0} m", status.distance_m).unwrap()
+            }
+            None => write_field!(self.distance_field, "dist      - m").unwrap(),
+        }
+        match status.and_then(|s| s.time_to_line_s) {
+            Some(t) if t < 1000.0 => write_field!(self.time_field, "time {:6.0} s", t).unwrap(),
+            _ => write_field!(self.time_field, "time      - s").unwrap(),
+        }
+        match status {
+            Some(status) if status.course_side && running => {
+                write_field!(self.over_field, "OVER EARLY").unwrap()
+            }
+            Some(status) if status.course_side => {
+                write_field!(self.over_field, "course side").unwrap()
+            }
+            _ => self.over_field.clear(),
+        }
+    }
+}
+
+/// Bearing, distance and velocity made good to the chosen waypoint
+pub struct NavScreen {
+    status_line: StatusLine,
+    mark_field: DisplayField<20>,
+    bearing_field: DisplayField<20>,
+    distance_field: DisplayField<20>,
+    vmg_field: DisplayField<20>,
+}
+
+impl NavScreen {
+    pub fn new() -> Self {
+        let mut screen = NavScreen {
+            status_line: StatusLine::new("nav"),
+            mark_field: DisplayField::new(),
+            bearing_field: DisplayField::new(),
+            distance_field: DisplayField::new(),
+            vmg_field: DisplayField::new(),
+        };
+        screen.update_mark(None, 0, 0);
+        screen.update_leg(None, SpeedUnit::Knots);
+        screen
+    }
+
+    pub fn render<D>(&mut self, layout: &Layout, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        self.status_line.render(layout, display)?;
+        let font = layout.font_24();
+
+        let mut cursor = Point::new(font.char_width() * 2, layout.font_18().char_height() * 2);
+        let down = Point::new(0, font.char_height());
+        font.render_field(display, cursor, &mut self.mark_field)?;
+        cursor = cursor + down;
+        font.render_field(display, cursor, &mut self.bearing_field)?;
+        cursor = cursor + down;
+        font.render_field(display, cursor, &mut self.distance_field)?;
+        cursor = cursor + down;
+        font.render_field(display, cursor, &mut self.vmg_field)?;
+        Result::Ok(())
+    }
+
+    pub fn update(&mut self, update: &Update) {
+        self.status_line.update(update);
+    }
+
+    /// Show the waypoint `name`, the `index`th of `count`
+    pub fn update_mark(&mut self, name: Option<&str>, index: usize, count: usize) {
+        match name {
+            Some(name) => {
+                write_field!(self.mark_field, "mark {:8} {}/{}", name, index + 1, count).unwrap()
+            }
+            None => write_field!(self.mark_field, "no waypoints").unwrap(),
+        }
+    }
+
+    pub fn update_leg(&mut self, leg: Option<Leg>, units: SpeedUnit) {
+        match leg {
+            Some(leg) => {
+                let bearing = leg.bearing.round() as u32 % 360;
+                write_field!(self.bearing_field, "brg  {:6}", bearing).unwrap();
+                write_field!(
+                    self.distance_field,
+                    "dist {:6.2} {}",
+                    units.distance_from_metres(leg.distance_m as u32),
+                    units.distance_label()
+                )
+                .unwrap();
+            }
+            None => {
+                write_field!(self.bearing_field, "brg       -").unwrap();
+                write_field!(
+                    self.distance_field,
+                    "dist      - {}",
+                    units.distance_label()
+                )
+                .unwrap();
+            }
+        }
+        match leg.and_then(|leg| leg.vmg) {
+            Some(vmg) => write_field!(
+                self.vmg_field,
+                "vmg  {:6.1} {}",
+                units.from_knots(vmg),
+                units.label()
+            )
+            .unwrap(),
+            None => write_field!(self.vmg_field, "vmg       - {}", units.label()).unwrap(),
+        }
+    }
+}
+
+/// The distance from where the anchor watch was set, and its radius
+pub struct AnchorScreen {
+    status_line: StatusLine,
+    distance_digits: [Updateable<Option<u8>>; 3],
+    watch_field: DisplayField<24>,
+}
+
+impl AnchorScreen {
+    pub fn new() -> Self {
+        AnchorScreen {
+            status_line: StatusLine::new("anch"),
+            distance_digits: [
+                Updateable::new(None),
+                Updateable::new(None),
+                Updateable::new(None),
+            ],
+            watch_field: DisplayField::new(),
+        }
+    }
+
+    pub fn render<D>(&mut self, layout: &Layout, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        self.status_line.render(layout, display)?;
+
+        let font = layout.font_156();
+        let mut cursor = layout.font_18().char_point(0, 2);
+        let nextc = Point::new(font.char_width(), 0);
+        for digit in self.distance_digits.iter_mut() {
+            if let Some(od) = digit.updated() {
+                let c = match *od {
+                    Some(d) => char::from_digit(d as u32, 10).unwrap(),
+                    None => '-',
+                };
+                cursor = font.write_char(display, cursor, c)?;
+            } else {
+                cursor = cursor + nextc;
+            }
+        }
+
+        let font = layout.font_24();
+        let bottom = display.bounding_box().size.height as i32 - font.char_height();
+        font.render_field(
+            display,
+            Point::new(font.char_width(), bottom),
+            &mut self.watch_field,
+        )?;
+        Result::Ok(())
+    }
+
+    pub fn update(&mut self, update: &Update) {
+        self.status_line.update(update);
+    }
+
+    pub fn update_watch(&mut self, watch: &AnchorWatch) {
+        match watch.distance_m() {
+            Some(d) => {
+                let d = (d.round() as u32).min(999);
+                self.distance_digits[0].set(Some((d / 100) as u8));
+                self.distance_digits[1].set(Some((d / 10 % 10) as u8));
+                self.distance_digits[2].set(Some((d % 10) as u8));
+            }
+            None => {
+                for digit in self.distance_digits.iter_mut() {
+                    digit.set(None);
+                }
+            }
+        }
+        if !watch.is_set() {
+            write_field!(self.watch_field, "double press to set").unwrap();
+        } else if watch.is_alarm() {
+            write_field!(self.watch_field, "radius {} m  DRAGGING", watch.radius_m()).unwrap();
+        } else {
+            write_field!(self.watch_field, "radius {} m", watch.radius_m()).unwrap();
+        }
+    }
+}
+
+/// The speed over the last few minutes, as a line graph
+pub struct GraphScreen {
+    status_line: StatusLine,
+    history: Option<SpeedHistory>,
+    units: SpeedUnit,
+    // The graph is redrawn when a point is added, or the units change
+    plotted: Updateable<(u32, SpeedUnit)>,
+    top_field: DisplayField<3>,
+    span_field: DisplayField<24>,
+}
+
+impl GraphScreen {
+    pub fn new() -> Self {
+        GraphScreen {
+            status_line: StatusLine::new("hist"),
+            history: None,
+            units: SpeedUnit::Knots,
+            plotted: Updateable::new((0, SpeedUnit::Knots)),
+            top_field: DisplayField::new(),
+            span_field: DisplayField::from_str("no speed history yet"),
+        }
+    }
+
+    pub fn render<D>(&mut self, layout: &Layout, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        self.status_line.render(layout, display)?;
+
+        let font = layout.font_18();
+        let size = display.bounding_box().size;
+        let left = font.char_width() * 3 + 12;
+        let right = size.width as i32 - 8;
+        let top = font.char_height() * 2;
+        let bottom = size.height as i32 - font.char_height() - 12;
+
+        if self.plotted.updated().is_some() {
+            self.render_graph(
+                layout,
+                display,
+                Point::new(left, top),
+                Point::new(right, bottom),
+            )?;
+        }
+        font.render_field(
+            display,
+            Point::new(4, top - font.char_height() / 2),
+            &mut self.top_field,
+        )?;
+        font.render_field(display, Point::new(left, bottom + 8), &mut self.span_field)?;
+        Result::Ok(())
+    }
+
+    fn render_graph<D>(
+        &mut self,
+        layout: &Layout,
+        display: &mut D,
+        tl: Point,
+        br: Point,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        Rectangle::with_corners(tl - Point::new(6, 2), br + Point::new(2, 2))
+            .into_styled(layout.bg_fill_style)
+            .draw(display)?;
+        Line::new(tl, Point::new(tl.x, br.y))
+            .into_styled(layout.fg_fill_style)
+            .draw(display)?;
+        Line::new(Point::new(tl.x, br.y), br)
+            .into_styled(layout.fg_fill_style)
+            .draw(display)?;
+
+        let units = self.units;
+        let max = match &self.history {
+            Some(history) => units.from_knots(history.max()),
+            None => 0f32,
+        };
+        let (axis_top, step) = axis_scale(max);
+        write_field!(self.top_field, "{:>3}", axis_top).unwrap();
+
+        let height = (br.y - tl.y) as f32;
+        let y_of = |v: f32| br.y - (v.min(axis_top as f32) / axis_top as f32 * height) as i32;
+        for tick in (step..=axis_top).step_by(step as usize) {
+            let y = y_of(tick as f32);
+            Line::new(Point::new(tl.x - 6, y), Point::new(tl.x, y))
+                .into_styled(layout.fg_fill_style)
+                .draw(display)?;
+        }
+
+        let history = match &self.history {
+            Some(history) if history.len() >= 2 => history,
+            _ => return Result::Ok(()),
+        };
+        let width = br.x - tl.x;
+        let last = history.len() as i32 - 1;
+        let mut previous: Option<Point> = None;
+        for (i, speed) in history.iter().enumerate() {
+            let point = Point::new(
+                tl.x + i as i32 * width / last,
+                y_of(units.from_knots(speed)),
+            );
+            if let Some(previous) = previous {
+                Line::new(previous, point)
+                    .into_styled(layout.fg_fill_style)
+                    .draw(display)?;
+            }
+            previous = Some(point);
+        }
+        Result::Ok(())
+    }
+
+    pub fn update(&mut self, update: &Update) {
+        self.status_line.update(update);
+        if let Update::Gps(gps) = update {
+            self.update_history(&gps.history, gps.units);
+        }
+    }
+
+    fn update_history(&mut self, history: &SpeedHistory, units: SpeedUnit) {
+        self.plotted.set((history.added(), units));
+        self.units = units;
+        if history.is_empty() {
+            write_field!(self.span_field, "no speed history yet").unwrap();
+        } else {
+            let minutes = (history.len() as u32 * history.point_s() as u32 + 59) / 60;
+            write_field!(
+                self.span_field,
+                "{} min  max {:.1} {}",
+                minutes,
+                units.from_knots(history.max()),
+                units.label()
+            )
+            .unwrap();
+        }
+        self.history = Some(history.clone());
+    }
+}
+
+/// The recent track as breadcrumbs around the boat, north or course up,
+/// zoomed to fit. The map is only redrawn when it has to move, zoom or
+/// turn; otherwise new breadcrumbs and the boat are drawn onto it.
+pub struct TrackScreen {
+    status_line: StatusLine,
+    course_up: bool,
+    // How the map was last drawn, if it has been
+    frame: Option<TrackFrame>,
+    // The whole track, kept only until the map is redrawn
+    redraw: Option<TrackHistory>,
+    // Breadcrumbs to add to the map
+    new_points: [(i32, i32); TRACK_NEW_MAX],
+    new_len: usize,
+    // The latest breadcrumbs drawn, to put back where the boat was
+    recent: [Option<Point>; TRACK_NEW_MAX],
+    // How many breadcrumbs the track had ever had, at the last update
+    added: u32,
+    boat: Option<((i32, i32), Option<f32>)>,
+    // The boat as it is drawn, and its course tick
+    drawn_boat: Option<(Point, Option<Point>)>,
+    scale_field: DisplayField<8>,
+    mode_field: DisplayField<8>,
+}
+
+// More new breadcrumbs than this between renders redraws the map
+const TRACK_NEW_MAX: usize = 4;
+
+/// How the track map is drawn: centred on a point, turned, and zoomed
+#[derive(Clone, Copy)]
+struct TrackFrame {
+    projection: LocalProjection,
+    turn: f32,
+    // The map's height over its width
+    aspect: f32,
+    range_m: u32,
+}
+
+impl TrackFrame {
+    /// (x, y) in metres across and up the map from its centre
+    fn map(&self, p: (i32, i32)) -> (f32, f32) {
+        let (east, north) = self.projection.project(p.0, p.1);
+        rotate(east, north, self.turn)
+    }
+
+    /// How far `p` is from the centre, as a distance up the map, allowing
+    /// for the map being wider than it is high
+    fn extent(&self, p: (i32, i32)) -> f32 {
+        let (x, y) = self.map(p);
+        y.abs().max(x.abs() * self.aspect)
+    }
+}
+
+/// Where the track map goes on the display
+#[derive(Clone, Copy)]
+struct MapArea {
+    center: Point,
+    half: i32,
+    tl: Point,
+    br: Point,
+}
+
+// The map turns with the course in steps of 10 degrees, so that it is
+// only redrawn for a real change of course
+fn track_turn(course_up: bool, course: Option<f32>) -> f32 {
+    match (course_up, course) {
+        (true, Some(course)) => (course / 10.0).round() * 10.0,
+        _ => 0f32,
+    }
+}
+
+impl TrackScreen {
+    pub fn new() -> Self {
+        let mut screen = TrackScreen {
+            status_line: StatusLine::new("trk"),
+            course_up: false,
+            frame: None,
+            redraw: None,
+            new_points: [(0, 0); TRACK_NEW_MAX],
+            new_len: 0,
+            recent: [None; TRACK_NEW_MAX],
+            added: 0,
+            boat: None,
+            drawn_boat: None,
+            scale_field: DisplayField::new(),
+            mode_field: DisplayField::new(),
+        };
+        screen.set_course_up(false);
+        screen
+    }
+
+    pub fn render<D>(&mut self, layout: &Layout, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        self.status_line.render(layout, display)?;
+
+        let font = layout.font_18();
+        let size = display.bounding_box().size;
+        let top = font.char_height() + 8;
+        let bottom = size.height as i32 - font.char_height() - 8;
+        let half = (bottom - top) / 2;
+        let bar = half / 2;
+        let area = MapArea {
+            center: Point::new(size.width as i32 / 2, top + half),
+            half,
+            tl: Point::new(0, top),
+            br: Point::new(size.width as i32 - 1, bottom),
+        };
+        if let Some(track) = self.redraw.take() {
+            self.redraw_track(layout, display, &track, area)?;
+
+            // The scale bar is the length of half the range
+            let y = bottom + 4 + font.char_height() / 2;
+            Line::new(Point::new(8, y), Point::new(8 + bar, y))
+                .into_styled(layout.fg_fill_style)
+                .draw(display)?;
+            for x in [8, 8 + bar] {
+                Line::new(Point::new(x, y - 4), Point::new(x, y + 4))
+                    .into_styled(layout.fg_fill_style)
+                    .draw(display)?;
+            }
+        } else {
+            self.plot_new(layout, display, area)?;
+        }
+        font.render_field(
+            display,
+            Point::new(16 + bar, bottom + 4),
+            &mut self.scale_field,
+        )?;
+        let right = size.width as i32 - 4 - font.char_width() * 8;
+        font.render_field(display, Point::new(right, bottom + 4), &mut self.mode_field)?;
+        Result::Ok(())
+    }
+
+    // Draw the whole map again, centred on the boat and zoomed so that
+    // every breadcrumb fits
+    fn redraw_track<D>(
+        &mut self,
+        layout: &Layout,
+        display: &mut D,
+        track: &TrackHistory,
+        area: MapArea,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        Rectangle::with_corners(area.tl, area.br)
+            .into_styled(layout.bg_fill_style)
+            .draw(display)?;
+        self.new_len = 0;
+        self.recent = [None; TRACK_NEW_MAX];
+        self.drawn_boat = None;
+
+        let here = match self.boat.map(|(here, _)| here).or_else(|| track.last()) {
+            Some(here) => here,
+            None => {
+                self.frame = None;
+                write_field!(self.scale_field, "").unwrap();
+                return Result::Ok(());
+            }
+        };
+        let course = self.boat.and_then(|(_, course)| course);
+        let mut frame = TrackFrame {
+            projection: LocalProjection::new(here.0, here.1),
+            turn: track_turn(self.course_up, course),
+            aspect: area.half as f32 / (area.center.x - area.tl.x) as f32,
+            range_m: 0,
+        };
+        let extent = track.iter().map(|p| frame.extent(p)).fold(0f32, f32::max);
+        frame.range_m = zoom_m(extent);
+        let scale_m = frame.range_m / 2;
+        if scale_m < 1000 {
+            write_field!(self.scale_field, "{} m", scale_m).unwrap();
+        } else if scale_m % 1000 == 0 {
+            write_field!(self.scale_field, "{} km", scale_m / 1000).unwrap();
+        } else {
+            write_field!(self.scale_field, "{:.1} km", scale_m as f32 / 1000.0).unwrap();
+        }
+        self.frame = Some(frame);
+
+        for p in track.iter() {
+            self.plot_point(layout, display, &frame, area, p)?;
+        }
+        self.draw_boat(layout, display, &frame, area)
+    }
+
+    // Add the new breadcrumbs to the map, and move the boat
+    fn plot_new<D>(
+        &mut self,
+        layout: &Layout,
+        display: &mut D,
+        area: MapArea,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        let frame = match self.frame {
+            Some(frame) => frame,
+            None => return Result::Ok(()),
+        };
+        for i in 0..self.new_len {
+            self.plot_point(layout, display, &frame, area, self.new_points[i])?;
+        }
+        self.new_len = 0;
+        self.draw_boat(layout, display, &frame, area)
+    }
+
+    fn plot_point<D>(
+        &mut self,
+        layout: &Layout,
+        display: &mut D,
+        frame: &TrackFrame,
+        area: MapArea,
+        p: (i32, i32),
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        let p = to_map_point(frame, area, p);
+        self.recent.rotate_left(1);
+        self.recent[TRACK_NEW_MAX - 1] = Some(p);
+        draw_breadcrumb(layout, display, area, p)
+    }
+
+    // Draw the boat, with a tick along its course, rubbing it out where it
+    // was and putting back the breadcrumbs it covered
+    fn draw_boat<D>(
+        &mut self,
+        layout: &Layout,
+        display: &mut D,
+        frame: &TrackFrame,
+        area: MapArea,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        let boat = self.boat.map(|(here, course)| {
+            let at = to_map_point(frame, area, here);
+            let tick = course.map(|course| {
+                let (x, y) = rotate(0f32, 18f32, frame.turn - course);
+                at + Point::new(x as i32, -y as i32)
+            });
+            (at, tick)
+        });
+        if boat == self.drawn_boat {
+            return Result::Ok(());
+        }
+        if let Some((at, tick)) = self.drawn_boat {
+            draw_boat_at(display, layout.bg_fill_style, at, tick)?;
+            for p in self.recent.iter().flatten() {
+                draw_breadcrumb(layout, display, area, *p)?;
+            }
+        }
+        if let Some((at, tick)) = boat {
+            draw_boat_at(display, layout.fg_fill_style, at, tick)?;
+        }
+        self.drawn_boat = boat;
+        Result::Ok(())
+    }
+
+    pub fn update(&mut self, update: &Update) {
+        self.status_line.update(update);
+        if let Update::Gps(gps) = update {
+            let track = &gps.track;
+            let new = track.added().wrapping_sub(self.added) as usize;
+            self.added = track.added();
+            self.boat = gps
+                .position_raw
+                .or_else(|| track.last())
+                .map(|here| (here, gps.course));
+
+            // A breadcrumb that falls off the end of the track stays on the
+            // map until it is next redrawn
+            let new_points = move || track.iter().skip(track.len().saturating_sub(new));
+            let fits = match (&self.frame, &self.redraw) {
+                (Some(frame), None) => {
+                    let range_m = frame.range_m as f32;
+                    frame.turn == track_turn(self.course_up, gps.course)
+                        && new <= TRACK_NEW_MAX - self.new_len
+                        && self
+                            .boat
+                            .map_or(true, |(here, _)| frame.extent(here) <= range_m * 0.75)
+                        && new_points().all(|p| frame.extent(p) <= range_m)
+                }
+                _ => false,
+            };
+            if fits {
+                for p in new_points() {
+                    self.new_points[self.new_len] = p;
+                    self.new_len += 1;
+                }
+            } else {
+                self.redraw = Some(track.clone());
+            }
+        }
+    }
+
+    /// Turn the map, from the next fix
+    pub fn set_course_up(&mut self, course_up: bool) {
+        self.course_up = course_up;
+        if course_up {
+            write_field!(self.mode_field, "{:>8}", "crs up").unwrap();
+        } else {
+            write_field!(self.mode_field, "{:>8}", "north up").unwrap();
+        }
+        self.frame = None;
+    }
+}
+
+fn to_map_point(frame: &TrackFrame, area: MapArea, p: (i32, i32)) -> Point {
+    let px_per_m = area.half as f32 / frame.range_m as f32;
+    let (x, y) = frame.map(p);
+    area.center + Point::new((x * px_per_m) as i32, -(y * px_per_m) as i32)
+}
+
+fn draw_breadcrumb<D>(
+    layout: &Layout,
+    display: &mut D,
+    area: MapArea,
+    p: Point,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = DPixelColor>,
+{
+    if p.x > area.tl.x && p.x < area.br.x && p.y > area.tl.y && p.y < area.br.y {
+        Rectangle::new(p - Point::new(1, 1), Size::new(3, 3))
+            .into_styled(layout.fg_fill_style)
+            .draw(display)?;
+    }
+    Result::Ok(())
+}
+
+fn draw_boat_at<D>(
+    display: &mut D,
+    style: PrimitiveStyle<DPixelColor>,
+    at: Point,
+    tick: Option<Point>,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = DPixelColor>,
+{
+    Circle::with_center(at, 11)
+        .into_styled(style)
+        .draw(display)?;
+    if let Some(tick) = tick {
+        Line::new(at, tick).into_styled(style).draw(display)?;
+    }
+    Result::Ok(())
+}
+
+/// The course on a heading tape and in digits, with the wind shift since
+/// the reference heading was set
+pub struct HeadingScreen {
+    status_line: StatusLine,
+    course: Option<f32>,
+    // In whole degrees
+    tape: Updateable<Option<u16>>,
+    heading_digits: [Updateable<Option<u8>>; 3],
+    reference: HeadingReference,
+    // Up for a lift, down for a header
+    arrow: Updateable<Option<bool>>,
+    shift_field: DisplayField<24>,
+}
+
+impl HeadingScreen {
+    // The tape's scale, and how far it reaches either side of the course
+    const PX_PER_DEG: i32 = 5;
+    const TAPE_DEG: i32 = 40;
+
+    pub fn new() -> Self {
+        let mut screen = HeadingScreen {
+            status_line: StatusLine::new("hdg"),
+            course: None,
+            tape: Updateable::new(None),
+            heading_digits: [
+                Updateable::new(None),
+                Updateable::new(None),
+                Updateable::new(None),
+            ],
+            reference: HeadingReference::new(),
+            arrow: Updateable::new(None),
+            shift_field: DisplayField::new(),
+        };
+        screen.update_shift();
+        screen
+    }
+
+    pub fn render<D>(&mut self, layout: &Layout, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        self.status_line.render(layout, display)?;
+
+        let width = display.bounding_box().size.width as i32;
+        let top = layout.font_18().char_height() + 12;
+        if let Some(tape) = self.tape.updated() {
+            let tape = *tape;
+            Self::render_tape(layout, display, tape, top, width)?;
+        }
+
+        let font = layout.font_78();
+        let mut cursor = Point::new(width / 2 - font.char_width() * 3 / 2, top + 66);
+        let nextc = Point::new(font.char_width(), 0);
+        for digit in self.heading_digits.iter_mut() {
+            if let Some(od) = digit.updated() {
+                let c = match *od {
+                    Some(d) => char::from_digit(d as u32, 10).unwrap(),
+                    None => '-',
+                };
+                cursor = font.write_char(display, cursor, c)?;
+            } else {
+                cursor = cursor + nextc;
+            }
+        }
+
+        if let Some(arrow) = self.arrow.updated() {
+            let (x, y) = (cursor.x + 40, cursor.y + font.char_height() / 2);
+            Rectangle::with_corners(Point::new(x - 30, y - 35), Point::new(x + 30, y + 35))
+                .into_styled(layout.bg_fill_style)
+                .draw(display)?;
+            let (tip, base) = match arrow {
+                Some(true) => (y - 30, y + 20),
+                Some(false) => (y + 30, y - 20),
+                None => (y, y),
+            };
+            if arrow.is_some() {
+                Triangle::new(
+                    Point::new(x, tip),
+                    Point::new(x - 25, base),
+                    Point::new(x + 25, base),
+                )
+                .into_styled(layout.fg_fill_style)
+                .draw(display)?;
+            }
+        }
+
+        let font = layout.font_24();
+        let bottom = display.bounding_box().size.height as i32 - font.char_height() - 4;
+        font.render_field(display, Point::new(4, bottom), &mut self.shift_field)?;
+        Result::Ok(())
+    }
+
+    /// Ticks every 5 degrees, and labels every 30, centred on `course`
+    fn render_tape<D>(
+        layout: &Layout,
+        display: &mut D,
+        course: Option<u16>,
+        top: i32,
+        width: i32,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        let font = layout.font_18();
+        let bottom = top + font.char_height() + 24;
+        Rectangle::with_corners(Point::new(0, top), Point::new(width - 1, bottom + 12))
+            .into_styled(layout.bg_fill_style)
+            .draw(display)?;
+
+        // The pointer to the course, under the tape
+        let cx = width / 2;
+        Triangle::new(
+            Point::new(cx, bottom + 2),
+            Point::new(cx - 8, bottom + 12),
+            Point::new(cx + 8, bottom + 12),
+        )
+        .into_styled(layout.fg_fill_style)
+        .draw(display)?;
+
+        let course = match course {
+            Some(course) => course as i32,
+            None => return Result::Ok(()),
+        };
+        let first = (course - Self::TAPE_DEG).div_euclid(5) * 5;
+        for d in (first..=course + Self::TAPE_DEG).step_by(5) {
+            let x = cx + (d - course) * Self::PX_PER_DEG;
+            let len = match d.rem_euclid(30) {
+                0 => 20,
+                10 | 20 => 14,
+                _ => 8,
+            };
+            Line::new(Point::new(x, bottom - len), Point::new(x, bottom))
+                .into_styled(layout.fg_fill_style)
+                .draw(display)?;
+            if d.rem_euclid(30) != 0 {
+                continue;
+            }
+            let mut buf: [u8; 3] = [0; 3];
+            let mut w = U8Writer::new(&mut buf);
+            match d.rem_euclid(360) {
+                0 => write!(w, "N").unwrap(),
+                90 => write!(w, "E").unwrap(),
+                180 => write!(w, "S").unwrap(),
+                270 => write!(w, "W").unwrap(),
+                v => write!(w, "{:03}", v).unwrap(),
+            }
+            let label = w.as_str();
+            let lx = x - label.len() as i32 * font.char_width() / 2;
+            font.write_str(display, Point::new(lx, top), label)?;
+        }
+        Result::Ok(())
+    }
+
+    pub fn update(&mut self, update: &Update) {
+        self.status_line.update(update);
+        if let Update::Gps(gps) = update {
+            self.course = gps.course;
+            let course = gps.course.map(|c| c.round() as u16 % 360);
+            self.tape.set(course);
+            match course {
+                Some(course) => {
+                    self.heading_digits[0].set(Some((course / 100) as u8));
+                    self.heading_digits[1].set(Some((course / 10 % 10) as u8));
+                    self.heading_digits[2].set(Some((course % 10) as u8));
+                }
+                None => {
+                    for digit in self.heading_digits.iter_mut() {
+                        digit.set(None);
+                    }
+                }
+            }
+            self.update_shift();
+        }
+    }
+
+    pub fn set_reference(&mut self, reference: HeadingReference) {
+        self.reference = reference;
+        self.update_shift();
+    }
+
+    fn update_shift(&mut self) {
+        let reference = match self.reference.heading() {
+            Some(heading) => heading.round() as u16 % 360,
+            None => {
+                self.arrow.set(None);
+                write_field!(self.shift_field, "double press to set ref").unwrap();
+                return;
+            }
+        };
+        let tack = self.reference.tack().label();
+        let shift = self.course.and_then(|course| self.reference.shift(course));
+        self.arrow.set(match shift {
+            Some(Shift::Lift(_)) => Some(true),
+            Some(Shift::Header(_)) => Some(false),
+            _ => None,
+        });
+        match shift {
+            Some(Shift::Lift(d)) => {
+                write_field!(
+                    self.shift_field,
+                    "ref {:03} {}  lift {}",
+                    reference,
+                    tack,
+                    d.round() as u32
+                )
+            }
+            Some(Shift::Header(d)) => write_field!(
+                self.shift_field,
+                "ref {:03} {}  header {}",
+                reference,
+                tack,
+                d.round() as u32
+            ),
+            Some(Shift::Steady) => {
+                write_field!(self.shift_field, "ref {:03} {}  steady", reference, tack)
+            }
+            None => write_field!(self.shift_field, "ref {:03} {}", reference, tack),
+        }
+        .unwrap();
+    }
+}
+
+/// The cost of the last tack or gybe, and how many there have been
+pub struct ManoeuvreScreen {
+    status_line: StatusLine,
+    turn_field: DisplayField<20>,
+    loss_field: DisplayField<20>,
+    recover_field: DisplayField<20>,
+    lost_field: DisplayField<20>,
+    counts_field: DisplayField<20>,
+}
+
+impl ManoeuvreScreen {
+    pub fn new() -> Self {
+        let mut screen = ManoeuvreScreen {
+            status_line: StatusLine::new("man"),
+            turn_field: DisplayField::new(),
+            loss_field: DisplayField::new(),
+            recover_field: DisplayField::new(),
+            lost_field: DisplayField::new(),
+            counts_field: DisplayField::new(),
+        };
+        screen.update_manoeuvres(&ManoeuvreStats::default(), SpeedUnit::Knots);
+        screen
+    }
+
+    pub fn render<D>(&mut self, layout: &Layout, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = DPixelColor>,
+    {
+        self.status_line.render(layout, display)?;
+        let font = layout.font_24();
+
+        let mut cursor = Point::new(font.char_width() * 2, layout.font_18().char_height() * 2);
+        let down = Point::new(0, font.char_height());
+        font.render_field(display, cursor, &mut self.turn_field)?;
+        cursor = cursor + down;
+        font.render_field(display, cursor, &mut self.loss_field)?;
+        cursor = cursor + down;
+        font.render_field(display, cursor, &mut self.recover_field)?;
+        cursor = cursor + down;
+        font.render_field(display, cursor, &mut self.lost_field)?;
+        cursor = cursor + down;
+        font.render_field(display, cursor, &mut self.counts_field)?;
+        Result::Ok(())
+    }
+
+    pub fn update(&mut self, update: &Update) {
+        self.status_line.update(update);
+        if let Update::Gps(gps) = update {
+            self.update_manoeuvres(&gps.manoeuvres, gps.units);
+        }
+    }
+
+    fn update_manoeuvres(&mut self, stats: &ManoeuvreStats, units: SpeedUnit) {
+        write_field!(
+            self.counts_field,
+            "tacks {:<4} gybes {}",
+            stats.tacks,
+            stats.gybes
+        )
+        .unwrap();
+        let last = match stats.last {
+            Some(last) => last,
+            None => {
+                write_field!(self.turn_field, "no manoeuvres yet").unwrap();
+                self.loss_field.clear();
+                self.recover_field.clear();
+                self.lost_field.clear();
+                return;
+            }
+        };
+        let kind = match last.kind {
+            Kind::Tack => "tack",
+            Kind::Gybe => "gybe",
+        };
+        let side = if last.turn_deg < 0.0 { "port" } else { "stbd" };
+        write_field!(
+            self.turn_field,
+            "{} {:4} to {}",
+            kind,
+            last.turn_deg.abs().round() as u32,
+            side
+        )
+        .unwrap();
+        write_field!(
+            self.loss_field,
+            "loss    {:5.1} {}",
+            units.from_knots(last.speed_loss),
+            units.label()
+        )
+        .unwrap();
+        match last.recover_ms {
+            Some(ms) => {
+                write_field!(self.recover_field, "recover {:5.1} s", ms as f32 / 1000.0).unwrap()
+            }
+            None => write_field!(self.recover_field, "recover     - s").unwrap(),
+        }
+        write_field!(
+            self.lost_field,
+            "lost    {:5} m",
+            last.distance_lost_m.round() as i32
+        )
+        .unwrap();
+    }
+}
+
+const BATTERY_WIDTH: u32 = 30;
+const BATTERY_HEIGHT: u32 = 16;
+
+const BATTERY_MINV: u32 = 3400;
+const BATTERY_MAXV: u32 = 4200;
+
+fn battery_percent(batmv: u32) -> u32 {
+    if batmv < BATTERY_MINV {
+        0
+    } else if batmv > BATTERY_MAXV {
+        100
+    } else {
+        (batmv - BATTERY_MINV) * 100 / (BATTERY_MAXV - BATTERY_MINV)
+    }
+}
+
+pub fn render_battery_top_centre<D>(
+    display: &mut D,
+    layout: &Layout,
+    percent: u32,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = DPixelColor>,
+{
+    let loc = Point::new(
+        ((display.bounding_box().size.width - BATTERY_WIDTH) / 2) as i32,
+        15,
+    );
+    render_battery(display, layout, loc, percent)
+}
+
+pub fn render_battery<D>(
+    display: &mut D,
+    layout: &Layout,
+    loc: Point,
+    percent: u32,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = DPixelColor>,
+{
+    let size = Size::new(BATTERY_WIDTH, BATTERY_HEIGHT);
+    let border = Size::new(2, 2);
+    let border2 = Size::new(3, 3);
+    let nib_size = Size::new(3, 6);
+
+    Rectangle::new(loc, size)
+        .into_styled(layout.fg_fill_style)
+        .draw(display)?;
+
+    Rectangle::new(
+        loc + Size::new(size.width, (size.height - nib_size.height) / 2),
+        nib_size,
+    )
+    .into_styled(layout.fg_fill_style)
+    .draw(display)?;
+
+    Rectangle::new(loc + border, size - border * 2)
+        .into_styled(layout.bg_fill_style)
+        .draw(display)?;
+
+    let mut fsize = size - border2 * 2;
+    fsize.width = fsize.width * percent / 100;
+    Rectangle::new(loc + border2, fsize)
+        .into_styled(layout.fg_fill_style)
+        .draw(display)?;
+    Ok(())
+}
+
+pub struct Updateable<T> {
+    value: T,
+    updated: bool,
+}
+
+impl<T: PartialEq> Updateable<T> {
+    pub fn new(value: T) -> Self {
+        Updateable {
+            value,
+            updated: true,
+        }
+    }
+
+    pub fn set(&mut self, value: T) {
+        if self.value != value {
+            self.value = value;
+            self.updated = true;
+        }
+    }
+
+    pub fn updated(&mut self) -> Option<&T> {
+        if self.updated {
+            self.updated = false;
+            Some(&self.value)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,303 @@
+//! User settings and the waypoint list, persisted in flash.
+//!
+//! Each save appends a complete, checksummed record to a sector, and the
+//! last valid record is the current settings. The waypoints are saved as
+//! a run of records, one per waypoint, and the last complete run is the
+//! current list. When the sector is full it is erased and writing starts
+//! again from the beginning, with whichever of the settings and waypoints
+//! isn't being saved copied over. A power loss at just that moment loses
+//! them, and the settings revert to defaults.
+
+use crate::gps_setup::DynModel;
+use crate::screens::Page;
+use crate::storage::Flash;
+use crate::units::SpeedUnit;
+use crate::waypoints::{Waypoint, Waypoints, MAX_WAYPOINTS, NAME_LEN};
+
+const RECORD_SIZE: usize = 32;
+const PAYLOAD_SIZE: usize = RECORD_SIZE - 4;
+const RECORD_MAGIC: u16 = 0x5453; // "ST"
+const WAYPOINT_MAGIC: u16 = 0x5057; // "WP"
+
+/// The speed smoothing windows that can be chosen, in seconds
+pub const SMOOTHING_S: [u8; 3] = [1, 2, 5];
+
+/// The long average windows that can be chosen, in seconds
+pub const AVG_WINDOW_S: [u8; 3] = [10, 30, 60];
+
+/// The receiver's measurement rates that can be chosen, in Hz
+pub const RATE_HZ: [u8; 4] = [1, 2, 5, 10];
+
+/// The worst speed accuracies that can be chosen for a fix's speed to be
+/// used, in cm/s
+pub const SPEED_ACC_CM_S: [u8; 4] = [20, 50, 100, 200];
+
+/// The worst position accuracies that can be chosen, in metres
+pub const POS_ACC_M: [u8; 4] = [5, 10, 20, 50];
+
+/// The fewest satellites in use that can be chosen
+pub const MIN_SATS: [u8; 4] = [4, 5, 6, 8];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Settings {
+    pub units: SpeedUnit,
+    /// The window the displayed speed is averaged over
+    pub smoothing_s: u8,
+    /// The window for the average speed and its maximum
+    pub avg_window_s: u8,
+    /// The page shown at power up
+    pub page: Page,
+    /// Black text on white
+    pub invert: bool,
+    /// How often the receiver reports a fix
+    pub rate_hz: u8,
+    /// How the receiver expects the tracker to move
+    pub gps_model: DynModel,
+    /// Fixes less accurate than these, or with fewer satellites, are left
+    /// out of the speeds and statistics
+    pub speed_acc_cm_s: u8,
+    pub pos_acc_m: u8,
+    pub min_sats: u8,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            units: SpeedUnit::Knots,
+            smoothing_s: 1,
+            avg_window_s: 10,
+            page: Page::Speed,
+            invert: false,
+            rate_hz: 2,
+            gps_model: DynModel::Sea,
+            speed_acc_cm_s: 50,
+            pos_acc_m: 20,
+            min_sats: 5,
+        }
+    }
+}
+
+impl Settings {
+    // Unused bytes are left as 0xff, and a field that doesn't decode
+    // takes its default, so that fields can be added later.
+    fn encode(&self) -> [u8; PAYLOAD_SIZE] {
+        let mut p = [0xff; PAYLOAD_SIZE];
+        p[0] = self.units.to_u8();
+        p[1] = self.page.to_u8();
+        p[2] = self.invert as u8;
+        p[3] = self.smoothing_s;
+        p[4] = self.avg_window_s;
+        p[5] = self.rate_hz;
+        p[6] = self.gps_model.to_u8();
+        p[7] = self.speed_acc_cm_s;
+        p[8] = self.pos_acc_m;
+        p[9] = self.min_sats;
+        p
+    }
+
+    fn decode(p: &[u8]) -> Self {
+        let default = Settings::default();
+        Settings {
+            units: SpeedUnit::from_u8(p[0]).unwrap_or(default.units),
+            page: Page::from_u8(p[1]).unwrap_or(default.page),
+            invert: match p[2] {
+                0 => false,
+                1 => true,
+                _ => default.invert,
+            },
+            smoothing_s: one_of(&SMOOTHING_S, p[3]).unwrap_or(default.smoothing_s),
+            avg_window_s: one_of(&AVG_WINDOW_S, p[4]).unwrap_or(default.avg_window_s),
+            rate_hz: one_of(&RATE_HZ, p[5]).unwrap_or(default.rate_hz),
+            gps_model: DynModel::from_u8(p[6]).unwrap_or(default.gps_model),
+            speed_acc_cm_s: one_of(&SPEED_ACC_CM_S, p[7]).unwrap_or(default.speed_acc_cm_s),
+            pos_acc_m: one_of(&POS_ACC_M, p[8]).unwrap_or(default.pos_acc_m),
+            min_sats: one_of(&MIN_SATS, p[9]).unwrap_or(default.min_sats),
+        }
+    }
+}
+
+fn one_of(choices: &[u8], v: u8) -> Option<u8> {
+    choices.iter().copied().find(|c| *c == v)
+}
+
+/// The choice after `v`, wrapping round, for stepping through choices
+pub fn next_choice(choices: &[u8], v: u8) -> u8 {
+    match choices.iter().position(|c| *c == v) {
+        Some(i) => choices[(i + 1) % choices.len()],
+        None => choices[0],
+    }
+}
+
+// A waypoint record holds its index in the list and the list's length,
+// so that a list cut short by a power loss can be recognised. An empty
+// list is saved as a single record with a length of zero.
+fn encode_waypoint(index: usize, count: usize, waypoint: &Waypoint) -> [u8; PAYLOAD_SIZE] {
+    let mut p = [0xff; PAYLOAD_SIZE];
+    p[0] = index as u8;
+    p[1] = count as u8;
+    p[2..2 + NAME_LEN].copy_from_slice(waypoint.name_bytes());
+    p[10..14].copy_from_slice(&waypoint.latitude_raw.to_le_bytes());
+    p[14..18].copy_from_slice(&waypoint.longitude_raw.to_le_bytes());
+    p
+}
+
+fn decode_waypoint(p: &[u8]) -> Waypoint {
+    let raw = |b: &[u8]| i32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+    Waypoint::from_name_bytes(&p[2..2 + NAME_LEN], raw(&p[10..14]), raw(&p[14..18]))
+}
+
+pub struct SettingsStore<F: Flash> {
+    flash: F,
+    // The next record to write, across all sectors
+    next: usize,
+}
+
+impl<F: Flash> SettingsStore<F> {
+    pub fn open(flash: F) -> Self {
+        let mut store = SettingsStore { flash, next: 0 };
+        // Records are written in order, so the first erased one is the end
+        while store.next < store.capacity() && !store.is_erased(store.next) {
+            store.next += 1;
+        }
+        store
+    }
+
+    /// The most recently saved settings, or the defaults
+    pub fn load(&self) -> Settings {
+        self.latest_settings().unwrap_or_default()
+    }
+
+    pub fn save(&mut self, settings: &Settings) -> Result<(), F::Error> {
+        if self.load() == *settings {
+            return Ok(());
+        }
+        if self.next == self.capacity() {
+            let waypoints = self.load_waypoints();
+            self.erase()?;
+            if !waypoints.is_empty() {
+                self.append_waypoints(&waypoints)?;
+            }
+        }
+        self.append(RECORD_MAGIC, &settings.encode())
+    }
+
+    /// The most recently saved waypoints, or none
+    pub fn load_waypoints(&self) -> Waypoints {
+        (0..self.next)
+            .rev()
+            .find_map(|i| self.read_waypoints(i))
+            .unwrap_or_else(Waypoints::new)
+    }
+
+    pub fn save_waypoints(&mut self, waypoints: &Waypoints) -> Result<(), F::Error> {
+        if self.load_waypoints() == *waypoints {
+            return Ok(());
+        }
+        if self.next + waypoints.len().max(1) > self.capacity() {
+            let settings = self.latest_settings();
+            self.erase()?;
+            if let Some(settings) = settings {
+                self.append(RECORD_MAGIC, &settings.encode())?;
+            }
+        }
+        self.append_waypoints(waypoints)
+    }
+
+    fn latest_settings(&self) -> Option<Settings> {
+        (0..self.next)
+            .rev()
+            .find_map(|i| self.read(i, RECORD_MAGIC))
+            .map(|p| Settings::decode(&p))
+    }
+
+    // The list whose last record is `last`, if it's all there
+    fn read_waypoints(&self, last: usize) -> Option<Waypoints> {
+        let p = self.read(last, WAYPOINT_MAGIC)?;
+        let count = p[1] as usize;
+        if p[0] as usize + 1 != count.max(1) || count > MAX_WAYPOINTS || count > last + 1 {
+            return None;
+        }
+        let mut waypoints = Waypoints::new();
+        for (index, i) in (last + 1 - count..=last).enumerate() {
+            let p = self.read(i, WAYPOINT_MAGIC)?;
+            if p[0] as usize != index || p[1] as usize != count {
+                return None;
+            }
+            waypoints.push(decode_waypoint(&p)).ok()?;
+        }
+        Some(waypoints)
+    }
+
+    fn append_waypoints(&mut self, waypoints: &Waypoints) -> Result<(), F::Error> {
+        let count = waypoints.len();
+        if count == 0 {
+            let empty = Waypoint::new("", 0, 0);
+            return self.append(WAYPOINT_MAGIC, &encode_waypoint(0, 0, &empty));
+        }
+        for (index, waypoint) in waypoints.as_slice().iter().enumerate() {
+            self.append(WAYPOINT_MAGIC, &encode_waypoint(index, count, waypoint))?;
+        }
+        Ok(())
+    }
+
+    fn append(&mut self, magic: u16, payload: &[u8; PAYLOAD_SIZE]) -> Result<(), F::Error> {
+        let mut record = [0u8; RECORD_SIZE];
+        record[0..2].copy_from_slice(&magic.to_le_bytes());
+        record[2..2 + PAYLOAD_SIZE].copy_from_slice(payload);
+        let (ck_a, ck_b) = checksum(&record[0..RECORD_SIZE - 2]);
+        record[RECORD_SIZE - 2] = ck_a;
+        record[RECORD_SIZE - 1] = ck_b;
+
+        let offset = self.next * RECORD_SIZE;
+        self.next += 1;
+        self.flash.program(offset, &record)
+    }
+
+    fn erase(&mut self) -> Result<(), F::Error> {
+        for sector in 0..self.flash.sector_count() {
+            self.flash.erase_sector(sector)?;
+        }
+        self.next = 0;
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.flash.sector_count() * self.flash.sector_size() / RECORD_SIZE
+    }
+
+    fn read_record(&self, i: usize) -> [u8; RECORD_SIZE] {
+        let mut record = [0u8; RECORD_SIZE];
+        self.flash.read(i * RECORD_SIZE, &mut record);
+        record
+    }
+
+    fn is_erased(&self, i: usize) -> bool {
+        self.read_record(i).iter().all(|b| *b == 0xff)
+    }
+
+    // The payload of record `i`, if it's valid and of the kind `magic`
+    fn read(&self, i: usize, magic: u16) -> Option<[u8; PAYLOAD_SIZE]> {
+        let record = self.read_record(i);
+        let ck = checksum(&record[0..RECORD_SIZE - 2]);
+        if u16::from_le_bytes([record[0], record[1]]) == magic
+            && ck == (record[RECORD_SIZE - 2], record[RECORD_SIZE - 1])
+        {
+            let mut payload = [0u8; PAYLOAD_SIZE];
+            payload.copy_from_slice(&record[2..2 + PAYLOAD_SIZE]);
+            Some(payload)
+        } else {
+            None
+        }
+    }
+}
+
+// The same 8 bit fletcher checksum as UBX packets
+fn checksum(bytes: &[u8]) -> (u8, u8) {
+    let mut ck_a: u8 = 0;
+    let mut ck_b: u8 = 0;
+    for b in bytes {
+        ck_a = ck_a.wrapping_add(*b);
+        ck_b = ck_b.wrapping_add(ck_a);
+    }
+    (ck_a, ck_b)
+}
@@ -0,0 +1,86 @@
+//! Distance and time to a race start line, pinged at both ends.
+//!
+//! Looking upwind from behind the line, the pin end is on the left and
+//! the committee boat on the right, which puts the course side ahead.
+
+use crate::gps::{enu_velocity, hypot, metres_per_s_from_knots, LocalProjection};
+
+/// Where the boat is relative to the line
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct LineStatus {
+    /// Perpendicular distance to the line, positive behind it
+    pub distance_m: f32,
+    /// At the current speed and course, if heading for the line
+    pub time_to_line_s: Option<f32>,
+    /// Over the line, on the course side
+    pub course_side: bool,
+}
+
+pub struct StartLine {
+    // Positions in the receiver's units of 1e-7 degrees
+    pin: Option<(i32, i32)>,
+    boat: Option<(i32, i32)>,
+}
+
+impl StartLine {
+    pub fn new() -> Self {
+        StartLine {
+            pin: None,
+            boat: None,
+        }
+    }
+
+    pub fn set_pin(&mut self, position_raw: (i32, i32)) {
+        self.pin = Some(position_raw);
+    }
+
+    pub fn set_boat(&mut self, position_raw: (i32, i32)) {
+        self.boat = Some(position_raw);
+    }
+
+    pub fn has_pin(&self) -> bool {
+        self.pin.is_some()
+    }
+
+    pub fn has_boat(&self) -> bool {
+        self.boat.is_some()
+    }
+
+    /// The status at `position_raw`, moving at `speed` knots on `course`,
+    /// once both ends have been pinged
+    pub fn status(
+        &self,
+        position_raw: (i32, i32),
+        speed: f32,
+        course: Option<f32>,
+    ) -> Option<LineStatus> {
+        let (pin, boat) = (self.pin?, self.boat?);
+        let projection = LocalProjection::new(pin.0, pin.1);
+        let (bx, by) = projection.project(boat.0, boat.1);
+        let length = hypot(bx, by);
+        if length < 1.0 {
+            // Both ends pinged in the same place
+            return None;
+        }
+        // The unit normal to the line, pointing to the course side
+        let (nx, ny) = (-by / length, bx / length);
+
+        let (px, py) = projection.project(position_raw.0, position_raw.1);
+        let distance_m = -(px * nx + py * ny);
+
+        let time_to_line_s = course.and_then(|course| {
+            let (ve, vn) = enu_velocity(metres_per_s_from_knots(speed), course);
+            let closing = ve * nx + vn * ny;
+            if distance_m > 0.0 && closing > 0.0 {
+                Some(distance_m / closing)
+            } else {
+                None
+            }
+        });
+        Some(LineStatus {
+            distance_m,
+            time_to_line_s,
+            course_side: distance_m < 0.0,
+        })
+    }
+}
@@ -0,0 +1,120 @@
+//! The countdown to a sailing race start.
+//!
+//! Races are started with a five minute sequence: the warning signal at
+//! five minutes, the preparatory signal at four, its removal at one, and
+//! the start at zero. The timer is started at the warning signal, and
+//! synced to the nearest minute at any of the later ones.
+
+use crate::gps::GpsTime;
+use crate::track_log::seconds_since_2000;
+
+const MINUTE_MS: u64 = 60 * 1000;
+
+/// The length of the start sequence
+pub const SEQUENCE_MS: u64 = 5 * MINUTE_MS;
+
+/// Milliseconds for timing the start. The clock runs from the monotonic
+/// timer, and is corrected to GPS time whenever a new GPS second is seen,
+/// so it carries on smoothly when the fix comes and goes.
+pub struct RaceClock {
+    mono_ms: u64,
+    // The GPS and monotonic times of the first GPS second seen
+    base: Option<(u64, u64)>,
+    last_gps_s: Option<u32>,
+    correction_ms: i64,
+}
+
+impl RaceClock {
+    pub fn new() -> Self {
+        RaceClock {
+            mono_ms: 0,
+            base: None,
+            last_gps_s: None,
+            correction_ms: 0,
+        }
+    }
+
+    /// Advance to the monotonic time `mono_ms`
+    pub fn tick(&mut self, mono_ms: u64) {
+        self.mono_ms = mono_ms;
+    }
+
+    /// Correct the clock to a GPS time received at the last tick
+    pub fn update_gps(&mut self, time: &GpsTime) {
+        // Without the date the receiver has no idea of the time either
+        let s = match seconds_since_2000(time) {
+            Some(s) => s,
+            None => return,
+        };
+        // The first report of a second comes closest to its start
+        if self.last_gps_s == Some(s) {
+            return;
+        }
+        self.last_gps_s = Some(s);
+        let gps_ms = s as u64 * 1000;
+        match self.base {
+            None => self.base = Some((gps_ms, self.mono_ms)),
+            Some((gps0, mono0)) => {
+                self.correction_ms =
+                    (gps_ms as i64 - gps0 as i64) - (self.mono_ms as i64 - mono0 as i64);
+            }
+        }
+    }
+
+    pub fn now_ms(&self) -> u64 {
+        (self.mono_ms as i64 + self.correction_ms).max(0) as u64
+    }
+}
+
+pub struct StartTimer {
+    // The time of the start, by the race clock
+    gun_ms: Option<u64>,
+}
+
+impl StartTimer {
+    pub fn new() -> Self {
+        StartTimer { gun_ms: None }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.gun_ms.is_some()
+    }
+
+    /// Start the countdown at the warning signal, or if it's running, sync
+    /// it to the signal just made by rounding to the nearest minute
+    pub fn start_or_sync(&mut self, now_ms: u64) {
+        let remaining_ms = match self.remaining_ms(now_ms) {
+            Some(ms) => (ms + MINUTE_MS / 2) / MINUTE_MS * MINUTE_MS,
+            None => SEQUENCE_MS,
+        };
+        self.gun_ms = Some(now_ms + remaining_ms);
+    }
+
+    /// The time to the start, if the countdown is running
+    pub fn remaining_ms(&self, now_ms: u64) -> Option<u64> {
+        self.gun_ms.map(|gun_ms| gun_ms.saturating_sub(now_ms))
+    }
+
+    /// True once, when the countdown reaches the start. The timer is then
+    /// stopped, ready for the next race.
+    pub fn gun(&mut self, now_ms: u64) -> bool {
+        match self.gun_ms {
+            Some(gun_ms) if now_ms >= gun_ms => {
+                self.gun_ms = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The name of the next signal, with `remaining_ms` to the start
+pub fn next_signal(remaining_ms: u64) -> &'static str {
+    if remaining_ms > 4 * MINUTE_MS {
+        "preparatory"
+    } else if remaining_ms > MINUTE_MS {
+        "one minute"
+    } else {
+        "start"
+    }
+}
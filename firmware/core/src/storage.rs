@@ -0,0 +1,70 @@
+/// A region of NOR flash, made up of equally sized sectors. Offsets are
+/// relative to the start of the region. As with real flash, erasing sets
+/// every byte of a sector to 0xff, and programming can only clear bits.
+pub trait Flash {
+    type Error;
+
+    fn sector_size(&self) -> usize;
+
+    fn sector_count(&self) -> usize;
+
+    fn read(&self, offset: usize, buf: &mut [u8]);
+
+    fn erase_sector(&mut self, sector: usize) -> Result<(), Self::Error>;
+
+    fn program(&mut self, offset: usize, data: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// Flash emulated in RAM, for tests and for reading dumps of the
+/// device's flash on the host.
+pub struct RamFlash<'a> {
+    mem: &'a mut [u8],
+    sector_size: usize,
+}
+
+impl<'a> RamFlash<'a> {
+    /// `mem` is taken as is, so pass it filled with 0xff for erased flash.
+    pub fn new(mem: &'a mut [u8], sector_size: usize) -> Self {
+        assert_eq!(mem.len() % sector_size, 0);
+        RamFlash { mem, sector_size }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.mem
+    }
+
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        self.mem
+    }
+}
+
+impl Flash for RamFlash<'_> {
+    type Error = core::convert::Infallible;
+
+    fn sector_size(&self) -> usize {
+        self.sector_size
+    }
+
+    fn sector_count(&self) -> usize {
+        self.mem.len() / self.sector_size
+    }
+
+    fn read(&self, offset: usize, buf: &mut [u8]) {
+        buf.copy_from_slice(&self.mem[offset..offset + buf.len()]);
+    }
+
+    fn erase_sector(&mut self, sector: usize) -> Result<(), Self::Error> {
+        let start = sector * self.sector_size;
+        for b in self.mem[start..start + self.sector_size].iter_mut() {
+            *b = 0xff;
+        }
+        Ok(())
+    }
+
+    fn program(&mut self, offset: usize, data: &[u8]) -> Result<(), Self::Error> {
+        for (b, d) in self.mem[offset..offset + data.len()].iter_mut().zip(data) {
+            *b &= *d;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,437 @@
+//! A log of fixes, kept in a ring of flash sectors.
+//!
+//! Each sector starts with a header holding a sequence number, so that
+//! the newest sector can be found again after a reset. Fixes are stored
+//! as fixed size, checksummed records, written in order after the header.
+//! The first erased record in the newest sector is the write pointer, and
+//! a record torn by a power loss fails its checksum and is skipped on
+//! reading. When the log is full, the oldest sector is erased and reused.
+//!
+//! Every boot starts a new session, and `start_session` starts another.
+
+use crate::gps::GpsTime;
+use crate::storage::Flash;
+
+pub const RECORD_SIZE: usize = 24;
+
+const HEADER_SIZE: usize = 8;
+const SECTOR_MAGIC: u32 = 0x314b_5254; // "TRK1"
+
+const FLAG_SESSION_START: u8 = 0x01;
+
+/// A single logged fix
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TrackPoint {
+    /// Seconds since 2000-01-01 00:00:00 UTC
+    pub time_s: u32,
+    pub time_ms: u16,
+    pub sats: u8,
+    /// Set on the first point of each session. Assigned by the log.
+    pub session_start: bool,
+    /// Degrees, scaled by 1e7 as reported by the receiver
+    pub latitude_raw: i32,
+    pub longitude_raw: i32,
+    pub speed_mm_s: u16,
+    /// Hundredths of a degree
+    pub course_cdeg: u16,
+    pub speed_acc_mm_s: u16,
+}
+
+impl TrackPoint {
+    pub fn time(&self) -> GpsTime {
+        time_from_seconds_since_2000(self.time_s)
+    }
+
+    pub fn encode(&self) -> [u8; RECORD_SIZE] {
+        let mut r = [0u8; RECORD_SIZE];
+        r[0..4].copy_from_slice(&self.time_s.to_le_bytes());
+        r[4..6].copy_from_slice(&self.time_ms.to_le_bytes());
+        r[6] = self.sats;
+        r[7] = if self.session_start {
+            FLAG_SESSION_START
+        } else {
+            0
+        };
+        r[8..12].copy_from_slice(&self.latitude_raw.to_le_bytes());
+        r[12..16].copy_from_slice(&self.longitude_raw.to_le_bytes());
+        r[16..18].copy_from_slice(&self.speed_mm_s.to_le_bytes());
+        r[18..20].copy_from_slice(&self.course_cdeg.to_le_bytes());
+        r[20..22].copy_from_slice(&self.speed_acc_mm_s.to_le_bytes());
+        let (ck_a, ck_b) = checksum(&r[0..22]);
+        r[22] = ck_a;
+        r[23] = ck_b;
+        r
+    }
+
+    /// Returns None for erased or corrupt records
+    pub fn decode(r: &[u8; RECORD_SIZE]) -> Option<TrackPoint> {
+        if is_erased(r) || checksum(&r[0..22]) != (r[22], r[23]) {
+            return None;
+        }
+        let u16_at = |i: usize| u16::from_le_bytes([r[i], r[i + 1]]);
+        let u32_at = |i: usize| u32::from_le_bytes([r[i], r[i + 1], r[i + 2], r[i + 3]]);
+        Some(TrackPoint {
+            time_s: u32_at(0),
+            time_ms: u16_at(4),
+            sats: r[6],
+            session_start: r[7] & FLAG_SESSION_START != 0,
+            latitude_raw: u32_at(8) as i32,
+            longitude_raw: u32_at(12) as i32,
+            speed_mm_s: u16_at(16),
+            course_cdeg: u16_at(18),
+            speed_acc_mm_s: u16_at(20),
+        })
+    }
+}
+
+// The same 8 bit fletcher checksum as UBX packets
+fn checksum(bytes: &[u8]) -> (u8, u8) {
+    let mut ck_a: u8 = 0;
+    let mut ck_b: u8 = 0;
+    for b in bytes {
+        ck_a = ck_a.wrapping_add(*b);
+        ck_b = ck_b.wrapping_add(ck_a);
+    }
+    (ck_a, ck_b)
+}
+
+fn is_erased(bytes: &[u8]) -> bool {
+    bytes.iter().all(|b| *b == 0xff)
+}
+
+pub struct TrackLog<F: Flash> {
+    flash: F,
+    // The newest sector, its sequence number, and the next record to write
+    sector: usize,
+    seq: u32,
+    next: usize,
+    session_started: bool,
+}
+
+impl<F: Flash> TrackLog<F> {
+    /// Open the log held in `flash`, recovering the write position.
+    pub fn open(flash: F) -> Self {
+        let mut log = TrackLog {
+            flash,
+            sector: 0,
+            seq: 0,
+            next: 0,
+            session_started: false,
+        };
+
+        let mut newest: Option<(usize, u32)> = None;
+        for sector in 0..log.flash.sector_count() {
+            match (log.sector_seq(sector), newest) {
+                (Some(seq), Some((_, nseq))) if seq <= nseq => {}
+                (Some(seq), _) => newest = Some((sector, seq)),
+                (None, _) => {}
+            }
+        }
+
+        match newest {
+            Some((sector, seq)) => {
+                log.sector = sector;
+                log.seq = seq;
+                log.next = log.find_end(sector);
+            }
+            None => {
+                // An empty log. Pretend the last sector is full, so that
+                // writing starts with the first.
+                log.sector = log.flash.sector_count() - 1;
+                log.seq = 0;
+                log.next = log.records_per_sector();
+            }
+        }
+        log
+    }
+
+    pub fn flash(&self) -> &F {
+        &self.flash
+    }
+
+    pub fn records_per_sector(&self) -> usize {
+        (self.flash.sector_size() - HEADER_SIZE) / RECORD_SIZE
+    }
+
+    /// Make the next point appended the start of a new session
+    pub fn start_session(&mut self) {
+        self.session_started = false;
+    }
+
+    pub fn append(&mut self, point: &TrackPoint) -> Result<(), F::Error> {
+        if self.next == self.records_per_sector() {
+            let sector = (self.sector + 1) % self.flash.sector_count();
+            self.start_sector(sector, self.seq + 1)?;
+        }
+
+        let mut point = *point;
+        point.session_start = !self.session_started;
+
+        // Claim the slot before writing, so that a failed write costs
+        // a record rather than being retried over a partial one.
+        let offset = self.record_offset(self.sector, self.next);
+        self.next += 1;
+        self.flash.program(offset, &point.encode())?;
+        self.session_started = true;
+        Ok(())
+    }
+
+    /// Erase every sector, discarding all sessions
+    pub fn clear(&mut self) -> Result<(), F::Error> {
+        for sector in 0..self.flash.sector_count() {
+            self.flash.erase_sector(sector)?;
+        }
+        self.sector = self.flash.sector_count() - 1;
+        self.seq = 0;
+        self.next = self.records_per_sector();
+        self.session_started = false;
+        Ok(())
+    }
+
+    /// All logged points, oldest first
+    pub fn points(&self) -> Points<'_, F> {
+        Points {
+            log: self,
+            cursor: Cursor { step: 0, index: 0 },
+        }
+    }
+
+    /// The logged sessions, oldest first. If the log has wrapped, the
+    /// oldest session may have lost its beginning.
+    pub fn sessions(&self) -> Sessions<'_, F> {
+        Sessions {
+            log: self,
+            cursor: Cursor { step: 0, index: 0 },
+        }
+    }
+
+    fn start_sector(&mut self, sector: usize, seq: u32) -> Result<(), F::Error> {
+        self.flash.erase_sector(sector)?;
+        let base = sector * self.flash.sector_size();
+        // The magic is written last, so that a valid magic implies a
+        // complete header
+        self.flash.program(base + 4, &seq.to_le_bytes())?;
+        self.flash.program(base, &SECTOR_MAGIC.to_le_bytes())?;
+        self.sector = sector;
+        self.seq = seq;
+        self.next = 0;
+        Ok(())
+    }
+
+    fn sector_seq(&self, sector: usize) -> Option<u32> {
+        let mut header = [0u8; HEADER_SIZE];
+        self.flash
+            .read(sector * self.flash.sector_size(), &mut header);
+        let magic = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let seq = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        if magic == SECTOR_MAGIC {
+            Some(seq)
+        } else {
+            None
+        }
+    }
+
+    fn record_offset(&self, sector: usize, index: usize) -> usize {
+        sector * self.flash.sector_size() + HEADER_SIZE + index * RECORD_SIZE
+    }
+
+    fn read_record(&self, sector: usize, index: usize) -> [u8; RECORD_SIZE] {
+        let mut record = [0u8; RECORD_SIZE];
+        self.flash
+            .read(self.record_offset(sector, index), &mut record);
+        record
+    }
+
+    // Records are written in order, so the erased records form a tail
+    // that can be found by bisection.
+    fn find_end(&self, sector: usize) -> usize {
+        let mut lo = 0;
+        let mut hi = self.records_per_sector();
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            if is_erased(&self.read_record(sector, mid)) {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        lo
+    }
+
+    // The first valid point at or after `cursor`, and the cursor following it
+    fn next_point(&self, mut cursor: Cursor) -> Option<(TrackPoint, Cursor)> {
+        let count = self.flash.sector_count();
+        while cursor.step < count {
+            // Sectors are visited oldest first, ending with the newest
+            let sector = (self.sector + 1 + cursor.step) % count;
+            let limit = if self.sector_seq(sector).is_none() {
+                0
+            } else if sector == self.sector {
+                self.next
+            } else {
+                self.records_per_sector()
+            };
+            while cursor.index < limit {
+                let record = self.read_record(sector, cursor.index);
+                cursor.index += 1;
+                if let Some(point) = TrackPoint::decode(&record) {
+                    return Some((point, cursor));
+                }
+            }
+            cursor = Cursor {
+                step: cursor.step + 1,
+                index: 0,
+            };
+        }
+        None
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Cursor {
+    step: usize,
+    index: usize,
+}
+
+pub struct Points<'a, F: Flash> {
+    log: &'a TrackLog<F>,
+    cursor: Cursor,
+}
+
+impl<'a, F: Flash> Iterator for Points<'a, F> {
+    type Item = TrackPoint;
+
+    fn next(&mut self) -> Option<TrackPoint> {
+        let (point, cursor) = self.log.next_point(self.cursor)?;
+        self.cursor = cursor;
+        Some(point)
+    }
+}
+
+pub struct Session<'a, F: Flash> {
+    log: &'a TrackLog<F>,
+    start: Cursor,
+    first: TrackPoint,
+}
+
+impl<'a, F: Flash> Session<'a, F> {
+    pub fn first(&self) -> &TrackPoint {
+        &self.first
+    }
+
+    pub fn points(&self) -> SessionPoints<'a, F> {
+        SessionPoints {
+            points: Points {
+                log: self.log,
+                cursor: self.start,
+            },
+            first: true,
+        }
+    }
+}
+
+pub struct SessionPoints<'a, F: Flash> {
+    points: Points<'a, F>,
+    first: bool,
+}
+
+impl<'a, F: Flash> Iterator for SessionPoints<'a, F> {
+    type Item = TrackPoint;
+
+    fn next(&mut self) -> Option<TrackPoint> {
+        let cursor = self.points.cursor;
+        let point = self.points.next()?;
+        if point.session_start && !self.first {
+            // Stay put at the start of the next session
+            self.points.cursor = cursor;
+            return None;
+        }
+        self.first = false;
+        Some(point)
+    }
+}
+
+pub struct Sessions<'a, F: Flash> {
+    log: &'a TrackLog<F>,
+    cursor: Cursor,
+}
+
+impl<'a, F: Flash> Iterator for Sessions<'a, F> {
+    type Item = Session<'a, F>;
+
+    fn next(&mut self) -> Option<Session<'a, F>> {
+        let start = self.cursor;
+        let (first, mut cursor) = self.log.next_point(start)?;
+
+        // Skip to the start of the following session
+        loop {
+            match self.log.next_point(cursor) {
+                Some((point, next)) if !point.session_start => cursor = next,
+                _ => break,
+            }
+        }
+        self.cursor = cursor;
+
+        Some(Session {
+            log: self.log,
+            start,
+            first,
+        })
+    }
+}
+
+const SECONDS_PER_DAY: u32 = 86400;
+
+// Days from 1970-01-01 to 2000-01-01
+const DAYS_TO_2000: u32 = 10957;
+
+/// None for times before 2000, or too long after it to count in a `u32`
+pub fn seconds_since_2000(time: &GpsTime) -> Option<u32> {
+    let days = days_from_civil(time.year as u32, time.month as u32, time.day as u32)?
+        .checked_sub(DAYS_TO_2000)?;
+    let secs = (time.hour as u32) * 3600 + (time.min as u32) * 60 + time.sec as u32;
+    days.checked_mul(SECONDS_PER_DAY)?.checked_add(secs)
+}
+
+pub fn time_from_seconds_since_2000(secs: u32) -> GpsTime {
+    let (year, month, day) = civil_from_days(secs / SECONDS_PER_DAY + DAYS_TO_2000);
+    let secs = secs % SECONDS_PER_DAY;
+    GpsTime {
+        year: year as u16,
+        month: month as u8,
+        day: day as u8,
+        hour: (secs / 3600) as u8,
+        min: ((secs / 60) % 60) as u8,
+        sec: (secs % 60) as u8,
+    }
+}
+
+// Days since 1970-01-01 of a date from 1970 onwards, after
+// http://howardhinnant.github.io/date_algorithms.html. None for earlier
+// dates, and for day 0.
+fn days_from_civil(year: u32, month: u32, day: u32) -> Option<u32> {
+    let y = if month <= 2 {
+        year.checked_sub(1)?
+    } else {
+        year
+    };
+    let era = y / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = ((153 * mp + 2) / 5 + day).checked_sub(1)?;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    (era * 146097 + doe).checked_sub(719468)
+}
+
+fn civil_from_days(days: u32) -> (u32, u32, u32) {
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
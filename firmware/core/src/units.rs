@@ -0,0 +1,77 @@
+//! Display units. Speeds are kept in knots internally, and converted for
+//! display. The distance unit follows the speed unit.
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SpeedUnit {
+    Knots,
+    Kmh,
+    Mph,
+    MetresPerSecond,
+}
+
+impl SpeedUnit {
+    pub const ALL: [SpeedUnit; 4] = [
+        SpeedUnit::Knots,
+        SpeedUnit::Kmh,
+        SpeedUnit::Mph,
+        SpeedUnit::MetresPerSecond,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SpeedUnit::Knots => "kt",
+            SpeedUnit::Kmh => "km/h",
+            SpeedUnit::Mph => "mph",
+            SpeedUnit::MetresPerSecond => "m/s",
+        }
+    }
+
+    pub fn distance_label(&self) -> &'static str {
+        match self {
+            SpeedUnit::Knots => "nm",
+            SpeedUnit::Kmh => "km",
+            SpeedUnit::Mph => "mi",
+            SpeedUnit::MetresPerSecond => "km",
+        }
+    }
+
+    pub fn from_knots(&self, knots: f32) -> f32 {
+        match self {
+            SpeedUnit::Knots => knots,
+            SpeedUnit::Kmh => knots * 1.852,
+            SpeedUnit::Mph => knots * 1.150_779,
+            SpeedUnit::MetresPerSecond => knots * 0.514_444,
+        }
+    }
+
+    pub fn distance_from_metres(&self, metres: u32) -> f32 {
+        let metres = metres as f32;
+        match self {
+            SpeedUnit::Knots => metres / 1852.0,
+            SpeedUnit::Kmh | SpeedUnit::MetresPerSecond => metres / 1000.0,
+            SpeedUnit::Mph => metres / 1609.344,
+        }
+    }
+
+    pub fn next(&self) -> SpeedUnit {
+        match self {
+            SpeedUnit::Knots => SpeedUnit::Kmh,
+            SpeedUnit::Kmh => SpeedUnit::Mph,
+            SpeedUnit::Mph => SpeedUnit::MetresPerSecond,
+            SpeedUnit::MetresPerSecond => SpeedUnit::Knots,
+        }
+    }
+
+    pub fn to_u8(&self) -> u8 {
+        match self {
+            SpeedUnit::Knots => 0,
+            SpeedUnit::Kmh => 1,
+            SpeedUnit::Mph => 2,
+            SpeedUnit::MetresPerSecond => 3,
+        }
+    }
+
+    pub fn from_u8(v: u8) -> Option<SpeedUnit> {
+        SpeedUnit::ALL.get(v as usize).copied()
+    }
+}
@@ -0,0 +1,35 @@
+//! A millisecond clock from a timer that wraps.
+//!
+//! The monotonic timer counts system clock cycles in 32 bits, so it wraps
+//! every couple of minutes. This counts on through the wraps, as long as
+//! it is read at least once between each.
+
+pub struct Uptime {
+    ticks_per_ms: u32,
+    last_ticks: u32,
+    // Ticks since the last whole millisecond counted
+    part_ticks: u32,
+    ms: u64,
+}
+
+impl Uptime {
+    /// A clock for a timer counting at `hz`, from when it read zero
+    pub const fn new(hz: u32) -> Self {
+        Uptime {
+            ticks_per_ms: hz / 1000,
+            last_ticks: 0,
+            part_ticks: 0,
+            ms: 0,
+        }
+    }
+
+    /// The milliseconds since the timer started, given that it now reads
+    /// `ticks`
+    pub fn now_ms(&mut self, ticks: u32) -> u64 {
+        let elapsed = ticks.wrapping_sub(self.last_ticks) as u64 + self.part_ticks as u64;
+        self.last_ticks = ticks;
+        self.ms += elapsed / self.ticks_per_ms as u64;
+        self.part_ticks = (elapsed % self.ticks_per_ms as u64) as u32;
+        self.ms
+    }
+}
@@ -0,0 +1,267 @@
+//! Waypoints, or marks, and navigation to them.
+//!
+//! The list is uploaded over the serial link as text: a `waypoints` line,
+//! then one `name latitude longitude` line per mark in decimal degrees,
+//! then `end`. Blank lines and lines starting with `#` are ignored.
+//!
+//! ```text
+//! waypoints
+//! top -33.851200 151.234100
+//! gate -33.860150 151.229900
+//! end
+//! ```
+
+use crate::gps::{hypot, LocalProjection};
+use micromath::F32Ext;
+
+pub const MAX_WAYPOINTS: usize = 16;
+
+/// Names longer than this are cut short
+pub const NAME_LEN: usize = 8;
+
+const MAX_LINE: usize = 48;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Waypoint {
+    // Padded with spaces
+    name: [u8; NAME_LEN],
+    /// Degrees, scaled by 1e7 as reported by the receiver
+    pub latitude_raw: i32,
+    pub longitude_raw: i32,
+}
+
+impl Waypoint {
+    pub fn new(name: &str, latitude_raw: i32, longitude_raw: i32) -> Self {
+        let mut padded = [b' '; NAME_LEN];
+        for (p, c) in padded.iter_mut().zip(name.bytes()) {
+            *p = if c.is_ascii_graphic() || c == b' ' {
+                c
+            } else {
+                b'?'
+            };
+        }
+        Waypoint {
+            name: padded,
+            latitude_raw,
+            longitude_raw,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        // Only printable ascii is stored
+        core::str::from_utf8(&self.name).unwrap_or("?").trim_end()
+    }
+
+    pub(crate) fn name_bytes(&self) -> &[u8; NAME_LEN] {
+        &self.name
+    }
+
+    pub(crate) fn from_name_bytes(name: &[u8], latitude_raw: i32, longitude_raw: i32) -> Self {
+        Waypoint::new(
+            core::str::from_utf8(name).unwrap_or("?"),
+            latitude_raw,
+            longitude_raw,
+        )
+    }
+
+    /// Bearing, distance and velocity made good to the waypoint, from
+    /// `position_raw` moving at `speed` knots on `course`
+    pub fn navigate(&self, position_raw: (i32, i32), speed: f32, course: Option<f32>) -> Leg {
+        let projection = LocalProjection::new(position_raw.0, position_raw.1);
+        let (east, north) = projection.project(self.latitude_raw, self.longitude_raw);
+        let bearing = bearing(east, north);
+        Leg {
+            bearing,
+            distance_m: hypot(east, north),
+            vmg: course.map(|course| {
+                let off_rad = (course - bearing) * core::f32::consts::PI / 180.0;
+                speed * off_rad.cos()
+            }),
+        }
+    }
+}
+
+/// The way to a waypoint
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Leg {
+    /// Degrees true
+    pub bearing: f32,
+    pub distance_m: f32,
+    /// Knots toward the waypoint, negative when sailing away from it
+    pub vmg: Option<f32>,
+}
+
+/// The bearing in degrees true of a point `east` and `north` of here
+pub fn bearing(east: f32, north: f32) -> f32 {
+    let degrees = east.atan2(north) * 180.0 / core::f32::consts::PI;
+    if degrees < 0.0 {
+        degrees + 360.0
+    } else {
+        degrees
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Waypoints {
+    points: [Waypoint; MAX_WAYPOINTS],
+    len: usize,
+}
+
+impl Waypoints {
+    pub fn new() -> Self {
+        Waypoints {
+            points: [Waypoint::new("", 0, 0); MAX_WAYPOINTS],
+            len: 0,
+        }
+    }
+
+    /// Add a waypoint, if there's room
+    pub fn push(&mut self, waypoint: Waypoint) -> Result<(), Waypoint> {
+        if self.len == MAX_WAYPOINTS {
+            return Err(waypoint);
+        }
+        self.points[self.len] = waypoint;
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn as_slice(&self) -> &[Waypoint] {
+        &self.points[..self.len]
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl PartialEq for Waypoints {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for Waypoints {}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LoadError {
+    /// The line, counting from 1, couldn't be read
+    BadLine(usize),
+    TooMany,
+}
+
+/// Reads an upload of waypoints from the serial link, a byte at a time
+pub struct WaypointLoader {
+    line: [u8; MAX_LINE],
+    len: usize,
+    line_no: usize,
+    // The upload so far, from the `waypoints` line
+    loading: Option<Waypoints>,
+    error: Option<LoadError>,
+}
+
+impl WaypointLoader {
+    pub fn new() -> Self {
+        WaypointLoader {
+            line: [0; MAX_LINE],
+            len: 0,
+            line_no: 0,
+            loading: None,
+            error: None,
+        }
+    }
+
+    /// Take the next byte, returning the waypoints at the end of an upload
+    pub fn feed(&mut self, byte: u8) -> Option<Result<Waypoints, LoadError>> {
+        match byte {
+            b'\r' => None,
+            b'\n' => {
+                let len = core::mem::replace(&mut self.len, 0);
+                let line = self.line;
+                match line.get(..len).map(core::str::from_utf8) {
+                    Some(Ok(line)) => self.end_of_line(line.trim()),
+                    // Too long, or not text
+                    _ => self.end_of_line("?"),
+                }
+            }
+            _ => {
+                if self.len < MAX_LINE {
+                    self.line[self.len] = byte;
+                }
+                self.len = self.len.saturating_add(1);
+                None
+            }
+        }
+    }
+
+    fn end_of_line(&mut self, line: &str) -> Option<Result<Waypoints, LoadError>> {
+        if line == "waypoints" {
+            self.loading = Some(Waypoints::new());
+            self.line_no = 1;
+            self.error = None;
+            return None;
+        }
+        let waypoints = self.loading.as_mut()?;
+        self.line_no += 1;
+        if line == "end" {
+            let waypoints = self.loading.take().unwrap();
+            return Some(match self.error.take() {
+                Some(error) => Err(error),
+                None => Ok(waypoints),
+            });
+        }
+        if line.is_empty() || line.starts_with('#') || self.error.is_some() {
+            return None;
+        }
+        self.error = match parse_waypoint(line) {
+            Some(waypoint) => waypoints.push(waypoint).err().map(|_| LoadError::TooMany),
+            None => Some(LoadError::BadLine(self.line_no)),
+        };
+        None
+    }
+}
+
+fn parse_waypoint(line: &str) -> Option<Waypoint> {
+    let mut fields = line.split_whitespace();
+    let name = fields.next()?;
+    let latitude_raw = parse_degrees(fields.next()?, 90)?;
+    let longitude_raw = parse_degrees(fields.next()?, 180)?;
+    if fields.next().is_some() {
+        return None;
+    }
+    Some(Waypoint::new(name, latitude_raw, longitude_raw))
+}
+
+/// Decimal degrees to the receiver's units of 1e-7 degrees, without the
+/// rounding that going through f32 would bring
+fn parse_degrees(s: &str, limit: i32) -> Option<i32> {
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(s) => (true, s),
+        None => (false, s),
+    };
+    let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+    let digits = |s: &str| s.bytes().all(|c| c.is_ascii_digit());
+    if whole.is_empty() || whole.len() > 3 || fraction.len() > 7 {
+        return None;
+    }
+    if !digits(whole) || !digits(fraction) {
+        return None;
+    }
+    let whole = whole.parse::<i32>().ok()?;
+    if whole > limit {
+        return None;
+    }
+    let mut raw = whole * 10_000_000;
+    let mut scale = 1_000_000;
+    for c in fraction.bytes() {
+        raw += (c - b'0') as i32 * scale;
+        scale /= 10;
+    }
+    if raw > limit * 10_000_000 {
+        return None;
+    }
+    Some(if negative { -raw } else { raw })
+}
@@ -0,0 +1,56 @@
+use gpstracker_core::anchor::AnchorWatch;
+
+const ANCHOR: (i32, i32) = (-338_500_000, 1_512_100_000);
+
+// Roughly, at the anchor's latitude
+const M_PER_RAW_LAT: f64 = 0.011_119_5;
+
+/// The position `north` metres from the anchor
+fn north(north: f64) -> (i32, i32) {
+    (ANCHOR.0 + (north / M_PER_RAW_LAT) as i32, ANCHOR.1)
+}
+
+#[test]
+fn alarm_outside_the_radius() {
+    let mut watch = AnchorWatch::new();
+    watch.update(Some(north(500.0)));
+    assert!(!watch.is_set());
+    assert_eq!(watch.distance_m(), None);
+    assert!(!watch.is_alarm());
+
+    watch.set(ANCHOR);
+    assert_eq!(watch.radius_m(), 30);
+    watch.update(Some(north(25.0)));
+    assert!((watch.distance_m().unwrap() - 25.0).abs() < 0.5);
+    assert!(!watch.is_alarm());
+    watch.update(Some(north(35.0)));
+    assert!(watch.is_alarm());
+
+    watch.clear();
+    assert!(!watch.is_set());
+    assert!(!watch.is_alarm());
+}
+
+#[test]
+fn losing_the_fix_keeps_the_alarm() {
+    let mut watch = AnchorWatch::new();
+    watch.set(ANCHOR);
+    watch.update(Some(north(40.0)));
+    watch.update(None);
+    assert!(watch.is_alarm());
+}
+
+#[test]
+fn radius_steps_round() {
+    let mut watch = AnchorWatch::new();
+    watch.set(ANCHOR);
+    watch.update(Some(north(40.0)));
+    assert!(watch.is_alarm());
+    watch.next_radius();
+    assert_eq!(watch.radius_m(), 50);
+    assert!(!watch.is_alarm());
+    for _ in 0..3 {
+        watch.next_radius();
+    }
+    assert_eq!(watch.radius_m(), 20);
+}
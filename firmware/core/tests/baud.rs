@@ -0,0 +1,47 @@
+mod common;
+
+use common::{nav_odo, ubx_frame};
+use gpstracker_core::baud::FrameDetector;
+
+const GGA: &[u8] = b"$GPGGA,092750.000,5321.6802,N,00630.3372,W,1,8,1.03,61.7,M,55.2,M,,*76\r\n";
+
+// The number of frames found in `bytes`
+fn frames(detector: &mut FrameDetector, bytes: &[u8]) -> usize {
+    bytes.iter().filter(|b| detector.feed(**b)).count()
+}
+
+#[test]
+fn ubx_and_nmea_frames_are_found() {
+    let mut detector = FrameDetector::new();
+    assert_eq!(frames(&mut detector, &nav_odo(1852)), 1);
+    assert_eq!(frames(&mut detector, GGA), 1);
+    assert_eq!(frames(&mut detector, &ubx_frame(0x0a, 0x04, &[])), 1);
+}
+
+#[test]
+fn bad_checksums_are_not_frames() {
+    let mut detector = FrameDetector::new();
+    let mut odo = nav_odo(1852);
+    let last = odo.len() - 1;
+    odo[last] ^= 1;
+    assert_eq!(frames(&mut detector, &odo), 0);
+
+    let mut gga = GGA.to_vec();
+    gga[10] = b'9';
+    assert_eq!(frames(&mut detector, &gga), 0);
+}
+
+#[test]
+fn frames_are_found_after_garbage() {
+    let mut detector = FrameDetector::new();
+    // Bytes read at the wrong baud rate, including stray sync characters
+    let mut bytes = vec![
+        0x00, 0xb5, 0x13, b'$', 0xfe, 0xb5, 0x62, 0x01, 0xff, 0xff, 0xff,
+    ];
+    assert_eq!(frames(&mut detector, &bytes), 0);
+    detector.reset();
+    bytes.clear();
+    bytes.extend_from_slice(b"$GP*");
+    bytes.extend_from_slice(GGA);
+    assert_eq!(frames(&mut detector, &bytes), 1);
+}
@@ -0,0 +1,107 @@
+//! Helpers for building UBX frames to feed through the parser. The
+//! gpstracker-tools tests use them too.
+#![allow(dead_code)]
+
+pub fn ubx_frame(class: u8, id: u8, payload: &[u8]) -> Vec<u8> {
+    let len = payload.len() as u16;
+    let mut frame = vec![0xb5, 0x62, class, id, len as u8, (len >> 8) as u8];
+    frame.extend_from_slice(payload);
+    let mut ck_a: u8 = 0;
+    let mut ck_b: u8 = 0;
+    for b in &frame[2..] {
+        ck_a = ck_a.wrapping_add(*b);
+        ck_b = ck_b.wrapping_add(ck_a);
+    }
+    frame.push(ck_a);
+    frame.push(ck_b);
+    frame
+}
+
+/// The fields of a NAV-PVT message that the tracker cares about.
+#[derive(Clone)]
+pub struct Pvt {
+    pub itow_ms: u32,
+    pub fix_ok: bool,
+    /// Whether the receiver has the date and time
+    pub time_valid: bool,
+    /// 3 for a 3D fix, 2 for 2D
+    pub fix_type: u8,
+    pub num_satellites: u8,
+    pub lat_deg: f64,
+    pub lon_deg: f64,
+    pub ground_speed_mm_s: u32,
+    pub heading_deg: f64,
+    pub pos_acc_mm: u32,
+    pub speed_acc_mm_s: u32,
+}
+
+impl Pvt {
+    pub fn new() -> Self {
+        Pvt {
+            itow_ms: 0,
+            fix_ok: true,
+            time_valid: true,
+            fix_type: 3,
+            num_satellites: 8,
+            lat_deg: -33.85,
+            lon_deg: 151.21,
+            ground_speed_mm_s: 0,
+            heading_deg: 0.0,
+            pos_acc_mm: 2000,
+            speed_acc_mm_s: 200,
+        }
+    }
+
+    pub fn frame(&self) -> Vec<u8> {
+        let mut p = [0u8; 92];
+        p[0..4].copy_from_slice(&self.itow_ms.to_le_bytes());
+        p[4..6].copy_from_slice(&2022u16.to_le_bytes());
+        p[6] = 6; // month
+        p[7] = 19; // day
+        let secs = self.itow_ms / 1000;
+        p[8] = ((secs / 3600) % 24) as u8;
+        p[9] = ((secs / 60) % 60) as u8;
+        p[10] = (secs % 60) as u8;
+        // Valid date, time, fully resolved
+        p[11] = if self.time_valid { 0x07 } else { 0x00 };
+        p[20] = if self.fix_ok { self.fix_type } else { 0 };
+        p[21] = if self.fix_ok { 0x01 } else { 0x00 }; // gnssFixOK
+        p[23] = self.num_satellites;
+        p[24..28].copy_from_slice(&((self.lon_deg * 1e7).round() as i32).to_le_bytes());
+        p[28..32].copy_from_slice(&((self.lat_deg * 1e7).round() as i32).to_le_bytes());
+        p[40..44].copy_from_slice(&self.pos_acc_mm.to_le_bytes());
+        p[60..64].copy_from_slice(&self.ground_speed_mm_s.to_le_bytes());
+        p[64..68].copy_from_slice(&((self.heading_deg * 1e5).round() as i32).to_le_bytes());
+        p[68..72].copy_from_slice(&self.speed_acc_mm_s.to_le_bytes());
+        ubx_frame(0x01, 0x07, &p)
+    }
+}
+
+pub fn nav_odo(distance_m: u32) -> Vec<u8> {
+    let mut p = [0u8; 20];
+    p[8..12].copy_from_slice(&distance_m.to_le_bytes());
+    p[12..16].copy_from_slice(&distance_m.to_le_bytes());
+    ubx_frame(0x01, 0x09, &p)
+}
+
+pub fn feed(gps: &mut gpstracker_core::gps::Gps, bytes: &[u8]) {
+    for b in bytes {
+        gps.parse_u8(*b);
+    }
+}
+
+/// An ACK-ACK, or an ACK-NAK, for the message `class` and `id`
+pub fn ack(class: u8, id: u8, accepted: bool) -> Vec<u8> {
+    ubx_frame(0x05, if accepted { 0x01 } else { 0x00 }, &[class, id])
+}
+
+/// A CFG-NAV5 message with the given dynamic model, fix mode and static
+/// hold threshold in cm/s
+pub fn cfg_nav5(dyn_model: u8, fix_mode: u8, static_hold_cm_s: u8) -> Vec<u8> {
+    let mut p = [0u8; 36];
+    p[0..2].copy_from_slice(&0xffffu16.to_le_bytes());
+    p[2] = dyn_model;
+    p[3] = fix_mode;
+    p[22] = static_hold_cm_s;
+    ubx_frame(0x06, 0x24, &p)
+}
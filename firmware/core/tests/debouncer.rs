@@ -0,0 +1,125 @@
+use gpstracker_core::debouncer::{Debouncer, GestureConfig, Gestures, Press, Transition};
+
+// Feed `inputs` in every 10ms from `*now_ms`, collecting transitions
+fn run(debounce: &mut Debouncer, now_ms: &mut u32, inputs: &[bool]) -> Vec<Transition> {
+    let mut transitions = Vec::new();
+    for input in inputs {
+        transitions.extend(debounce.next(*input, *now_ms));
+        *now_ms += 10;
+    }
+    transitions
+}
+
+#[test]
+fn initial_state_is_not_a_transition() {
+    let mut debounce = Debouncer::new(10);
+    let mut now = 0;
+    assert_eq!(run(&mut debounce, &mut now, &[true, true, true]), vec![]);
+}
+
+#[test]
+fn stable_input_transitions() {
+    let mut debounce = Debouncer::new(10);
+    let mut now = 0;
+    assert_eq!(
+        run(
+            &mut debounce,
+            &mut now,
+            &[false, true, true, true, false, false]
+        ),
+        vec![Transition::ToHigh, Transition::ToLow]
+    );
+}
+
+#[test]
+fn bounces_are_ignored() {
+    let mut debounce = Debouncer::new(20);
+    let mut now = 0;
+    assert_eq!(
+        run(
+            &mut debounce,
+            &mut now,
+            &[false, true, true, false, true, false, false]
+        ),
+        vec![]
+    );
+    assert_eq!(
+        run(&mut debounce, &mut now, &[true, true, true]),
+        vec![Transition::ToHigh]
+    );
+}
+
+const CONFIG: GestureConfig = GestureConfig {
+    long_ms: 1000,
+    double_ms: 300,
+    repeat_ms: 200,
+};
+
+// Feed `pressed` in every 10ms from `*now_ms` for `ms`, collecting gestures
+fn hold(gestures: &mut Gestures, now_ms: &mut u32, pressed: bool, ms: u32) -> Vec<Press> {
+    let mut presses = Vec::new();
+    for _ in 0..ms / 10 {
+        presses.extend(gestures.next(pressed, *now_ms));
+        *now_ms = now_ms.wrapping_add(10);
+    }
+    presses
+}
+
+fn gestures(config: GestureConfig) -> Gestures {
+    let mut gestures = Gestures::new(Debouncer::new(10), config);
+    gestures.next(false, 0);
+    gestures
+}
+
+#[test]
+fn short_press_after_double_press_window() {
+    let mut g = gestures(CONFIG);
+    let mut now = 10;
+    assert_eq!(hold(&mut g, &mut now, true, 100), vec![]);
+    assert_eq!(hold(&mut g, &mut now, false, 250), vec![]);
+    assert_eq!(hold(&mut g, &mut now, false, 100), vec![Press::Short]);
+}
+
+#[test]
+fn short_press_on_release_without_doubles() {
+    let mut g = gestures(GestureConfig {
+        double_ms: 0,
+        ..CONFIG
+    });
+    let mut now = 10;
+    assert_eq!(hold(&mut g, &mut now, true, 100), vec![]);
+    assert_eq!(hold(&mut g, &mut now, false, 20), vec![Press::Short]);
+}
+
+#[test]
+fn double_press() {
+    let mut g = gestures(CONFIG);
+    let mut now = 10;
+    hold(&mut g, &mut now, true, 100);
+    hold(&mut g, &mut now, false, 100);
+    assert_eq!(hold(&mut g, &mut now, true, 100), vec![Press::Double]);
+    // Nothing more for the rest of the second press
+    assert_eq!(hold(&mut g, &mut now, true, 1500), vec![]);
+    assert_eq!(hold(&mut g, &mut now, false, 1000), vec![]);
+}
+
+#[test]
+fn long_press_then_repeats() {
+    let mut g = gestures(CONFIG);
+    let mut now = 10;
+    assert_eq!(hold(&mut g, &mut now, true, 900), vec![]);
+    assert_eq!(hold(&mut g, &mut now, true, 200), vec![Press::Long]);
+    assert_eq!(
+        hold(&mut g, &mut now, true, 450),
+        vec![Press::Repeat, Press::Repeat]
+    );
+    assert_eq!(hold(&mut g, &mut now, false, 1000), vec![]);
+}
+
+#[test]
+fn gestures_across_clock_wrap() {
+    let mut g = gestures(CONFIG);
+    let mut now = u32::MAX - 505;
+    assert_eq!(hold(&mut g, &mut now, true, 1100), vec![Press::Long]);
+    assert_eq!(hold(&mut g, &mut now, true, 250), vec![Press::Repeat]);
+}
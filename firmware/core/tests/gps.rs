@@ -0,0 +1,336 @@
+mod common;
+
+use common::{feed, nav_odo, Pvt};
+use gpstracker_core::gps::{rotate, AverageBuffer, Gps};
+use gpstracker_core::settings::Settings;
+
+// 10 knots, to within the resolution of the receiver
+const TEN_KNOTS_MM_S: u32 = 5144;
+
+fn assert_near(a: f32, b: f32) {
+    assert!((a - b).abs() < 0.01, "{} != {}", a, b);
+}
+
+#[test]
+fn no_update_until_odometer() {
+    let mut gps = Gps::new();
+    // A new Gps reports its initial state once
+    assert!(gps.take().is_some());
+    assert!(gps.take().is_none());
+
+    feed(&mut gps, &Pvt::new().frame());
+    assert!(gps.take().is_none());
+
+    feed(&mut gps, &nav_odo(0));
+    assert!(gps.take().is_some());
+    assert!(gps.take().is_none());
+}
+
+#[test]
+fn position_and_speed() {
+    let mut gps = Gps::new();
+    let mut pvt = Pvt::new();
+    pvt.ground_speed_mm_s = TEN_KNOTS_MM_S;
+    pvt.heading_deg = 271.5;
+
+    feed(&mut gps, &pvt.frame());
+    feed(&mut gps, &nav_odo(1852));
+    let data = gps.take().unwrap();
+    assert_eq!(data.sat_in_use, 8);
+    assert_near(data.latitude.unwrap(), -33.85);
+    assert_near(data.longitude.unwrap(), 151.21);
+    assert_eq!(data.position_raw, Some((-338_500_000, 1_512_100_000)));
+    assert_near(data.course.unwrap(), 271.5);
+    assert_eq!(data.distance_m, 1852);
+    // The displayed speed is averaged over a second
+    assert_near(data.speed, 5.0);
+
+    feed(&mut gps, &pvt.frame());
+    feed(&mut gps, &nav_odo(1852));
+    let data = gps.take().unwrap();
+    assert_near(data.speed, 10.0);
+    assert_near(data.max_speed, 10.0);
+    assert_near(data.avg_speed, 1.0);
+    assert_near(data.max_avg_speed, 1.0);
+}
+
+#[test]
+fn lost_fix_clears_position() {
+    let mut gps = Gps::new();
+    let mut pvt = Pvt::new();
+    pvt.ground_speed_mm_s = TEN_KNOTS_MM_S;
+    feed(&mut gps, &pvt.frame());
+    feed(&mut gps, &pvt.frame());
+
+    pvt.fix_ok = false;
+    pvt.ground_speed_mm_s = 0;
+    feed(&mut gps, &pvt.frame());
+    feed(&mut gps, &nav_odo(0));
+    let data = gps.take().unwrap();
+    assert!(data.latitude.is_none());
+    assert!(data.longitude.is_none());
+    assert!(data.position_raw.is_none());
+    assert!(data.course.is_none());
+    assert!(data.time.is_none());
+    assert_near(data.speed, 0.0);
+    assert_near(data.max_speed, 10.0);
+}
+
+#[test]
+fn corrupt_frame_is_ignored() {
+    let mut gps = Gps::new();
+    gps.take();
+
+    let mut frame = Pvt::new().frame();
+    let n = frame.len();
+    frame[n - 1] ^= 0xff;
+    feed(&mut gps, &frame);
+    feed(&mut gps, &nav_odo(42));
+    assert_eq!(gps.take().unwrap().distance_m, 42);
+}
+
+#[test]
+fn average_buffer() {
+    let mut avg: AverageBuffer<4> = AverageBuffer::new();
+    assert_near(avg.avg_value(), 0.0);
+    for v in [1.0, 2.0, 3.0, 4.0] {
+        avg.add(v);
+    }
+    assert_near(avg.avg_value(), 2.5);
+    // The oldest sample is replaced
+    avg.add(5.0);
+    assert_near(avg.avg_value(), 3.5);
+}
+
+#[test]
+fn average_buffer_window() {
+    let mut avg: AverageBuffer<8> = AverageBuffer::with_window(2);
+    avg.add(1.0);
+    avg.add(3.0);
+    assert_near(avg.avg_value(), 2.0);
+    avg.add(5.0);
+    assert_near(avg.avg_value(), 4.0);
+
+    // Changing the window starts again
+    avg.set_window(4);
+    assert_eq!(avg.window(), 4);
+    avg.add(4.0);
+    assert_near(avg.avg_value(), 1.0);
+
+    // A long run of samples leaves the average exact
+    let mut avg: AverageBuffer<120> = AverageBuffer::with_window(120);
+    for i in 0..100_000 {
+        avg.add(0.1 + (i % 7) as f32 * 13.7);
+    }
+    for _ in 0..120 {
+        avg.add(12.3);
+    }
+    assert_near(avg.avg_value(), 12.3);
+}
+
+#[test]
+fn averaging_windows_from_settings() {
+    let mut gps = Gps::new();
+    gps.apply_settings(&Settings {
+        smoothing_s: 2,
+        avg_window_s: 30,
+        ..Settings::default()
+    });
+    let mut pvt = Pvt::new();
+    pvt.ground_speed_mm_s = TEN_KNOTS_MM_S;
+    feed(&mut gps, &pvt.frame());
+    feed(&mut gps, &nav_odo(0));
+    let data = gps.take().unwrap();
+    assert_eq!(data.avg_window_s, 30);
+    // One sample of four
+    assert_near(data.speed, 2.5);
+    // One sample of sixty
+    assert_near(data.avg_speed, 10.0 / 60.0);
+}
+
+#[test]
+fn windows_follow_the_measurement_rate() {
+    let mut gps = Gps::new();
+    assert!(!gps.reconfigure_pending());
+    gps.apply_settings(&Settings {
+        rate_hz: 10,
+        smoothing_s: 1,
+        avg_window_s: 10,
+        ..Settings::default()
+    });
+    // The receiver hasn't been found, so gets the rate when it is
+    assert!(!gps.reconfigure_pending());
+    let mut pvt = Pvt::new();
+    pvt.ground_speed_mm_s = TEN_KNOTS_MM_S;
+    feed(&mut gps, &pvt.frame());
+    feed(&mut gps, &nav_odo(0));
+    let data = gps.take().unwrap();
+    // One sample of ten
+    assert_near(data.speed, 1.0);
+    // One sample of a hundred
+    assert_near(data.avg_speed, 0.1);
+}
+
+#[test]
+fn reset_trip() {
+    let mut gps = Gps::new();
+    let mut pvt = Pvt::new();
+    pvt.ground_speed_mm_s = TEN_KNOTS_MM_S;
+    feed(&mut gps, &pvt.frame());
+    feed(&mut gps, &pvt.frame());
+    feed(&mut gps, &nav_odo(5000));
+    assert_near(gps.take().unwrap().max_speed, 10.0);
+
+    gps.reset_trip();
+    let data = gps.take().unwrap();
+    assert_near(data.max_speed, 0.0);
+    assert_near(data.max_avg_speed, 0.0);
+    assert_eq!(data.distance_m, 0);
+
+    // Distance counts on from the receiver's odometer at the reset
+    feed(&mut gps, &nav_odo(5200));
+    assert_eq!(gps.take().unwrap().distance_m, 200);
+}
+
+#[test]
+fn inaccurate_fixes_are_left_out_of_the_speeds() {
+    let mut gps = Gps::new();
+    let mut pvt = Pvt::new();
+    pvt.ground_speed_mm_s = TEN_KNOTS_MM_S;
+    feed(&mut gps, &pvt.frame());
+    feed(&mut gps, &pvt.frame());
+    assert!(gps.take_track_point().is_some());
+
+    // A spike, with the receiver unsure of it
+    let mut spike = pvt.clone();
+    spike.ground_speed_mm_s = 3 * TEN_KNOTS_MM_S;
+    spike.speed_acc_mm_s = 2000;
+    feed(&mut gps, &spike.frame());
+    let mut fix_2d = pvt.clone();
+    fix_2d.fix_type = 2;
+    fix_2d.ground_speed_mm_s = 0;
+    feed(&mut gps, &fix_2d.frame());
+    let mut few_sats = fix_2d.clone();
+    few_sats.fix_type = 3;
+    few_sats.num_satellites = 4;
+    feed(&mut gps, &few_sats.frame());
+    let mut wandering = fix_2d.clone();
+    wandering.fix_type = 3;
+    wandering.pos_acc_mm = 30_000;
+    feed(&mut gps, &wandering.frame());
+    feed(&mut gps, &nav_odo(0));
+    let data = gps.take().unwrap();
+    assert_eq!(data.rejected_samples, 4);
+    assert_near(data.speed, 10.0);
+    assert_near(data.max_speed, 10.0);
+    // Nor are they logged
+    assert!(gps.take_track_point().is_none());
+
+    // Unless the thresholds are loosened
+    gps.apply_settings(&Settings {
+        speed_acc_cm_s: 200,
+        ..Settings::default()
+    });
+    feed(&mut gps, &spike.frame());
+    feed(&mut gps, &nav_odo(0));
+    let data = gps.take().unwrap();
+    assert_eq!(data.rejected_samples, 4);
+    // Averaged with the last good fix
+    assert_near(data.max_speed, 20.0);
+}
+
+#[test]
+fn inaccurate_fixes_break_the_runs() {
+    let mut gps = Gps::new();
+    let mut pvt = Pvt::new();
+    pvt.ground_speed_mm_s = TEN_KNOTS_MM_S;
+    feed(&mut gps, &pvt.frame());
+
+    // Without the fix between them, the good fixes would make a 2 second
+    // run
+    let mut spike = pvt.clone();
+    spike.itow_ms = 1000;
+    spike.speed_acc_mm_s = 2000;
+    feed(&mut gps, &spike.frame());
+    for itow_ms in [2000, 3000] {
+        pvt.itow_ms = itow_ms;
+        feed(&mut gps, &pvt.frame());
+    }
+    feed(&mut gps, &nav_odo(0));
+    let data = gps.take().unwrap();
+    assert_eq!(data.rejected_samples, 1);
+    assert_near(data.speed, 10.0);
+    assert!(data.rankings.peak_2s.is_none());
+}
+
+#[test]
+fn inaccurate_fixes_keep_off_the_track_but_take_their_time() {
+    let mut gps = Gps::new();
+    let mut pvt = Pvt::new();
+    pvt.ground_speed_mm_s = TEN_KNOTS_MM_S;
+    feed(&mut gps, &pvt.frame());
+    pvt.itow_ms = 1000;
+    feed(&mut gps, &pvt.frame());
+
+    // Wandering a kilometre off
+    let mut wandering = pvt.clone();
+    wandering.lat_deg += 0.01;
+    wandering.pos_acc_mm = 30_000;
+    for itow_ms in [2000, 3000, 4000] {
+        wandering.itow_ms = itow_ms;
+        feed(&mut gps, &wandering.frame());
+    }
+    feed(&mut gps, &nav_odo(0));
+    let data = gps.take().unwrap();
+    assert_eq!(data.rejected_samples, 3);
+    assert_eq!(data.track.len(), 1);
+    assert!(data.history.is_empty());
+
+    // The next good fix completes the history's first point, five
+    // seconds on
+    pvt.itow_ms = 5000;
+    feed(&mut gps, &pvt.frame());
+    feed(&mut gps, &nav_odo(0));
+    let data = gps.take().unwrap();
+    assert_eq!(data.track.len(), 1);
+    assert_eq!(data.history.len(), 1);
+    assert_near(data.history.max(), 10.0);
+}
+
+#[test]
+fn track_points() {
+    let mut gps = Gps::new();
+    let mut pvt = Pvt::new();
+    pvt.itow_ms = 36_000_250;
+    pvt.ground_speed_mm_s = TEN_KNOTS_MM_S;
+    pvt.heading_deg = 271.5;
+    feed(&mut gps, &pvt.frame());
+
+    let point = gps.take_track_point().unwrap();
+    assert!(gps.take_track_point().is_none());
+    assert_eq!(point.time().hour, 10);
+    assert_eq!(point.time_ms, 250);
+    assert_eq!(point.sats, 8);
+    assert_eq!(point.latitude_raw, -338_500_000);
+    assert_eq!(point.speed_mm_s, TEN_KNOTS_MM_S as u16);
+    assert_eq!(point.course_cdeg, 27150);
+
+    // Nor before the receiver has the time
+    pvt.time_valid = false;
+    feed(&mut gps, &pvt.frame());
+    assert!(gps.take_track_point().is_none());
+    pvt.time_valid = true;
+
+    // Nothing is logged without a fix
+    pvt.fix_ok = false;
+    feed(&mut gps, &pvt.frame());
+    assert!(gps.take_track_point().is_none());
+}
+
+#[test]
+fn rotate_turns_the_bearing_up() {
+    let (x, y) = rotate(100.0, 0.0, 90.0);
+    assert!(x.abs() < 0.1 && (y - 100.0).abs() < 0.1, "{} {}", x, y);
+    let (x, y) = rotate(0.0, 100.0, 90.0);
+    assert!((x + 100.0).abs() < 0.1 && y.abs() < 0.1, "{} {}", x, y);
+}
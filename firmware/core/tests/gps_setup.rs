@@ -0,0 +1,280 @@
+mod common;
+
+use common::{ack, cfg_nav5, nav_odo};
+use gpstracker_core::gps::Gps;
+use gpstracker_core::gps_setup::{
+    DynModel, GpsPort, GpsStatus, LINK_BAUD, MAX_RETRIES, STATIC_HOLD_CM_S,
+};
+use gpstracker_core::settings::Settings;
+
+const CFG: u8 = 0x06;
+const CFG_PRT: u8 = 0x00;
+const CFG_MSG: u8 = 0x01;
+const CFG_RATE: u8 = 0x08;
+const CFG_NAV5: u8 = 0x24;
+
+// The raw CfgNav5 values for the sea model and automatic 2D/3D fixes
+const SEA: u8 = 5;
+const FIX_AUTO: u8 = 3;
+
+/// Records what the setup asks of the serial port and timer
+#[derive(Default)]
+struct Port {
+    // The class and id of each message sent
+    sent: Vec<(u8, u8)>,
+    baud: u32,
+    timer: Option<(u32, u32)>,
+}
+
+impl GpsPort for Port {
+    fn send(&mut self, bytes: &[u8]) {
+        self.sent.push((bytes[2], bytes[3]));
+    }
+
+    fn set_baud(&mut self, baud: u32) {
+        self.baud = baud;
+    }
+
+    fn start_timer(&mut self, id: u32, ms: u32) {
+        self.timer = Some((id, ms));
+    }
+}
+
+impl Port {
+    fn last_sent(&self) -> (u8, u8) {
+        *self.sent.last().unwrap()
+    }
+
+    fn expire(&mut self, gps: &mut Gps) {
+        let (id, _) = self.timer.take().unwrap();
+        gps.timeout(id, self);
+    }
+}
+
+fn receive(gps: &mut Gps, port: &mut Port, bytes: &[u8]) {
+    for b in bytes {
+        gps.received(*b, port);
+    }
+}
+
+// Start, and find the receiver at its default baud rate
+fn found(gps: &mut Gps, port: &mut Port) {
+    gps.start(port);
+    assert_eq!(port.baud, 9600);
+    assert_eq!(gps.status(), GpsStatus::Searching);
+    receive(gps, port, b"$GPTXT,01,01,02,ANTSTATUS=OK*3B\r\n");
+    assert_eq!(gps.status(), GpsStatus::Configuring);
+    assert_eq!(port.last_sent(), (CFG, CFG_PRT));
+
+    // Left a moment to change baud rate, then asked again
+    port.expire(gps);
+    assert_eq!(port.baud, LINK_BAUD);
+    assert_eq!(port.last_sent(), (CFG, CFG_PRT));
+}
+
+#[test]
+fn setup_steps_through_the_commands() {
+    let mut gps = Gps::new();
+    let mut port = Port::default();
+    found(&mut gps, &mut port);
+
+    receive(&mut gps, &mut port, &ack(CFG, CFG_PRT, true));
+    assert_eq!(port.last_sent(), (CFG, CFG_RATE));
+    // Acks for something else are ignored
+    receive(&mut gps, &mut port, &ack(CFG, CFG_MSG, true));
+    assert_eq!(port.last_sent(), (CFG, CFG_RATE));
+    receive(&mut gps, &mut port, &ack(CFG, CFG_RATE, true));
+    assert_eq!(port.last_sent(), (CFG, CFG_NAV5));
+    // Then the navigation settings are asked for, to check them
+    let sent = port.sent.len();
+    receive(&mut gps, &mut port, &ack(CFG, CFG_NAV5, true));
+    assert_eq!(port.sent.len(), sent + 1);
+    assert_eq!(port.last_sent(), (CFG, CFG_NAV5));
+    receive(
+        &mut gps,
+        &mut port,
+        &cfg_nav5(SEA, FIX_AUTO, STATIC_HOLD_CM_S),
+    );
+    assert_eq!(port.last_sent(), (CFG, CFG_MSG));
+    receive(&mut gps, &mut port, &ack(CFG, CFG_MSG, true));
+    assert_eq!(port.last_sent(), (CFG, CFG_MSG));
+    assert_eq!(gps.status(), GpsStatus::Configuring);
+    receive(&mut gps, &mut port, &ack(CFG, CFG_MSG, true));
+    assert_eq!(gps.status(), GpsStatus::Ready);
+    assert_eq!(gps.take().unwrap().status, GpsStatus::Ready);
+
+    // Once set up, messages are parsed as usual
+    receive(&mut gps, &mut port, &nav_odo(1852));
+    assert_eq!(gps.take().unwrap().distance_m, 1852);
+}
+
+// Set up as far as checking the navigation settings
+fn checking_nav5(gps: &mut Gps, port: &mut Port) {
+    found(gps, port);
+    receive(gps, port, &ack(CFG, CFG_PRT, true));
+    receive(gps, port, &ack(CFG, CFG_RATE, true));
+    receive(gps, port, &ack(CFG, CFG_NAV5, true));
+}
+
+#[test]
+fn navigation_settings_that_didnt_take_are_sent_again() {
+    let mut gps = Gps::new();
+    let mut port = Port::default();
+    checking_nav5(&mut gps, &mut port);
+
+    // Still portable, the receiver's default
+    receive(
+        &mut gps,
+        &mut port,
+        &cfg_nav5(0, FIX_AUTO, STATIC_HOLD_CM_S),
+    );
+    assert_eq!(port.last_sent(), (CFG, CFG_NAV5));
+    receive(&mut gps, &mut port, &ack(CFG, CFG_NAV5, true));
+    receive(
+        &mut gps,
+        &mut port,
+        &cfg_nav5(SEA, FIX_AUTO, STATIC_HOLD_CM_S),
+    );
+    assert_eq!(port.last_sent(), (CFG, CFG_MSG));
+}
+
+#[test]
+fn navigation_settings_that_never_take_are_given_up_on() {
+    let mut gps = Gps::new();
+    let mut port = Port::default();
+    checking_nav5(&mut gps, &mut port);
+
+    for _ in 0..MAX_RETRIES {
+        receive(
+            &mut gps,
+            &mut port,
+            &cfg_nav5(0, FIX_AUTO, STATIC_HOLD_CM_S),
+        );
+        assert_eq!(port.last_sent(), (CFG, CFG_NAV5));
+        receive(&mut gps, &mut port, &ack(CFG, CFG_NAV5, true));
+    }
+    assert_eq!(gps.status(), GpsStatus::Configuring);
+    receive(
+        &mut gps,
+        &mut port,
+        &cfg_nav5(0, FIX_AUTO, STATIC_HOLD_CM_S),
+    );
+    assert_eq!(gps.status(), GpsStatus::Searching);
+    assert_eq!(port.baud, 9600);
+}
+
+#[test]
+fn new_settings_are_sent_once_set_up() {
+    let mut gps = Gps::new();
+    let mut port = Port::default();
+    checking_nav5(&mut gps, &mut port);
+    receive(
+        &mut gps,
+        &mut port,
+        &cfg_nav5(SEA, FIX_AUTO, STATIC_HOLD_CM_S),
+    );
+    receive(&mut gps, &mut port, &ack(CFG, CFG_MSG, true));
+    receive(&mut gps, &mut port, &ack(CFG, CFG_MSG, true));
+    assert_eq!(gps.status(), GpsStatus::Ready);
+
+    gps.apply_settings(&Settings {
+        gps_model: DynModel::Airborne,
+        ..Settings::default()
+    });
+    assert!(gps.reconfigure_pending());
+    receive(&mut gps, &mut port, &nav_odo(0));
+    assert!(!gps.reconfigure_pending());
+    assert_eq!(gps.status(), GpsStatus::Configuring);
+    assert_eq!(port.last_sent(), (CFG, CFG_RATE));
+    receive(&mut gps, &mut port, &ack(CFG, CFG_RATE, true));
+    receive(&mut gps, &mut port, &ack(CFG, CFG_NAV5, true));
+    // Airborne, with under 1g
+    receive(
+        &mut gps,
+        &mut port,
+        &cfg_nav5(6, FIX_AUTO, STATIC_HOLD_CM_S),
+    );
+    receive(&mut gps, &mut port, &ack(CFG, CFG_MSG, true));
+    receive(&mut gps, &mut port, &ack(CFG, CFG_MSG, true));
+    assert_eq!(gps.status(), GpsStatus::Ready);
+}
+
+#[test]
+fn settings_before_setup_are_only_sent_in_it() {
+    let mut gps = Gps::new();
+    let mut port = Port::default();
+    gps.apply_settings(&Settings {
+        gps_model: DynModel::Airborne,
+        ..Settings::default()
+    });
+    assert!(!gps.reconfigure_pending());
+    checking_nav5(&mut gps, &mut port);
+    receive(
+        &mut gps,
+        &mut port,
+        &cfg_nav5(6, FIX_AUTO, STATIC_HOLD_CM_S),
+    );
+    receive(&mut gps, &mut port, &ack(CFG, CFG_MSG, true));
+    receive(&mut gps, &mut port, &ack(CFG, CFG_MSG, true));
+    assert_eq!(gps.status(), GpsStatus::Ready);
+    assert!(!gps.reconfigure_pending());
+}
+
+#[test]
+fn refused_and_unanswered_commands_are_sent_again() {
+    let mut gps = Gps::new();
+    let mut port = Port::default();
+    found(&mut gps, &mut port);
+
+    let sent = port.sent.len();
+    receive(&mut gps, &mut port, &ack(CFG, CFG_PRT, false));
+    assert_eq!(port.sent.len(), sent + 1);
+    assert_eq!(port.last_sent(), (CFG, CFG_PRT));
+    port.expire(&mut gps);
+    assert_eq!(port.sent.len(), sent + 2);
+    assert_eq!(port.last_sent(), (CFG, CFG_PRT));
+
+    // Then given up on, to look for the receiver again
+    for _ in 2..MAX_RETRIES {
+        port.expire(&mut gps);
+    }
+    assert_eq!(gps.status(), GpsStatus::Configuring);
+    port.expire(&mut gps);
+    assert_eq!(gps.status(), GpsStatus::Searching);
+    assert_eq!(port.baud, 9600);
+}
+
+#[test]
+fn silent_receiver_is_not_responding() {
+    let mut gps = Gps::new();
+    let mut port = Port::default();
+    gps.start(&mut port);
+    let mut bauds = vec![port.baud];
+    while gps.status() == GpsStatus::Searching {
+        port.expire(&mut gps);
+        bauds.push(port.baud);
+    }
+    assert_eq!(gps.status(), GpsStatus::NotResponding);
+    assert_eq!(gps.take().unwrap().status, GpsStatus::NotResponding);
+    assert_eq!(bauds, [9600, 115200, 38400, 57600, 19200, 4800, 9600]);
+
+    // Still listening, and found at the link baud rate it goes straight
+    // to setting up
+    port.expire(&mut gps);
+    assert_eq!(port.baud, 115200);
+    receive(&mut gps, &mut port, &nav_odo(0));
+    assert_eq!(gps.status(), GpsStatus::Configuring);
+    assert_eq!(port.last_sent(), (CFG, CFG_PRT));
+}
+
+#[test]
+fn earlier_timers_are_ignored() {
+    let mut gps = Gps::new();
+    let mut port = Port::default();
+    gps.start(&mut port);
+    let (first, _) = port.timer.unwrap();
+    port.expire(&mut gps);
+    assert_eq!(port.baud, 115200);
+    gps.timeout(first, &mut port);
+    assert_eq!(port.baud, 115200);
+}
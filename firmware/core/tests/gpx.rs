@@ -0,0 +1,56 @@
+use gpstracker_core::gpx;
+use gpstracker_core::storage::RamFlash;
+use gpstracker_core::track_log::{TrackLog, TrackPoint};
+
+fn point(i: u32) -> TrackPoint {
+    TrackPoint {
+        // 2022-06-19T14:05:32Z
+        time_s: 708_962_732 + i,
+        time_ms: 500,
+        sats: 9,
+        session_start: false,
+        latitude_raw: -338_500_001,
+        longitude_raw: 1_512_100_000,
+        speed_mm_s: 5144,
+        course_cdeg: 27105,
+        speed_acc_mm_s: 300,
+    }
+}
+
+#[test]
+fn track_point() {
+    let mut out = String::new();
+    gpx::write_point(&mut out, &point(0)).unwrap();
+    assert_eq!(
+        out,
+        concat!(
+            "<trkpt lat=\"-33.8500001\" lon=\"151.2100000\">",
+            "<time>2022-06-19T14:05:32.500Z</time><sat>9</sat>",
+            "<extensions><gpxtpx:TrackPointExtension>",
+            "<gpxtpx:speed>5.144</gpxtpx:speed><gpxtpx:course>271.05</gpxtpx:course>",
+            "</gpxtpx:TrackPointExtension></extensions></trkpt>\n"
+        )
+    );
+}
+
+#[test]
+fn sessions_become_tracks() {
+    let mut mem = vec![0xff; 2 * 4096];
+    let mut log = TrackLog::open(RamFlash::new(&mut mem, 4096));
+    for i in 0..3 {
+        log.append(&point(i)).unwrap();
+    }
+    log.start_session();
+    for i in 10..12 {
+        log.append(&point(i)).unwrap();
+    }
+
+    let mut out = String::new();
+    gpx::write_log(&mut out, &log).unwrap();
+
+    assert!(out.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<gpx version=\"1.1\""));
+    assert!(out.ends_with("</gpx>\n"));
+    assert_eq!(out.matches("<trk>").count(), 2);
+    assert_eq!(out.matches("<trkpt ").count(), 5);
+    assert!(out.contains("<trk><name>2022-06-19T14:05:42.500Z</name><trkseg>\n"));
+}
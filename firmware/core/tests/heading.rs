@@ -0,0 +1,32 @@
+use gpstracker_core::heading::{heading_delta, HeadingReference, Shift, Tack};
+
+#[test]
+fn delta_wraps_through_north() {
+    assert_eq!(heading_delta(350.0, 10.0), 20.0);
+    assert_eq!(heading_delta(10.0, 350.0), -20.0);
+    assert_eq!(heading_delta(90.0, 270.0), 180.0);
+    assert_eq!(heading_delta(270.0, 90.0), 180.0);
+    assert_eq!(heading_delta(45.0, 45.0), 0.0);
+}
+
+#[test]
+fn no_shift_until_the_reference_is_set() {
+    let mut reference = HeadingReference::new();
+    assert_eq!(reference.shift(45.0), None);
+    reference.set(45.0);
+    assert_eq!(reference.shift(46.0), Some(Shift::Steady));
+}
+
+#[test]
+fn lifts_depend_on_the_tack() {
+    let mut reference = HeadingReference::new();
+    reference.set(355.0);
+    assert_eq!(reference.tack(), Tack::Starboard);
+    assert_eq!(reference.shift(5.0), Some(Shift::Lift(10.0)));
+    assert_eq!(reference.shift(345.0), Some(Shift::Header(10.0)));
+
+    reference.change_tack();
+    assert_eq!(reference.tack(), Tack::Port);
+    assert_eq!(reference.shift(5.0), Some(Shift::Header(10.0)));
+    assert_eq!(reference.shift(345.0), Some(Shift::Lift(10.0)));
+}
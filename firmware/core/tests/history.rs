@@ -0,0 +1,78 @@
+use gpstracker_core::history::{
+    axis_scale, zoom_m, SpeedHistory, TrackHistory, HISTORY_LEN, TRACK_LEN,
+};
+
+#[test]
+fn samples_are_averaged_into_points() {
+    let mut history = SpeedHistory::new(2);
+    assert!(history.is_empty());
+    assert!(!history.add(1.0, 500));
+    assert!(!history.add(2.0, 500));
+    assert!(!history.add(3.0, 500));
+    assert!(history.add(6.0, 500));
+    assert_eq!(history.iter().collect::<Vec<_>>(), vec![3.0]);
+    assert_eq!(history.added(), 1);
+    assert_eq!(history.point_s(), 2);
+}
+
+#[test]
+fn points_cover_the_same_time_at_any_rate() {
+    let mut history = SpeedHistory::new(2);
+    for _ in 0..19 {
+        assert!(!history.add(4.0, 100));
+    }
+    assert!(history.add(4.0, 100));
+    assert_eq!(history.iter().collect::<Vec<_>>(), vec![4.0]);
+}
+
+#[test]
+fn oldest_points_are_dropped() {
+    let mut history = SpeedHistory::new(5);
+    for i in 0..HISTORY_LEN + 10 {
+        history.add(i as f32, 5000);
+    }
+    assert_eq!(history.len(), HISTORY_LEN);
+    let points: Vec<f32> = history.iter().collect();
+    assert_eq!(points[0], 10.0);
+    assert_eq!(points[HISTORY_LEN - 1], (HISTORY_LEN + 9) as f32);
+    assert_eq!(history.max(), (HISTORY_LEN + 9) as f32);
+}
+
+#[test]
+fn axis_is_rounded_up_to_a_step() {
+    assert_eq!(axis_scale(0.0), (1, 1));
+    assert_eq!(axis_scale(3.2), (4, 1));
+    assert_eq!(axis_scale(7.0), (8, 2));
+    assert_eq!(axis_scale(23.4), (25, 5));
+    assert_eq!(axis_scale(40.0), (40, 10));
+}
+
+#[test]
+fn breadcrumbs_are_spaced_out() {
+    // About 11m of latitude
+    const STEP: i32 = 1000;
+    let mut track = TrackHistory::new(10.0);
+    assert!(track.add((-338_500_000, 1_512_100_000)));
+    assert!(!track.add((-338_500_000 + STEP / 2, 1_512_100_000)));
+    assert!(track.add((-338_500_000 + STEP, 1_512_100_000)));
+    assert_eq!(track.len(), 2);
+    assert_eq!(track.added(), 2);
+    assert_eq!(track.last(), Some((-338_500_000 + STEP, 1_512_100_000)));
+
+    for i in 0..TRACK_LEN as i32 {
+        track.add((-338_500_000 + (i + 2) * STEP, 1_512_100_000));
+    }
+    assert_eq!(track.len(), TRACK_LEN);
+    assert_eq!(
+        track.iter().next(),
+        Some((-338_500_000 + 2 * STEP, 1_512_100_000))
+    );
+}
+
+#[test]
+fn zoom_reaches_the_furthest_point() {
+    assert_eq!(zoom_m(0.0), 50);
+    assert_eq!(zoom_m(50.0), 50);
+    assert_eq!(zoom_m(120.0), 200);
+    assert_eq!(zoom_m(100_000.0), 20_000);
+}
@@ -0,0 +1,149 @@
+use std::cell::Cell;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use embedded_hal::digital::v2::InputPin;
+use gpstracker_core::debouncer::{GestureConfig, Press};
+use gpstracker_core::input::{Input, Inputs, RotarySwitch, Switch};
+use gpstracker_core::screens::Page;
+
+// A pin for a reed switch, which the test can close
+#[derive(Clone)]
+struct ReedPin(Rc<Cell<bool>>);
+
+impl ReedPin {
+    fn new() -> Self {
+        ReedPin(Rc::new(Cell::new(false)))
+    }
+
+    fn set_closed(&self, closed: bool) {
+        self.0.set(closed);
+    }
+}
+
+impl InputPin for ReedPin {
+    type Error = Infallible;
+
+    fn is_high(&self) -> Result<bool, Infallible> {
+        Ok(!self.0.get())
+    }
+
+    fn is_low(&self) -> Result<bool, Infallible> {
+        Ok(self.0.get())
+    }
+}
+
+const GESTURES: GestureConfig = GestureConfig {
+    long_ms: 1000,
+    double_ms: 0,
+    repeat_ms: 200,
+};
+
+// Poll every 10ms for `ms`, collecting the inputs
+fn poll<const N: usize>(inputs: &mut Inputs<ReedPin, N>, now_ms: &mut u32, ms: u32) -> Vec<Input> {
+    let mut events = Vec::new();
+    for _ in 0..ms / 10 {
+        events.extend(inputs.poll(*now_ms));
+        *now_ms += 10;
+    }
+    events
+}
+
+#[test]
+fn switch_reports_initial_state_then_changes() {
+    let pin = ReedPin::new();
+    let mut switch = Switch::new(pin.clone());
+    assert_eq!(switch.poll(0), Some(false));
+    assert_eq!(switch.poll(10), None);
+
+    pin.set_closed(true);
+    assert_eq!(switch.poll(20), None);
+    assert_eq!(switch.poll(30), Some(true));
+    assert_eq!(switch.poll(40), None);
+}
+
+#[test]
+fn button_presses() {
+    let button = ReedPin::new();
+    let mut inputs: Inputs<ReedPin, 0> = Inputs::new(button.clone(), GESTURES, None, None);
+    let mut now = 0;
+    assert!(!inputs.is_idle());
+    assert_eq!(poll(&mut inputs, &mut now, 100), vec![]);
+    assert!(inputs.is_idle());
+
+    button.set_closed(true);
+    poll(&mut inputs, &mut now, 100);
+    assert!(!inputs.is_idle());
+    button.set_closed(false);
+    assert_eq!(
+        poll(&mut inputs, &mut now, 100),
+        vec![Input::Button(Press::Short)]
+    );
+    assert!(inputs.is_idle());
+}
+
+#[test]
+fn idle_until_switches_settle() {
+    let pin = ReedPin::new();
+    let mut inputs: Inputs<ReedPin, 0> = Inputs::new(
+        ReedPin::new(),
+        GESTURES,
+        None,
+        Some(Switch::new(pin.clone())),
+    );
+    let mut now = 0;
+    poll(&mut inputs, &mut now, 100);
+    assert!(inputs.is_idle());
+
+    pin.set_closed(true);
+    poll(&mut inputs, &mut now, 10);
+    assert!(!inputs.is_idle());
+    poll(&mut inputs, &mut now, 10);
+    assert!(inputs.is_idle());
+}
+
+#[test]
+fn rotary_switch_selects_pages() {
+    let pins = [ReedPin::new(), ReedPin::new(), ReedPin::new()];
+    pins[1].set_closed(true);
+    let rotary = RotarySwitch::new(pins.clone(), [Page::Speed, Page::Cog, Page::Misc]);
+    let mut inputs = Inputs::new(ReedPin::new(), GESTURES, Some(rotary), None);
+    let mut now = 0;
+
+    // The starting position is reported
+    assert_eq!(
+        poll(&mut inputs, &mut now, 100),
+        vec![Input::SelectPage(Page::Cog)]
+    );
+
+    // Nothing happens between positions
+    pins[1].set_closed(false);
+    assert_eq!(poll(&mut inputs, &mut now, 100), vec![]);
+
+    pins[2].set_closed(true);
+    assert_eq!(
+        poll(&mut inputs, &mut now, 100),
+        vec![Input::SelectPage(Page::Misc)]
+    );
+}
+
+#[test]
+fn flip_switch_pauses_logging() {
+    let pin = ReedPin::new();
+    let mut inputs: Inputs<ReedPin, 0> = Inputs::new(
+        ReedPin::new(),
+        GESTURES,
+        None,
+        Some(Switch::new(pin.clone())),
+    );
+    let mut now = 0;
+    assert_eq!(poll(&mut inputs, &mut now, 100), vec![Input::Logging(true)]);
+
+    pin.set_closed(true);
+    assert_eq!(
+        poll(&mut inputs, &mut now, 100),
+        vec![Input::Logging(false)]
+    );
+    pin.set_closed(false);
+    assert_eq!(poll(&mut inputs, &mut now, 100), vec![Input::Logging(true)]);
+}
@@ -0,0 +1,104 @@
+use gpstracker_core::manoeuvre::{Kind, Manoeuvre, ManoeuvreDetector, ManoeuvreStats};
+
+const STEP_MS: u32 = 500;
+
+struct Boat {
+    detector: ManoeuvreDetector,
+    itow: u32,
+    course: f32,
+    speed: f32,
+    found: Vec<Manoeuvre>,
+}
+
+impl Boat {
+    fn new(course: f32, speed: f32) -> Self {
+        Boat {
+            detector: ManoeuvreDetector::new(),
+            itow: 100_000,
+            course,
+            speed,
+            found: Vec::new(),
+        }
+    }
+
+    /// Turn to `course` and change to `speed` evenly over `ms`
+    fn sail(&mut self, ms: u32, course: f32, speed: f32) {
+        let steps = ms / STEP_MS;
+        let turn = (course - self.course) / steps as f32;
+        let change = (speed - self.speed) / steps as f32;
+        for _ in 0..steps {
+            self.course += turn;
+            self.speed += change;
+            self.itow += STEP_MS;
+            let found = self
+                .detector
+                .add(self.itow, self.speed, self.course.rem_euclid(360.0));
+            self.found.extend(found);
+        }
+    }
+}
+
+#[test]
+fn tack_stalls_the_boat() {
+    let mut boat = Boat::new(45.0, 6.0);
+    boat.sail(20_000, 45.0, 6.0);
+    boat.sail(4_000, -45.0, 2.0);
+    boat.sail(8_000, -45.0, 6.0);
+    boat.sail(10_000, -45.0, 6.0);
+
+    assert_eq!(boat.found.len(), 1);
+    let tack = boat.found[0];
+    assert_eq!(tack.kind, Kind::Tack);
+    assert!((tack.turn_deg + 90.0).abs() < 5.0, "{}", tack.turn_deg);
+    assert!((tack.speed_loss - 4.0).abs() < 0.5, "{}", tack.speed_loss);
+    let recover_ms = tack.recover_ms.unwrap();
+    assert!(recover_ms > 8_000 && recover_ms < 12_000, "{}", recover_ms);
+    assert!(tack.distance_lost_m > 5.0, "{}", tack.distance_lost_m);
+}
+
+#[test]
+fn gybe_keeps_its_speed() {
+    let mut boat = Boat::new(135.0, 15.0);
+    boat.sail(20_000, 135.0, 15.0);
+    boat.sail(3_000, 225.0, 12.0);
+    boat.sail(3_000, 225.0, 15.0);
+    boat.sail(10_000, 225.0, 15.0);
+
+    assert_eq!(boat.found.len(), 1);
+    let gybe = boat.found[0];
+    assert_eq!(gybe.kind, Kind::Gybe);
+    assert!((gybe.turn_deg - 90.0).abs() < 5.0, "{}", gybe.turn_deg);
+}
+
+#[test]
+fn small_course_changes_are_not_manoeuvres() {
+    let mut boat = Boat::new(350.0, 6.0);
+    boat.sail(20_000, 350.0, 6.0);
+    boat.sail(3_000, 390.0, 6.0);
+    boat.sail(20_000, 390.0, 6.0);
+    assert!(boat.found.is_empty());
+
+    // Nor are turns too slow to be sailing ones
+    let mut boat = Boat::new(0.0, 6.0);
+    boat.sail(20_000, 0.0, 6.0);
+    boat.sail(60_000, 180.0, 6.0);
+    boat.sail(20_000, 180.0, 6.0);
+    assert!(boat.found.is_empty());
+}
+
+#[test]
+fn stats_count_each_kind() {
+    let mut boat = Boat::new(45.0, 6.0);
+    boat.sail(20_000, 45.0, 6.0);
+    boat.sail(4_000, -45.0, 2.0);
+    boat.sail(20_000, -45.0, 6.0);
+    boat.sail(4_000, 45.0, 2.0);
+    boat.sail(20_000, 45.0, 6.0);
+
+    let mut stats = ManoeuvreStats::default();
+    for m in boat.found.iter() {
+        stats.add(*m);
+    }
+    assert_eq!((stats.tacks, stats.gybes), (2, 0));
+    assert_eq!(stats.last.map(|m| m.turn_deg > 0.0), Some(true));
+}
@@ -0,0 +1,153 @@
+use gpstracker_core::menu::{Action, Item, Menu, Press};
+use gpstracker_core::screens::Page;
+use gpstracker_core::settings::Settings;
+use gpstracker_core::units::SpeedUnit;
+
+fn value(menu: &Menu, item: Item) -> String {
+    let mut s = String::new();
+    menu.write_value(&mut s, item).unwrap();
+    s
+}
+
+// Move the cursor down to `item`
+fn select(menu: &mut Menu, item: Item) {
+    while menu.selected() != item {
+        assert_eq!(menu.press(Press::Short), None);
+    }
+}
+
+#[test]
+fn short_presses_move_down_and_wrap() {
+    let mut menu = Menu::new(Settings::default());
+    for item in Item::ALL {
+        assert_eq!(menu.selected(), item);
+        menu.press(Press::Short);
+    }
+    assert_eq!(menu.selected(), Item::Units);
+}
+
+#[test]
+fn holding_moves_down_and_double_presses_move_up() {
+    let mut menu = Menu::new(Settings::default());
+    menu.press(Press::Repeat);
+    menu.press(Press::Repeat);
+    assert_eq!(menu.selected(), Item::ALL[2]);
+    menu.press(Press::Double);
+    assert_eq!(menu.selected(), Item::ALL[1]);
+    menu.press(Press::Double);
+    menu.press(Press::Double);
+    assert_eq!(menu.selected(), Item::Exit);
+
+    // Holding keeps changing a selected setting
+    select(&mut menu, Item::Units);
+    menu.press(Press::Long);
+    menu.press(Press::Repeat);
+    menu.press(Press::Repeat);
+    assert_eq!(value(&menu, Item::Units), "mph");
+}
+
+#[test]
+fn change_and_confirm_a_setting() {
+    let mut menu = Menu::new(Settings::default());
+    assert_eq!(value(&menu, Item::Units), "kt");
+
+    // Long press to select, short presses to change, long press to confirm
+    assert_eq!(menu.press(Press::Long), None);
+    assert!(menu.editing());
+    menu.press(Press::Short);
+    menu.press(Press::Short);
+    assert_eq!(value(&menu, Item::Units), "mph");
+    menu.press(Press::Long);
+    assert!(!menu.editing());
+
+    // Short presses move down again
+    menu.press(Press::Short);
+    assert_eq!(menu.selected(), Item::ALL[1]);
+
+    select(&mut menu, Item::Exit);
+    let expected = Settings {
+        units: SpeedUnit::Mph,
+        ..Settings::default()
+    };
+    assert_eq!(menu.press(Press::Long), Some(Action::Exit(expected)));
+}
+
+#[test]
+fn values_cycle_through_choices() {
+    let mut menu = Menu::new(Settings::default());
+    menu.press(Press::Long);
+    let mut seen = Vec::new();
+    for _ in 0..5 {
+        seen.push(value(&menu, Item::Units));
+        menu.press(Press::Short);
+    }
+    assert_eq!(seen, ["kt", "km/h", "mph", "m/s", "kt"]);
+
+    select_next_and_edit(&mut menu, Item::Screen);
+    menu.press(Press::Short);
+    assert_eq!(menu.settings().page, Page::Stats);
+
+    select_next_and_edit(&mut menu, Item::Invert);
+    menu.press(Press::Short);
+    assert_eq!(value(&menu, Item::Invert), "on");
+}
+
+#[test]
+fn averaging_windows_cycle_through_choices() {
+    let mut menu = Menu::new(Settings::default());
+    select(&mut menu, Item::Average);
+    menu.press(Press::Long);
+    let mut seen = Vec::new();
+    for _ in 0..4 {
+        seen.push(value(&menu, Item::Average));
+        menu.press(Press::Short);
+    }
+    assert_eq!(seen, ["10s", "30s", "60s", "10s"]);
+
+    select_next_and_edit(&mut menu, Item::Smoothing);
+    menu.press(Press::Short);
+    assert_eq!(value(&menu, Item::Smoothing), "2s");
+    assert_eq!(menu.settings().smoothing_s, 2);
+}
+
+#[test]
+fn rate_cycles_through_choices() {
+    let mut menu = Menu::new(Settings::default());
+    select(&mut menu, Item::Rate);
+    menu.press(Press::Long);
+    assert_eq!(value(&menu, Item::Rate), "2Hz");
+    menu.press(Press::Short);
+    menu.press(Press::Short);
+    menu.press(Press::Short);
+    assert_eq!(value(&menu, Item::Rate), "1Hz");
+
+    select_next_and_edit(&mut menu, Item::Model);
+    assert_eq!(value(&menu, Item::Model), "sea");
+    menu.press(Press::Short);
+    assert_eq!(value(&menu, Item::Model), "portable");
+
+    select_next_and_edit(&mut menu, Item::SpeedAcc);
+    assert_eq!(value(&menu, Item::SpeedAcc), "50cm/s");
+    menu.press(Press::Short);
+    assert_eq!(value(&menu, Item::SpeedAcc), "100cm/s");
+
+    select_next_and_edit(&mut menu, Item::MinSats);
+    menu.press(Press::Short);
+    assert_eq!(value(&menu, Item::MinSats), "6");
+}
+
+fn select_next_and_edit(menu: &mut Menu, item: Item) {
+    menu.press(Press::Long);
+    select(menu, item);
+    menu.press(Press::Long);
+}
+
+#[test]
+fn reset_trip() {
+    let mut menu = Menu::new(Settings::default());
+    select(&mut menu, Item::ResetTrip);
+    assert_eq!(value(&menu, Item::ResetTrip), "");
+    assert_eq!(menu.press(Press::Long), Some(Action::ResetTrip));
+    assert_eq!(value(&menu, Item::ResetTrip), "done");
+    assert!(!menu.editing());
+}
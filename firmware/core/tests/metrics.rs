@@ -0,0 +1,137 @@
+use gpstracker_core::metrics::Metrics;
+
+const KNOT_MM_S: f32 = 514.444;
+const LAT0: f64 = -33.85;
+const LON0: f64 = 151.21;
+
+/// Drives the metrics with 2Hz fixes along a path of straight legs
+struct Boat {
+    metrics: Metrics,
+    itow_ms: u32,
+    east_m: f64,
+    north_m: f64,
+}
+
+impl Boat {
+    fn new() -> Self {
+        Boat {
+            metrics: Metrics::new(),
+            itow_ms: 100_000,
+            east_m: 0.0,
+            north_m: 0.0,
+        }
+    }
+
+    fn fix(&mut self, speed_kt: f32) {
+        let lat = LAT0 + self.north_m / 111_195.0;
+        let lon = LON0 + self.east_m / (111_195.0 * LAT0.to_radians().cos());
+        self.metrics.add(
+            self.itow_ms,
+            (speed_kt * KNOT_MM_S) as u32,
+            (lat * 1e7).round() as i32,
+            (lon * 1e7).round() as i32,
+        );
+    }
+
+    /// Sail for `secs` at `speed_kt` on `course_deg`
+    fn sail(&mut self, secs: u32, speed_kt: f32, course_deg: f64) {
+        if self.itow_ms == 100_000 {
+            self.fix(speed_kt);
+        }
+        let step_m = (speed_kt * KNOT_MM_S) as f64 / 1000.0 * 0.5;
+        for _ in 0..secs * 2 {
+            self.itow_ms += 500;
+            self.east_m += step_m * course_deg.to_radians().sin();
+            self.north_m += step_m * course_deg.to_radians().cos();
+            self.fix(speed_kt);
+        }
+    }
+}
+
+fn assert_near(value: Option<f32>, expected: f32) {
+    let v = value.expect("no value");
+    assert!((v - expected).abs() < 0.05, "{} != {}", v, expected);
+}
+
+#[test]
+fn steady_speed() {
+    let mut boat = Boat::new();
+    boat.sail(1, 10.0, 0.0);
+    assert!(boat.metrics.rankings().peak_2s.is_none());
+    boat.sail(15, 10.0, 0.0);
+
+    let rankings = boat.metrics.rankings();
+    assert_near(rankings.peak_2s, 10.0);
+    // Only one 10 second run fits in 16 seconds
+    assert!(rankings.avg_5x10s.is_none());
+    assert!(rankings.nautical_mile.is_none());
+    assert!(rankings.one_hour.is_none());
+    assert!(rankings.alpha_500.is_none());
+}
+
+#[test]
+fn peak_2s() {
+    let mut boat = Boat::new();
+    boat.sail(10, 10.0, 0.0);
+    boat.sail(2, 25.0, 0.0);
+    boat.sail(10, 10.0, 0.0);
+    assert_near(boat.metrics.rankings().peak_2s, 25.0);
+}
+
+#[test]
+fn five_by_ten_seconds() {
+    let mut boat = Boat::new();
+    for speed in [20.0, 22.0, 24.0, 26.0, 28.0, 15.0].iter() {
+        boat.sail(12, *speed, 0.0);
+        boat.sail(30, 5.0, 0.0);
+    }
+    // The 15 knot run doesn't make the best five
+    assert_near(boat.metrics.rankings().avg_5x10s, 24.0);
+}
+
+#[test]
+fn nautical_mile_and_hour() {
+    let mut boat = Boat::new();
+    boat.sail(600, 15.0, 90.0);
+    boat.sail(3000, 10.0, 90.0);
+    let rankings = boat.metrics.rankings();
+    // A nautical mile at 15 knots takes 4 minutes
+    assert_near(rankings.nautical_mile, 15.0);
+    // The best hour starts at the beginning: 10 minutes at 15 knots, 50 at 10
+    let hour = rankings.one_hour.unwrap();
+    assert!((hour - (15.0 + 5.0 * 10.0) / 6.0).abs() < 0.1, "{}", hour);
+}
+
+#[test]
+fn alpha_500() {
+    let mut boat = Boat::new();
+    // 250m out, a tight gybe, and 250m back alongside
+    boat.sail(10, 10.0, 90.0);
+    boat.sail(24, 20.0, 90.0);
+    boat.sail(2, 10.0, 0.0);
+    boat.sail(24, 20.0, 270.0);
+    boat.sail(10, 10.0, 270.0);
+    let alpha = boat.metrics.rankings().alpha_500.unwrap();
+    assert!(alpha > 17.0 && alpha < 20.0, "{}", alpha);
+}
+
+#[test]
+fn straight_line_is_not_an_alpha() {
+    let mut boat = Boat::new();
+    boat.sail(120, 20.0, 90.0);
+    assert!(boat.metrics.rankings().alpha_500.is_none());
+}
+
+#[test]
+fn lost_fix_breaks_runs() {
+    let mut boat = Boat::new();
+    boat.sail(1, 25.0, 0.0);
+    boat.metrics.interrupt();
+    boat.sail(1, 25.0, 0.0);
+    assert!(boat.metrics.rankings().peak_2s.is_none());
+
+    // A gap in the fixes also breaks them
+    boat.itow_ms += 5000;
+    boat.sail(1, 25.0, 0.0);
+    assert!(boat.metrics.rankings().peak_2s.is_none());
+}
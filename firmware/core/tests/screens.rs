@@ -0,0 +1,468 @@
+use core::convert::Infallible;
+
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+use gpstracker_core::debouncer::Press;
+use gpstracker_core::gps::GpsData;
+use gpstracker_core::gps_setup::GpsStatus;
+use gpstracker_core::heading::Tack;
+use gpstracker_core::history::{SpeedHistory, TrackHistory};
+use gpstracker_core::input::Input;
+use gpstracker_core::manoeuvre::{Kind, Manoeuvre, ManoeuvreStats};
+use gpstracker_core::menu::{Action, Item};
+use gpstracker_core::metrics::Rankings;
+use gpstracker_core::screens::{Page, Screens};
+use gpstracker_core::settings::Settings;
+use gpstracker_core::units::SpeedUnit;
+use gpstracker_core::waypoints::{Waypoint, Waypoints};
+
+/// Counts the pixels drawn, to check what a render touched.
+struct CountingDisplay {
+    drawn: usize,
+}
+
+impl DrawTarget for CountingDisplay {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        self.drawn += pixels.into_iter().count();
+        Ok(())
+    }
+}
+
+impl OriginDimensions for CountingDisplay {
+    fn size(&self) -> Size {
+        Size::new(400, 240)
+    }
+}
+
+fn gps_data(speed: f32) -> GpsData {
+    GpsData {
+        sat_in_use: 7,
+        course: Some(123.0),
+        speed,
+        max_speed: speed,
+        avg_speed: speed,
+        max_avg_speed: speed,
+        avg_window_s: 10,
+        distance_m: 1000,
+        hdop: None,
+        latitude: Some(-33.85),
+        longitude: Some(151.21),
+        position_raw: Some((-338_500_000, 1_512_100_000)),
+        time: None,
+        rankings: Rankings::default(),
+        history: SpeedHistory::new(5),
+        track: TrackHistory::new(10.0),
+        manoeuvres: ManoeuvreStats::default(),
+        units: SpeedUnit::Knots,
+        status: GpsStatus::Ready,
+        rejected_samples: 0,
+    }
+}
+
+/// Keeps what is drawn, to check what a page shows.
+struct ImageDisplay {
+    pixels: Vec<BinaryColor>,
+}
+
+impl ImageDisplay {
+    fn new() -> Self {
+        ImageDisplay {
+            pixels: vec![BinaryColor::Off; 400 * 240],
+        }
+    }
+}
+
+impl DrawTarget for ImageDisplay {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(p, color) in pixels {
+            if (0..400).contains(&p.x) && (0..240).contains(&p.y) {
+                self.pixels[(p.y * 400 + p.x) as usize] = color;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl OriginDimensions for ImageDisplay {
+    fn size(&self) -> Size {
+        Size::new(400, 240)
+    }
+}
+
+// The current page, drawn from scratch
+fn page_image(screens: &mut Screens) -> Vec<BinaryColor> {
+    let mut display = ImageDisplay::new();
+    screens.set_page(screens.page(), &mut display).unwrap();
+    display.pixels
+}
+
+fn render(screens: &mut Screens) -> usize {
+    let mut display = CountingDisplay { drawn: 0 };
+    screens.render(&mut display).unwrap();
+    display.drawn
+}
+
+#[test]
+fn unchanged_screen_draws_nothing() {
+    let mut screens = Screens::new();
+    assert!(render(&mut screens) > 0);
+    assert_eq!(render(&mut screens), 0);
+
+    screens.update_gps(&gps_data(12.3));
+    assert!(render(&mut screens) > 0);
+    assert_eq!(render(&mut screens), 0);
+}
+
+#[test]
+fn every_page_renders() {
+    let mut screens = Screens::new();
+    let data = gps_data(5.5);
+    for _ in 0..10 {
+        let mut display = CountingDisplay { drawn: 0 };
+        screens.next_page(&mut display).unwrap();
+        screens.update_gps(&data);
+        screens.update_vbat(3900);
+        assert!(render(&mut screens) > 0);
+    }
+}
+
+#[test]
+fn changing_units_redraws() {
+    let mut screens = Screens::new();
+    let mut display = CountingDisplay { drawn: 0 };
+    screens.set_page(Page::Stats, &mut display).unwrap();
+    let mut data = gps_data(5.5);
+    screens.update_gps(&data);
+    render(&mut screens);
+    assert_eq!(render(&mut screens), 0);
+
+    data.units = SpeedUnit::Kmh;
+    screens.update_gps(&data);
+    assert!(render(&mut screens) > 0);
+}
+
+#[test]
+fn stats_page_counts_the_manoeuvres() {
+    let mut screens = Screens::new();
+    let mut display = CountingDisplay { drawn: 0 };
+    screens.set_page(Page::Stats, &mut display).unwrap();
+    let mut data = gps_data(5.5);
+    screens.update_gps(&data);
+    render(&mut screens);
+    screens.update_gps(&data);
+    let unchanged = render(&mut screens);
+
+    data.manoeuvres.tacks = 1;
+    screens.update_gps(&data);
+    assert!(render(&mut screens) > unchanged);
+}
+
+#[test]
+fn gps_status_shows_on_the_misc_page() {
+    let mut screens = Screens::new();
+    let mut display = CountingDisplay { drawn: 0 };
+    screens.set_page(Page::Misc, &mut display).unwrap();
+    let mut data = gps_data(5.5);
+    screens.update_gps(&data);
+    render(&mut screens);
+    screens.update_gps(&data);
+    render(&mut screens);
+    assert_eq!(render(&mut screens), 0);
+
+    data.status = GpsStatus::NotResponding;
+    screens.update_gps(&data);
+    assert!(render(&mut screens) > 0);
+}
+
+#[test]
+fn long_press_opens_the_menu() {
+    let mut screens = Screens::new();
+    let mut display = CountingDisplay { drawn: 0 };
+    let settings = Settings::default();
+
+    screens
+        .press(Press::Short, &settings, &mut display)
+        .unwrap();
+    assert_eq!(screens.page(), Page::Stats);
+
+    // Open the menu, and choose the ranking page to start with
+    let mut press = |press| screens.press(press, &settings, &mut display).unwrap();
+    let screen = Item::ALL.iter().position(|i| *i == Item::Screen).unwrap();
+    assert_eq!(press(Press::Long), None);
+    for _ in 0..screen {
+        press(Press::Short);
+    }
+    press(Press::Long);
+    press(Press::Short);
+    press(Press::Short);
+    press(Press::Long);
+    for _ in screen + 1..Item::ALL.len() {
+        press(Press::Short);
+    }
+    let expected = Settings {
+        page: Page::Ranking,
+        ..settings
+    };
+    assert_eq!(press(Press::Long), Some(Action::Exit(expected)));
+    assert_eq!(screens.page(), Page::Ranking);
+}
+
+#[test]
+fn rotary_switch_selects_page_unless_in_menu() {
+    let mut screens = Screens::new();
+    let mut display = CountingDisplay { drawn: 0 };
+    let settings = Settings::default();
+    let mut input = |input| screens.input(input, &settings, &mut display).unwrap();
+
+    input(Input::SelectPage(Page::Cog));
+    input(Input::Button(Press::Long));
+    input(Input::SelectPage(Page::Misc));
+    input(Input::Logging(false));
+    assert_eq!(screens.page(), Page::Cog);
+}
+
+#[test]
+fn start_countdown_turns_to_speed_at_the_gun() {
+    let mut screens = Screens::new();
+    let mut display = CountingDisplay { drawn: 0 };
+    let settings = Settings::default();
+    screens.set_page(Page::Start, &mut display).unwrap();
+
+    screens.tick(1000, &mut display).unwrap();
+    screens
+        .press(Press::Double, &settings, &mut display)
+        .unwrap();
+    assert_eq!(screens.page(), Page::Start);
+
+    // The countdown redraws as it runs
+    render(&mut screens);
+    screens.tick(2000, &mut display).unwrap();
+    assert!(render(&mut screens) > 0);
+
+    screens.tick(300_999, &mut display).unwrap();
+    assert_eq!(screens.page(), Page::Start);
+    screens.tick(301_000, &mut display).unwrap();
+    assert_eq!(screens.page(), Page::Speed);
+}
+
+#[test]
+fn line_page_pings_the_ends() {
+    let mut screens = Screens::new();
+    let mut display = CountingDisplay { drawn: 0 };
+    let settings = Settings::default();
+    screens.set_page(Page::Line, &mut display).unwrap();
+    screens.update_gps(&gps_data(5.5));
+    render(&mut screens);
+
+    // Long and double presses ping the ends rather than open the menu
+    // or turn the page
+    for p in [Press::Double, Press::Long] {
+        let action = screens.press(p, &settings, &mut display).unwrap();
+        assert_eq!(action, None);
+        assert_eq!(screens.page(), Page::Line);
+    }
+
+    screens
+        .press(Press::Short, &settings, &mut display)
+        .unwrap();
+    assert_eq!(screens.page(), Page::Speed);
+}
+
+#[test]
+fn nav_page_steps_through_waypoints() {
+    let mut screens = Screens::new();
+    let mut display = CountingDisplay { drawn: 0 };
+    let settings = Settings::default();
+    let mut waypoints = Waypoints::new();
+    waypoints
+        .push(Waypoint::new("top", -338_400_000, 1_512_100_000))
+        .unwrap();
+    waypoints
+        .push(Waypoint::new("gate", -338_600_000, 1_512_100_000))
+        .unwrap();
+    screens.set_waypoints(waypoints);
+    screens.set_page(Page::Nav, &mut display).unwrap();
+    screens.update_gps(&gps_data(5.5));
+    render(&mut screens);
+
+    // A double press moves on to the next mark, rather than back a page
+    let mut display = CountingDisplay { drawn: 0 };
+    screens
+        .press(Press::Double, &settings, &mut display)
+        .unwrap();
+    assert_eq!(screens.page(), Page::Nav);
+    assert!(display.drawn > 0);
+    screens.update_gps(&gps_data(5.5));
+    assert!(render(&mut screens) > 0);
+}
+
+#[test]
+fn anchor_alarm_flashes_the_display() {
+    let mut screens = Screens::new();
+    let mut display = CountingDisplay { drawn: 0 };
+    let settings = Settings::default();
+    screens.set_page(Page::Anchor, &mut display).unwrap();
+    screens.update_gps(&gps_data(0.5));
+
+    // A double press sets the watch where the boat is
+    screens
+        .press(Press::Double, &settings, &mut display)
+        .unwrap();
+    assert_eq!(screens.page(), Page::Anchor);
+    assert!(!screens.alarm());
+
+    // Dragging 50m is past the default radius
+    let mut gps = gps_data(0.5);
+    gps.position_raw = Some((-338_495_500, 1_512_100_000));
+    screens.update_gps(&gps);
+    assert!(screens.alarm());
+
+    // The display is redrawn whenever it flashes
+    render(&mut screens);
+    let mut display = CountingDisplay { drawn: 0 };
+    screens.tick(500, &mut display).unwrap();
+    assert!(display.drawn > 0);
+    let mut display = CountingDisplay { drawn: 0 };
+    screens.tick(700, &mut display).unwrap();
+    assert_eq!(display.drawn, 0);
+    screens.tick(1000, &mut display).unwrap();
+    assert!(display.drawn > 0);
+
+    // A long press widens the radius, and a double press clears the watch
+    screens.press(Press::Long, &settings, &mut display).unwrap();
+    assert!(!screens.alarm());
+    screens
+        .press(Press::Double, &settings, &mut display)
+        .unwrap();
+    assert!(!screens.alarm());
+    assert_eq!(screens.page(), Page::Anchor);
+}
+
+#[test]
+fn graph_redraws_as_points_are_added() {
+    let mut screens = Screens::new();
+    let mut display = CountingDisplay { drawn: 0 };
+    screens.set_page(Page::Graph, &mut display).unwrap();
+    let mut gps = gps_data(5.5);
+    screens.update_gps(&gps);
+    render(&mut screens);
+
+    // Nothing changes until a point is added
+    screens.update_gps(&gps);
+    assert_eq!(render(&mut screens), 0);
+    gps.history.add(5.5, 5000);
+    gps.history.add(7.0, 5000);
+    screens.update_gps(&gps);
+    assert!(render(&mut screens) > 0);
+}
+
+#[test]
+fn track_page_turns_with_a_double_press() {
+    let mut screens = Screens::new();
+    let mut display = CountingDisplay { drawn: 0 };
+    let settings = Settings::default();
+    screens.set_page(Page::Track, &mut display).unwrap();
+    let mut gps = gps_data(5.5);
+    gps.track.add((-338_500_000, 1_512_100_000));
+    gps.track.add((-338_505_000, 1_512_100_000));
+    screens.update_gps(&gps);
+    render(&mut screens);
+
+    // Nothing changes while the boat stays put
+    screens.update_gps(&gps);
+    assert_eq!(render(&mut screens), 0);
+
+    let mut display = CountingDisplay { drawn: 0 };
+    screens
+        .press(Press::Double, &settings, &mut display)
+        .unwrap();
+    assert_eq!(screens.page(), Page::Track);
+    assert!(display.drawn > 0);
+}
+
+#[test]
+fn track_page_plots_new_breadcrumbs_without_redrawing() {
+    let mut screens = Screens::new();
+    let mut display = CountingDisplay { drawn: 0 };
+    screens.set_page(Page::Track, &mut display).unwrap();
+    let mut gps = gps_data(5.5);
+    gps.track.add((-338_500_000, 1_512_100_000));
+    gps.track.add((-338_505_000, 1_512_100_000));
+    screens.update_gps(&gps);
+    let redrawn = render(&mut screens);
+
+    // Moving on within the map only draws the new breadcrumb and the boat
+    gps.track.add((-338_503_000, 1_512_100_000));
+    gps.position_raw = Some((-338_503_000, 1_512_100_000));
+    screens.update_gps(&gps);
+    let plotted = render(&mut screens);
+    assert!(plotted > 0);
+    assert!(plotted < redrawn / 10);
+}
+
+#[test]
+fn heading_page_sets_the_reference() {
+    let mut screens = Screens::new();
+    let mut display = CountingDisplay { drawn: 0 };
+    let settings = Settings::default();
+    screens.set_page(Page::Heading, &mut display).unwrap();
+    screens.update_gps(&gps_data(5.5));
+    render(&mut screens);
+    let unset = page_image(&mut screens);
+
+    // Double and long presses set the reference and tack, rather than
+    // turn the page or open the menu
+    for p in [Press::Double, Press::Long] {
+        let mut display = CountingDisplay { drawn: 0 };
+        let action = screens.press(p, &settings, &mut display).unwrap();
+        assert_eq!(action, None);
+        assert_eq!(screens.page(), Page::Heading);
+        assert!(display.drawn > 0);
+    }
+
+    // The reference is kept from the course, and shown when the page is
+    // turned back to
+    let reference = screens.heading_reference();
+    assert_eq!(reference.heading(), Some(123.0));
+    assert_eq!(reference.tack(), Tack::Port);
+    screens.set_page(Page::Speed, &mut display).unwrap();
+    screens.set_page(Page::Heading, &mut display).unwrap();
+    assert_ne!(page_image(&mut screens), unset);
+
+    // A lift of 20 degrees on port, from the reference of 123
+    let mut gps = gps_data(5.5);
+    gps.course = Some(103.0);
+    screens.update_gps(&gps);
+    assert!(render(&mut screens) > 0);
+}
+
+#[test]
+fn manoeuvre_page_shows_the_last_one() {
+    let mut screens = Screens::new();
+    let mut display = CountingDisplay { drawn: 0 };
+    screens.set_page(Page::Manoeuvre, &mut display).unwrap();
+    let mut gps = gps_data(5.5);
+    screens.update_gps(&gps);
+    render(&mut screens);
+
+    gps.manoeuvres.add(Manoeuvre {
+        kind: Kind::Tack,
+        turn_deg: -92.0,
+        speed_loss: 3.2,
+        recover_ms: Some(7_500),
+        distance_lost_m: 14.0,
+    });
+    screens.update_gps(&gps);
+    assert!(render(&mut screens) > 0);
+}
@@ -0,0 +1,173 @@
+use gpstracker_core::gps_setup::DynModel;
+use gpstracker_core::screens::Page;
+use gpstracker_core::settings::{Settings, SettingsStore};
+use gpstracker_core::storage::RamFlash;
+use gpstracker_core::units::SpeedUnit;
+use gpstracker_core::waypoints::{Waypoint, Waypoints};
+
+// Room for four records
+const SECTOR_SIZE: usize = 128;
+
+fn with_units(units: SpeedUnit) -> Settings {
+    Settings {
+        units,
+        ..Settings::default()
+    }
+}
+
+#[test]
+fn erased_flash_has_defaults() {
+    let mut mem = vec![0xff; SECTOR_SIZE];
+    let store = SettingsStore::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+    assert_eq!(store.load(), Settings::default());
+    assert_eq!(store.load().units, SpeedUnit::Knots);
+}
+
+#[test]
+fn saved_settings_survive_reopen() {
+    let mut mem = vec![0xff; SECTOR_SIZE];
+    let mut store = SettingsStore::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+    store.save(&with_units(SpeedUnit::Kmh)).unwrap();
+    store.save(&with_units(SpeedUnit::Mph)).unwrap();
+    assert_eq!(store.load().units, SpeedUnit::Mph);
+
+    let store = SettingsStore::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+    assert_eq!(store.load().units, SpeedUnit::Mph);
+}
+
+#[test]
+fn unchanged_settings_are_not_written() {
+    let mut mem = vec![0xff; SECTOR_SIZE];
+    let mut store = SettingsStore::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+    store.save(&Settings::default()).unwrap();
+    store.save(&with_units(SpeedUnit::Kmh)).unwrap();
+    store.save(&with_units(SpeedUnit::Kmh)).unwrap();
+    // Only the change from the defaults was written
+    assert!(mem[..32].iter().any(|b| *b != 0xff));
+    assert!(mem[32..].iter().all(|b| *b == 0xff));
+}
+
+#[test]
+fn full_sector_is_erased_and_reused() {
+    let mut mem = vec![0xff; SECTOR_SIZE];
+    let mut store = SettingsStore::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+    for i in 0..10 {
+        let units = SpeedUnit::ALL[i % SpeedUnit::ALL.len()];
+        store.save(&with_units(units)).unwrap();
+        assert_eq!(store.load().units, units);
+    }
+    // The tenth save is the second record after the erase
+    assert!(mem[64..].iter().all(|b| *b == 0xff));
+    let store = SettingsStore::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+    assert_eq!(store.load().units, SpeedUnit::Kmh);
+}
+
+#[test]
+fn all_fields_round_trip() {
+    let mut mem = vec![0xff; SECTOR_SIZE];
+    let settings = Settings {
+        units: SpeedUnit::MetresPerSecond,
+        smoothing_s: 5,
+        avg_window_s: 60,
+        page: Page::Ranking,
+        invert: true,
+        rate_hz: 10,
+        gps_model: DynModel::Airborne,
+        speed_acc_cm_s: 200,
+        pos_acc_m: 5,
+        min_sats: 8,
+    };
+    let mut store = SettingsStore::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+    store.save(&settings).unwrap();
+    let store = SettingsStore::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+    assert_eq!(store.load(), settings);
+}
+
+#[test]
+fn corrupt_record_falls_back_to_previous() {
+    let mut mem = vec![0xff; SECTOR_SIZE];
+    let mut store = SettingsStore::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+    store.save(&with_units(SpeedUnit::Kmh)).unwrap();
+    store.save(&with_units(SpeedUnit::MetresPerSecond)).unwrap();
+    // An interrupted program leaves some bits still set
+    mem[32 + 2] = 0xff;
+    let store = SettingsStore::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+    assert_eq!(store.load().units, SpeedUnit::Kmh);
+}
+
+#[test]
+fn unit_conversions() {
+    assert_eq!(SpeedUnit::Knots.from_knots(10.0), 10.0);
+    assert!((SpeedUnit::Kmh.from_knots(10.0) - 18.52).abs() < 0.001);
+    assert!((SpeedUnit::Mph.from_knots(10.0) - 11.508).abs() < 0.001);
+    assert!((SpeedUnit::MetresPerSecond.from_knots(10.0) - 5.144).abs() < 0.001);
+
+    assert_eq!(SpeedUnit::Knots.distance_from_metres(3704), 2.0);
+    assert_eq!(SpeedUnit::Kmh.distance_from_metres(2500), 2.5);
+    assert!((SpeedUnit::Mph.distance_from_metres(1609) - 1.0).abs() < 0.001);
+
+    for units in SpeedUnit::ALL {
+        assert_eq!(SpeedUnit::from_u8(units.to_u8()), Some(units));
+    }
+    assert_eq!(SpeedUnit::from_u8(0xff), None);
+    assert_eq!(SpeedUnit::MetresPerSecond.next(), SpeedUnit::Knots);
+}
+
+fn waypoints(n: usize) -> Waypoints {
+    let mut waypoints = Waypoints::new();
+    for i in 0..n {
+        waypoints
+            .push(Waypoint::new("mark", i as i32, -(i as i32)))
+            .unwrap();
+    }
+    waypoints
+}
+
+#[test]
+fn waypoints_survive_reopen() {
+    let mut mem = vec![0xff; SECTOR_SIZE];
+    let mut store = SettingsStore::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+    assert!(store.load_waypoints().is_empty());
+    store.save_waypoints(&waypoints(2)).unwrap();
+    store.save(&with_units(SpeedUnit::Kmh)).unwrap();
+
+    let mut store = SettingsStore::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+    assert_eq!(store.load_waypoints(), waypoints(2));
+    assert_eq!(store.load().units, SpeedUnit::Kmh);
+
+    // Clearing the list is saved too
+    store.save_waypoints(&Waypoints::new()).unwrap();
+    let store = SettingsStore::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+    assert!(store.load_waypoints().is_empty());
+}
+
+#[test]
+fn erasing_keeps_waypoints_and_settings() {
+    let mut mem = vec![0xff; SECTOR_SIZE];
+    let mut store = SettingsStore::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+    store.save_waypoints(&waypoints(2)).unwrap();
+    for i in 0..6 {
+        store
+            .save(&with_units(SpeedUnit::ALL[i % SpeedUnit::ALL.len()]))
+            .unwrap();
+    }
+    assert_eq!(store.load_waypoints(), waypoints(2));
+
+    // Three waypoints don't fit after the settings and the two waypoints
+    store.save_waypoints(&waypoints(3)).unwrap();
+    assert_eq!(store.load_waypoints(), waypoints(3));
+    assert_eq!(store.load().units, SpeedUnit::Kmh);
+}
+
+#[test]
+fn torn_waypoints_fall_back_to_the_last_list() {
+    let mut mem = vec![0xff; SECTOR_SIZE];
+    let mut store = SettingsStore::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+    store.save_waypoints(&waypoints(1)).unwrap();
+    store.save_waypoints(&waypoints(2)).unwrap();
+    // Lose the second record of the new list
+    mem[2 * 32 + 5] = 0;
+
+    let store = SettingsStore::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+    assert_eq!(store.load_waypoints(), waypoints(1));
+}
@@ -0,0 +1,93 @@
+use gpstracker_core::start_line::StartLine;
+
+const PIN: (i32, i32) = (-338_500_000, 1_512_100_000);
+
+// Roughly, at the pin's latitude
+const M_PER_RAW_LAT: f64 = 0.011_119_5;
+const M_PER_RAW_LON: f64 = M_PER_RAW_LAT * 0.830_5;
+
+const TEN_KNOTS: f32 = 10.0;
+const TEN_KNOTS_M_S: f32 = 5.144;
+
+/// The position `east` and `north` metres from the pin
+fn at(east: f64, north: f64) -> (i32, i32) {
+    (
+        PIN.0 + (north / M_PER_RAW_LAT) as i32,
+        PIN.1 + (east / M_PER_RAW_LON) as i32,
+    )
+}
+
+/// A line 100m long, with the course side to the north
+fn line() -> StartLine {
+    let mut line = StartLine::new();
+    line.set_pin(PIN);
+    line.set_boat(at(100.0, 0.0));
+    line
+}
+
+fn assert_near(a: f32, b: f32) {
+    assert!((a - b).abs() < 0.5, "{} != {}", a, b);
+}
+
+#[test]
+fn needs_both_ends() {
+    let mut line = StartLine::new();
+    assert!(line.status(at(0.0, -10.0), 0.0, None).is_none());
+    line.set_boat(at(100.0, 0.0));
+    assert!(line.has_boat() && !line.has_pin());
+    assert!(line.status(at(0.0, -10.0), 0.0, None).is_none());
+    line.set_pin(PIN);
+    assert!(line.status(at(0.0, -10.0), 0.0, None).is_some());
+}
+
+#[test]
+fn distance_behind_the_line() {
+    let status = line().status(at(50.0, -20.0), 0.0, None).unwrap();
+    assert_near(status.distance_m, 20.0);
+    assert!(!status.course_side);
+    assert!(status.time_to_line_s.is_none());
+
+    // Beyond the ends, the distance is still to the line extended
+    let status = line().status(at(-30.0, -20.0), 0.0, None).unwrap();
+    assert_near(status.distance_m, 20.0);
+}
+
+#[test]
+fn over_the_line() {
+    let status = line().status(at(50.0, 5.0), 0.0, None).unwrap();
+    assert_near(status.distance_m, -5.0);
+    assert!(status.course_side);
+}
+
+#[test]
+fn time_to_line() {
+    // Straight at it
+    let status = line()
+        .status(at(50.0, -20.0), TEN_KNOTS, Some(0.0))
+        .unwrap();
+    assert_near(status.time_to_line_s.unwrap(), 20.0 / TEN_KNOTS_M_S);
+
+    // At 60 degrees to the line, closing at half speed
+    let status = line()
+        .status(at(50.0, -20.0), TEN_KNOTS, Some(60.0))
+        .unwrap();
+    assert_near(status.time_to_line_s.unwrap(), 40.0 / TEN_KNOTS_M_S);
+
+    // Sailing away from it, or along it
+    let status = line()
+        .status(at(50.0, -20.0), TEN_KNOTS, Some(180.0))
+        .unwrap();
+    assert!(status.time_to_line_s.is_none());
+    let status = line()
+        .status(at(50.0, -20.0), TEN_KNOTS, Some(90.0))
+        .unwrap();
+    assert!(status.time_to_line_s.is_none());
+}
+
+#[test]
+fn ends_in_one_place() {
+    let mut line = StartLine::new();
+    line.set_pin(PIN);
+    line.set_boat(PIN);
+    assert!(line.status(at(0.0, -10.0), 0.0, None).is_none());
+}
@@ -0,0 +1,95 @@
+use gpstracker_core::gps::GpsTime;
+use gpstracker_core::start_timer::{next_signal, RaceClock, StartTimer};
+
+const MINUTE_MS: u64 = 60_000;
+
+fn time(min: u8, sec: u8) -> GpsTime {
+    GpsTime {
+        year: 2024,
+        month: 3,
+        day: 9,
+        hour: 14,
+        min,
+        sec,
+    }
+}
+
+#[test]
+fn counts_down_five_minutes() {
+    let mut timer = StartTimer::new();
+    assert!(!timer.is_running());
+    assert_eq!(timer.remaining_ms(1000), None);
+
+    timer.start_or_sync(1000);
+    assert_eq!(timer.remaining_ms(1000), Some(5 * MINUTE_MS));
+    assert_eq!(timer.remaining_ms(61_000), Some(4 * MINUTE_MS));
+    assert!(!timer.gun(5 * MINUTE_MS));
+
+    // The gun fires once, and stops the timer
+    assert!(timer.gun(5 * MINUTE_MS + 1000));
+    assert!(!timer.is_running());
+    assert!(!timer.gun(5 * MINUTE_MS + 1500));
+}
+
+#[test]
+fn sync_rounds_to_the_nearest_minute() {
+    let mut timer = StartTimer::new();
+    timer.start_or_sync(0);
+
+    // Late for the preparatory signal
+    timer.start_or_sync(MINUTE_MS + 20_000);
+    assert_eq!(timer.remaining_ms(MINUTE_MS + 20_000), Some(4 * MINUTE_MS));
+
+    // Early for the one minute signal
+    let now = 4 * MINUTE_MS;
+    timer.start_or_sync(now);
+    assert_eq!(timer.remaining_ms(now), Some(MINUTE_MS));
+}
+
+#[test]
+fn signals() {
+    assert_eq!(next_signal(5 * MINUTE_MS), "preparatory");
+    assert_eq!(next_signal(4 * MINUTE_MS), "one minute");
+    assert_eq!(next_signal(MINUTE_MS), "start");
+}
+
+#[test]
+fn clock_runs_without_gps() {
+    let mut clock = RaceClock::new();
+    clock.tick(1234);
+    assert_eq!(clock.now_ms(), 1234);
+}
+
+#[test]
+fn clock_follows_gps_seconds() {
+    let mut clock = RaceClock::new();
+    clock.tick(10_000);
+    clock.update_gps(&time(0, 0));
+    assert_eq!(clock.now_ms(), 10_000);
+
+    // The monotonic clock is 20ms slow by the next GPS second
+    clock.tick(10_980);
+    clock.update_gps(&time(0, 1));
+    assert_eq!(clock.now_ms(), 11_000);
+
+    // Later reports of the same second don't move it
+    clock.tick(11_500);
+    clock.update_gps(&time(0, 1));
+    assert_eq!(clock.now_ms(), 11_520);
+
+    // Without a fix it carries on from the correction
+    clock.tick(20_000);
+    assert_eq!(clock.now_ms(), 20_020);
+}
+
+#[test]
+fn clock_ignores_time_without_date() {
+    let mut clock = RaceClock::new();
+    clock.tick(500);
+    let mut t = time(0, 0);
+    t.year = 0;
+    clock.update_gps(&t);
+    clock.tick(700);
+    clock.update_gps(&time(0, 1));
+    assert_eq!(clock.now_ms(), 700);
+}
@@ -0,0 +1,181 @@
+use gpstracker_core::gps::GpsTime;
+use gpstracker_core::storage::RamFlash;
+use gpstracker_core::track_log::{self, TrackLog, TrackPoint, RECORD_SIZE};
+
+// Small sectors, so that tests wrap the log quickly
+const SECTOR_SIZE: usize = 8 + 4 * RECORD_SIZE;
+const SECTORS: usize = 3;
+
+fn erased() -> Vec<u8> {
+    vec![0xff; SECTOR_SIZE * SECTORS]
+}
+
+fn point(i: u32) -> TrackPoint {
+    TrackPoint {
+        time_s: 1000 + i,
+        time_ms: 500,
+        sats: 9,
+        session_start: false,
+        latitude_raw: -338_500_000 + i as i32,
+        longitude_raw: 1_512_100_000,
+        speed_mm_s: 5144,
+        course_cdeg: 27150,
+        speed_acc_mm_s: 300,
+    }
+}
+
+fn times<F: gpstracker_core::storage::Flash>(log: &TrackLog<F>) -> Vec<u32> {
+    log.points().map(|p| p.time_s - 1000).collect()
+}
+
+#[test]
+fn record_round_trip() {
+    let mut p = point(7);
+    p.session_start = true;
+    assert_eq!(TrackPoint::decode(&p.encode()), Some(p));
+    assert_eq!(TrackPoint::decode(&[0xff; RECORD_SIZE]), None);
+
+    let mut corrupt = p.encode();
+    corrupt[9] ^= 0x10;
+    assert_eq!(TrackPoint::decode(&corrupt), None);
+}
+
+#[test]
+fn append_and_reopen() {
+    let mut mem = erased();
+    {
+        let mut log = TrackLog::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+        assert_eq!(log.points().count(), 0);
+        for i in 0..6 {
+            log.append(&point(i)).unwrap();
+        }
+        assert_eq!(times(&log), vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    // After a reset, writing continues where it left off, in a new session
+    let mut log = TrackLog::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+    assert_eq!(times(&log), vec![0, 1, 2, 3, 4, 5]);
+    log.append(&point(6)).unwrap();
+    assert_eq!(times(&log), vec![0, 1, 2, 3, 4, 5, 6]);
+
+    let sessions: Vec<Vec<u32>> = log
+        .sessions()
+        .map(|s| s.points().map(|p| p.time_s - 1000).collect())
+        .collect();
+    assert_eq!(sessions, vec![vec![0, 1, 2, 3, 4, 5], vec![6]]);
+}
+
+#[test]
+fn oldest_sector_is_reused_when_full() {
+    let mut mem = erased();
+    let mut log = TrackLog::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+    for i in 0..14 {
+        log.append(&point(i)).unwrap();
+    }
+    // Three sectors of four; the first sector was erased for the last two
+    assert_eq!(times(&log), (4..14).collect::<Vec<_>>());
+
+    let log = TrackLog::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+    assert_eq!(times(&log), (4..14).collect::<Vec<_>>());
+
+    // The session lost its first point, but is still returned
+    let sessions: Vec<_> = log.sessions().collect();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].first().time_s, 1004);
+    assert!(!sessions[0].first().session_start);
+}
+
+#[test]
+fn torn_record_is_skipped() {
+    let mut mem = erased();
+    {
+        let mut log = TrackLog::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+        for i in 0..2 {
+            log.append(&point(i)).unwrap();
+        }
+    }
+    // Simulate power loss part way through writing the third record
+    let torn = point(2).encode();
+    let offset = 8 + 2 * RECORD_SIZE;
+    mem[offset..offset + 10].copy_from_slice(&torn[..10]);
+
+    let mut log = TrackLog::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+    assert_eq!(times(&log), vec![0, 1]);
+    log.append(&point(3)).unwrap();
+    assert_eq!(times(&log), vec![0, 1, 3]);
+}
+
+#[test]
+fn explicit_sessions() {
+    let mut mem = erased();
+    let mut log = TrackLog::open(RamFlash::new(&mut mem, SECTOR_SIZE));
+    for i in 0..3 {
+        log.append(&point(i)).unwrap();
+    }
+    log.start_session();
+    for i in 3..5 {
+        log.append(&point(i)).unwrap();
+    }
+
+    let firsts: Vec<u32> = log.sessions().map(|s| s.first().time_s - 1000).collect();
+    assert_eq!(firsts, vec![0, 3]);
+    assert_eq!(log.sessions().nth(1).unwrap().points().count(), 2);
+
+    log.clear().unwrap();
+    assert_eq!(log.points().count(), 0);
+    assert_eq!(log.sessions().count(), 0);
+}
+
+#[test]
+fn time_conversion() {
+    let time = GpsTime {
+        year: 2022,
+        month: 6,
+        day: 19,
+        hour: 14,
+        min: 5,
+        sec: 32,
+    };
+    // 2022-06-19T14:05:32Z is unix time 1655647532
+    let secs = track_log::seconds_since_2000(&time).unwrap();
+    assert_eq!(secs, 1_655_647_532 - 946_684_800);
+    assert!(track_log::time_from_seconds_since_2000(secs) == time);
+
+    let leap_day = GpsTime {
+        year: 2024,
+        month: 2,
+        day: 29,
+        hour: 23,
+        min: 59,
+        sec: 59,
+    };
+    let secs = track_log::seconds_since_2000(&leap_day).unwrap();
+    assert!(track_log::time_from_seconds_since_2000(secs) == leap_day);
+    assert!(
+        track_log::time_from_seconds_since_2000(secs + 1)
+            == GpsTime {
+                year: 2024,
+                month: 3,
+                day: 1,
+                hour: 0,
+                min: 0,
+                sec: 0,
+            }
+    );
+
+    // Before 2000, as the receiver reports without a date
+    let unknown = GpsTime {
+        year: 0,
+        month: 0,
+        day: 0,
+        hour: 0,
+        min: 0,
+        sec: 0,
+    };
+    assert_eq!(track_log::seconds_since_2000(&unknown), None);
+    let last_century = GpsTime {
+        year: 1999,
+        ..leap_day
+    };
+    assert_eq!(track_log::seconds_since_2000(&last_century), None);
+}
@@ -0,0 +1,28 @@
+use gpstracker_core::uptime::Uptime;
+
+const HZ: u32 = 32_000_000;
+const TICKS_PER_MS: u32 = HZ / 1000;
+
+#[test]
+fn counts_milliseconds() {
+    let mut uptime = Uptime::new(HZ);
+    assert_eq!(uptime.now_ms(0), 0);
+    assert_eq!(uptime.now_ms(TICKS_PER_MS - 1), 0);
+    assert_eq!(uptime.now_ms(TICKS_PER_MS), 1);
+    // Parts of a millisecond add up
+    assert_eq!(uptime.now_ms(TICKS_PER_MS * 5 / 2), 2);
+    assert_eq!(uptime.now_ms(TICKS_PER_MS * 3), 3);
+}
+
+#[test]
+fn counts_on_when_the_timer_wraps() {
+    let mut uptime = Uptime::new(HZ);
+    let mut ticks = 0u32;
+    let mut ms = 0;
+    // Ten minutes, in steps of a minute, wrapping the timer four times
+    for _ in 0..10 {
+        ticks = ticks.wrapping_add(60_000 * TICKS_PER_MS);
+        ms = uptime.now_ms(ticks);
+    }
+    assert_eq!(ms, 10 * 60_000);
+}
@@ -0,0 +1,93 @@
+use gpstracker_core::waypoints::{bearing, LoadError, Waypoint, WaypointLoader, MAX_WAYPOINTS};
+
+fn load(text: &str) -> Vec<Result<Vec<Waypoint>, LoadError>> {
+    let mut loader = WaypointLoader::new();
+    text.bytes()
+        .filter_map(|b| loader.feed(b))
+        .map(|r| r.map(|w| w.as_slice().to_vec()))
+        .collect()
+}
+
+fn assert_near(a: f32, b: f32, within: f32) {
+    assert!((a - b).abs() < within, "{} != {}", a, b);
+}
+
+#[test]
+fn loads_a_list() {
+    let text =
+        "waypoints\r\ntop -33.8512 151.2341\r\n\r\n# the gate\r\ngate 33.86015 -1.5\r\nend\r\n";
+    let loaded = load(text);
+    assert_eq!(
+        loaded,
+        vec![Ok(vec![
+            Waypoint::new("top", -338_512_000, 1_512_341_000),
+            Waypoint::new("gate", 338_601_500, -15_000_000),
+        ])]
+    );
+    assert_eq!(loaded[0].as_ref().unwrap()[1].name(), "gate");
+}
+
+#[test]
+fn ignores_lines_outside_an_upload() {
+    assert_eq!(load("hello\nend\nwaypoints\nend\n"), vec![Ok(vec![])]);
+}
+
+#[test]
+fn long_names_are_cut_short() {
+    let loaded = load("waypoints\nwindward_mark 1 2\nend\n");
+    assert_eq!(loaded[0].as_ref().unwrap()[0].name(), "windward");
+}
+
+#[test]
+fn bad_lines_fail_the_upload() {
+    let bad = [
+        "top -33.8512",
+        "top -33.8512 151.2341 7",
+        "top 91 0",
+        "top 0 180.5",
+        "top 1.23456789 0",
+        "top 1e3 0",
+        "top -+1 0",
+        "top 0 0 00000000000000000000000000000000000000000000000000000",
+    ];
+    for line in bad.iter() {
+        let text = format!("waypoints\nok 1 2\n{}\nok 3 4\nend\n", line);
+        assert_eq!(load(&text), vec![Err(LoadError::BadLine(3))], "{}", line);
+    }
+}
+
+#[test]
+fn too_many() {
+    let mut text = String::from("waypoints\n");
+    for i in 0..=MAX_WAYPOINTS {
+        text += &format!("m{} 1 {}\n", i, i);
+    }
+    text += "end\n";
+    assert_eq!(load(&text), vec![Err(LoadError::TooMany)]);
+}
+
+#[test]
+fn bearings() {
+    assert_near(bearing(0.0, 1.0), 0.0, 0.2);
+    assert_near(bearing(1.0, 0.0), 90.0, 0.2);
+    assert_near(bearing(0.0, -1.0), 180.0, 0.2);
+    assert_near(bearing(-1.0, 1.0), 315.0, 0.2);
+}
+
+#[test]
+fn navigate_to_a_mark() {
+    // A mark a kilometre north east of here, roughly
+    let here = (-338_500_000, 1_512_100_000);
+    let mark = Waypoint::new("top", -338_436_400, 1_512_176_600);
+
+    let leg = mark.navigate(here, 10.0, Some(0.0));
+    assert_near(leg.bearing, 45.0, 0.5);
+    assert_near(leg.distance_m, 1000.0, 5.0);
+    assert_near(leg.vmg.unwrap(), 7.07, 0.05);
+
+    // Sailing away from it
+    let leg = mark.navigate(here, 10.0, Some(225.0));
+    assert_near(leg.vmg.unwrap(), -10.0, 0.05);
+
+    assert!(mark.navigate(here, 0.0, None).vmg.is_none());
+}
@@ -1,6 +1,12 @@
 /* Linker script for the STM32F103C8T6 */
 MEMORY
 {
-  FLASH : ORIGIN = 0x08000000, LENGTH = 512K
+  /* The 128K sectors are left out of FLASH: sector 5 holds the settings
+     and sectors 6 and 7 the track log. See src/flash_region.rs. An image
+     too big for the first 128K fails to link rather than spilling into
+     them. */
+  FLASH : ORIGIN = 0x08000000, LENGTH = 128K
+  SETTINGS : ORIGIN = 0x08020000, LENGTH = 128K
+  LOG : ORIGIN = 0x08040000, LENGTH = 256K
   RAM : ORIGIN = 0x20000000, LENGTH = 96K
 }
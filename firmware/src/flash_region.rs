@@ -0,0 +1,128 @@
+use core::cell::RefCell;
+
+use cortex_m::interrupt::{self, Mutex};
+use gpstracker_core::storage::Flash;
+use stm32f4xx_hal::flash::{self, FlashExt};
+use stm32f4xx_hal::pac::FLASH;
+
+/// The flash controller, shared by the regions
+pub type SharedFlash = Mutex<RefCell<FLASH>>;
+
+const UNLOCK_KEY1: u32 = 0x4567_0123;
+const UNLOCK_KEY2: u32 = 0xcdef_89ab;
+
+/// A run of equally sized sectors of the internal flash, left out of
+/// FLASH in memory.x.
+///
+/// The cpu stalls while the flash is being erased or programmed, as the
+/// code runs from the same bank. Erasing a 128K sector takes a second or
+/// two. Interrupts aren't held off for it, so they're served as soon as
+/// the flash lets them run, but gps data received meanwhile may be lost.
+pub struct FlashRegion {
+    flash: &'static SharedFlash,
+    offset: usize,
+    first_sector: u8,
+    sector_size: usize,
+    sectors: usize,
+}
+
+impl FlashRegion {
+    /// The SETTINGS region: sector 5 of the STM32F401CE. A whole 128K
+    /// sector for 32 byte records is generous, but the smaller sectors
+    /// are at the start of the flash, under the vector table and code. It
+    /// does mean thousands of saves between erases.
+    pub fn settings(flash: &'static SharedFlash) -> Self {
+        FlashRegion {
+            flash,
+            offset: 0x20000,
+            first_sector: 5,
+            sector_size: 128 * 1024,
+            sectors: 1,
+        }
+    }
+
+    /// The LOG region: sectors 6 and 7 of the STM32F401CE
+    pub fn track_log(flash: &'static SharedFlash) -> Self {
+        FlashRegion {
+            flash,
+            offset: 0x40000,
+            first_sector: 6,
+            sector_size: 128 * 1024,
+            sectors: 2,
+        }
+    }
+
+    // Only init and idle use the regions, so the flash is never already
+    // borrowed. Interrupts are held off meanwhile, so erases are waited for
+    // outside.
+    fn with_flash<R>(&self, f: impl FnOnce(&mut FLASH) -> R) -> R {
+        interrupt::free(|cs| f(&mut self.flash.borrow(cs).borrow_mut()))
+    }
+}
+
+impl Flash for FlashRegion {
+    type Error = flash::Error;
+
+    fn sector_size(&self) -> usize {
+        self.sector_size
+    }
+
+    fn sector_count(&self) -> usize {
+        self.sectors
+    }
+
+    fn read(&self, offset: usize, buf: &mut [u8]) {
+        let start = self.offset + offset;
+        self.with_flash(|flash| buf.copy_from_slice(&flash.read()[start..start + buf.len()]));
+    }
+
+    fn erase_sector(&mut self, sector: usize) -> Result<(), Self::Error> {
+        let sector = self.first_sector + sector as u8;
+        self.with_flash(|flash| start_erase(flash, sector));
+        while self.with_flash(|flash| flash.sr.read().bsy().bit_is_set()) {}
+        self.with_flash(finish_erase)
+    }
+
+    fn program(&mut self, offset: usize, data: &[u8]) -> Result<(), Self::Error> {
+        let offset = self.offset + offset;
+        self.with_flash(|flash| flash.unlocked().program(offset, data.iter()))
+    }
+}
+
+// Start erasing `sector`, as the HAL does, but without waiting for it to
+// finish
+fn start_erase(flash: &mut FLASH, sector: u8) {
+    flash.keyr.write(|w| unsafe { w.bits(UNLOCK_KEY1) });
+    flash.keyr.write(|w| unsafe { w.bits(UNLOCK_KEY2) });
+    flash.cr.modify(|_, w| unsafe {
+        w.strt()
+            .set_bit()
+            .psize()
+            .bits(0b00)
+            .snb()
+            .bits(sector)
+            .ser()
+            .set_bit()
+            .pg()
+            .clear_bit()
+    });
+}
+
+// Lock the flash again once an erase has finished, and report how it went
+fn finish_erase(flash: &mut FLASH) -> Result<(), flash::Error> {
+    flash.cr.modify(|_, w| w.ser().clear_bit().lock().set_bit());
+    let sr = flash.sr.read();
+    if sr.pgserr().bit() {
+        Err(flash::Error::ProgrammingSequence)
+    } else if sr.pgperr().bit() {
+        Err(flash::Error::ProgrammingParallelism)
+    } else if sr.pgaerr().bit() {
+        Err(flash::Error::ProgrammingAlignment)
+    } else if sr.wrperr().bit() {
+        Err(flash::Error::WriteProtection)
+    } else if sr.operr().bit() {
+        Err(flash::Error::Operation)
+    } else {
+        Ok(())
+    }
+}
@@ -1,31 +1,47 @@
 #![no_main]
 #![no_std]
-mod debouncer;
-mod gps;
-mod layout;
+mod flash_region;
 mod memory_display;
-mod screens;
-#[allow(dead_code)]
-mod u8writer;
 
 // set the panic handler
 extern crate panic_rtt_target;
 
-#[rtic::app(device = stm32f4xx_hal::pac, dispatchers = [EXTI0])]
+#[rtic::app(device = stm32f4xx_hal::pac, dispatchers = [SPI3])]
 mod app {
 
     use embedded_hal::spi::{Mode, Phase, Polarity};
-    use stm32f4xx_hal::{adc, gpio::NoPin, prelude::*, serial};
+    use stm32f4xx_hal::{
+        adc,
+        gpio::{Edge, ExtiPin, NoPin},
+        pac::{self, EXTI},
+        prelude::*,
+        serial,
+        syscfg::SysCfg,
+    };
 
     use dwt_systick_monotonic::DwtSystick;
 
     use rtt_target::{rprintln, rtt_init_print};
 
-    use nb::block;
+    use core::cell::RefCell;
+    use core::fmt::Write;
+    use cortex_m::interrupt::Mutex;
+    use heapless::spsc::{Consumer, Producer, Queue};
+    use heapless::Deque;
 
-    use crate::debouncer;
+    use crate::flash_region::{FlashRegion, SharedFlash};
     use crate::memory_display;
-    use crate::{gps::Gps, screens};
+    use gpstracker_core::debouncer;
+    use gpstracker_core::gps_setup::GpsPort;
+    use gpstracker_core::gpx;
+    use gpstracker_core::input::{self, Input, RotarySwitch, Switch};
+    use gpstracker_core::menu::Action;
+    use gpstracker_core::screens::Page;
+    use gpstracker_core::settings::SettingsStore;
+    use gpstracker_core::track_log::TrackLog;
+    use gpstracker_core::uptime::Uptime;
+    use gpstracker_core::waypoints::{LoadError, WaypointLoader, Waypoints, MAX_WAYPOINTS};
+    use gpstracker_core::{gps::Gps, screens};
 
     type Display = crate::memory_display::MemoryDisplay<
         stm32f4xx_hal::spi::Spi<
@@ -70,36 +86,73 @@ mod app {
         ),
         u8,
     >;
-    type Key =
-        stm32f4xx_hal::gpio::gpioa::PA0<stm32f4xx_hal::gpio::Input<stm32f4xx_hal::gpio::PullUp>>;
+    // The debug link on USART2, for exporting the track log and
+    // uploading waypoints
+    type LinkTx = stm32f4xx_hal::serial::Tx<stm32f4xx_hal::pac::USART2>;
+    type LinkRx = stm32f4xx_hal::serial::Rx<stm32f4xx_hal::pac::USART2>;
+    // Every input is a reed switch to ground, on a pulled up pin
+    type InputPin =
+        stm32f4xx_hal::gpio::ErasedPin<stm32f4xx_hal::gpio::Input<stm32f4xx_hal::gpio::PullUp>>;
+    type Inputs = input::Inputs<InputPin, 4>;
     type Adc = stm32f4xx_hal::adc::Adc<stm32f4xx_hal::pac::ADC1>;
     type Vin = stm32f4xx_hal::gpio::gpioa::PA1<stm32f4xx_hal::gpio::Analog>;
     type Led = stm32f4xx_hal::gpio::gpioc::PC13<
         stm32f4xx_hal::gpio::Output<stm32f4xx_hal::gpio::PushPull>,
     >;
 
-    const MONO_HZ: u32 = 84_000_000; // 8 MHz
+    // The system clock, which the monotonic timer counts
+    const MONO_HZ: u32 = 32_000_000; // 32 MHz
+
+    const GESTURES: debouncer::GestureConfig = debouncer::GestureConfig {
+        long_ms: 1000,
+        double_ms: 300,
+        repeat_ms: 250,
+    };
+
+    // The page for each rotary switch position, in pin order
+    const ROTARY_PAGES: [Page; 4] = [Page::Speed, Page::Stats, Page::Ranking, Page::Cog];
 
     #[monotonic(binds = SysTick, default = true)]
     type MyMono = DwtSystick<MONO_HZ>;
 
+    // How often the inputs are sampled, while they're changing
+    const INPUT_POLL_MS: u32 = 10;
+
+    // The queue of inputs for idle to act on, which holds one fewer than
+    // this. Enough for a burst of presses while the display is redrawn.
+    const INPUT_QUEUE: usize = 8;
+
     #[shared]
     struct Shared {
         gps: Gps,
+        gps_link: GpsLink,
         vbat_mv: Option<u16>,
+        exti: EXTI,
+        // Milliseconds since start up, for timing inputs and the start
+        uptime: Uptime,
+        // A waypoint upload, for idle to save and show
+        waypoints: Option<Waypoints>,
+        // The anchor watch alarm, which flashes the led
+        anchor_alarm: bool,
     }
 
     #[local]
     struct Local {
-        serial: Serial,
         display: Display,
-        key: Key,
         adc: Adc,
         vbatin: Vin,
         led: Led,
+        track_log: TrackLog<FlashRegion>,
+        settings_store: SettingsStore<FlashRegion>,
+        inputs: Inputs,
+        input_tx: Producer<'static, Input, INPUT_QUEUE>,
+        input_rx: Consumer<'static, Input, INPUT_QUEUE>,
+        link_tx: LinkTx,
+        link_rx: LinkRx,
+        waypoint_loader: WaypointLoader,
     }
 
-    #[init()]
+    #[init(local = [input_queue: Queue<Input, INPUT_QUEUE> = Queue::new()])]
     fn init(cx: init::Context) -> (Shared, Local, init::Monotonics) {
         rtt_init_print!();
         rprintln!("init: START");
@@ -108,7 +161,7 @@ mod app {
         // HAL structs
         let rcc = cx.device.RCC.constrain();
 
-        let clocks = rcc.cfgr.use_hse(25.MHz()).sysclk(32.MHz()).freeze();
+        let clocks = rcc.cfgr.use_hse(25.MHz()).sysclk(MONO_HZ.Hz()).freeze();
 
         let mut dcb = cx.core.DCB;
         let dwt = cx.core.DWT;
@@ -128,7 +181,10 @@ mod app {
 
         // configure the gpio ports
         let gpioa = cx.device.GPIOA.split();
+        let gpiob = cx.device.GPIOB.split();
         let gpioc = cx.device.GPIOC.split();
+        let mut syscfg = cx.device.SYSCFG.constrain();
+        let mut exti = cx.device.EXTI;
         let key = gpioa.pa0.into_pull_up_input();
         let vbatin = gpioa.pa1.into_analog();
         let mut led = gpioc.pc13.into_push_pull_output();
@@ -147,13 +203,67 @@ mod app {
 
         let delay = cx.device.TIM5.delay_us(&clocks);
         let mut display = memory_display::new_ls027b7dh01(spi, cs, delay);
-        let layout = crate::layout::Layout::new();
+        let layout = gpstracker_core::layout::Layout::new();
         let font = layout.font_18();
         font.write_str(&mut display, font.char_point(0, 0), "booting...")
             .unwrap();
         display.refresh();
 
-        // Configure the serial port for GPS data
+        // The track log and the settings each have a region of the flash,
+        // and share its controller
+        let flash: &'static SharedFlash =
+            cortex_m::singleton!(: SharedFlash = Mutex::new(RefCell::new(cx.device.FLASH)))
+                .unwrap();
+
+        // Open the track log, recovering the write position
+        let track_log = TrackLog::open(FlashRegion::track_log(flash));
+
+        let settings_store = SettingsStore::open(FlashRegion::settings(flash));
+        let settings = settings_store.load();
+
+        // The debug link: USART2 on PA2 (tx) and PA3 (rx) at 115200 baud
+        let (mut link_tx, mut link_rx) = cx
+            .device
+            .USART2
+            .serial(
+                (gpioa.pa2.into_alternate(), gpioa.pa3.into_alternate()),
+                115200.bps(),
+                &clocks,
+            )
+            .unwrap()
+            .split();
+
+        // Holding the button at power up exports the track log as GPX
+        if key.is_low() {
+            rprintln!("init: gpx export");
+            font.write_str(&mut display, font.char_point(0, 1), "exporting gpx...")
+                .unwrap();
+            display.refresh();
+            gpx::write_log(&mut link_tx, &track_log).unwrap();
+            rprintln!("init: gpx export done");
+        }
+        link_rx.listen();
+
+        // The inputs: the button on PA0, the rotary switch positions on
+        // PB12 to PB15, and the flip switch on PB10. Unfitted switches
+        // read as open, which leaves the page alone and logging on.
+        // Each interrupts on change, which starts the inputs being polled.
+        let mut on_change = |pin| interrupt_on_change(pin, &mut syscfg, &mut exti);
+        let rotary = RotarySwitch::new(
+            [
+                on_change(gpiob.pb12.into_pull_up_input().erase()),
+                on_change(gpiob.pb13.into_pull_up_input().erase()),
+                on_change(gpiob.pb14.into_pull_up_input().erase()),
+                on_change(gpiob.pb15.into_pull_up_input().erase()),
+            ],
+            ROTARY_PAGES,
+        );
+        let flip = Switch::new(on_change(gpiob.pb10.into_pull_up_input().erase()));
+        let inputs = Inputs::new(on_change(key.erase()), GESTURES, Some(rotary), Some(flip));
+
+        // Configure the serial port for GPS data. The baud rate is only a
+        // starting point: the receiver is looked for at each of the rates it
+        // might be at, from the USART1 interrupt and gps_timeout.
         let tx = gpioa.pa9.into_alternate();
         let rx = gpioa.pa10.into_alternate();
 
@@ -162,13 +272,20 @@ mod app {
             .USART1
             .serial((tx, rx), 9600.bps(), &clocks)
             .unwrap();
+        serial.listen(serial::Event::Rxne);
+        let mut gps_link = GpsLink {
+            serial,
+            pclk2_hz: clocks.pclk2().raw(),
+            tx: Deque::new(),
+            timer: None,
+        };
         let mut gps = Gps::new();
+        gps.apply_settings(&settings);
 
         rprintln!("init: gps");
 
-        gps.init(&mut serial);
+        gps.start(&mut gps_link);
         led.set_low();
-        serial.listen(serial::Event::Rxne);
 
         // Configure the ADC for battery voltage
         let adc_config = adc::config::AdcConfig::default();
@@ -177,59 +294,308 @@ mod app {
         // Periodically read the battery voltage
         read_batv::spawn_after(250.millis()).unwrap();
 
+        // Read the initial switch positions
+        poll_inputs::spawn().unwrap();
+
         let shared = Shared {
             gps,
+            gps_link,
             vbat_mv: Some(0),
+            exti,
+            uptime: Uptime::new(MONO_HZ),
+            waypoints: None,
+            anchor_alarm: false,
         };
 
         layout.clear(&mut display).unwrap();
 
+        let (input_tx, input_rx) = cx.local.input_queue.split();
+
         let local = Local {
-            serial,
             display,
-            key,
+            inputs,
+            input_tx,
+            input_rx,
             adc,
             vbatin,
             led,
+            track_log,
+            settings_store,
+            link_tx,
+            link_rx,
+            waypoint_loader: WaypointLoader::new(),
         };
 
         rprintln!("init: DONE");
         (shared, local, init::Monotonics(mono))
     }
 
-    #[task(binds = USART1, shared=[gps], local=[serial])]
-    fn usart1(mut cx: usart1::Context) {
-        let ereceived: Result<u8, serial::Error> = block!(cx.local.serial.read());
-        match ereceived {
-            Result::Err(_e) => {
-                rprintln!("usart1: error");
-                cx.shared.gps.lock(|gps| gps.parse_clear());
+    // Each byte from the receiver, and the next of anything queued to
+    // send to it. This runs above the other tasks, so that no byte from
+    // the receiver is missed.
+    #[task(binds = USART1, priority = 2, shared=[gps, gps_link])]
+    fn usart1(cx: usart1::Context) {
+        (cx.shared.gps, cx.shared.gps_link).lock(|gps, link| {
+            let ereceived: nb::Result<u8, serial::Error> = link.serial.read();
+            match ereceived {
+                Result::Err(nb::Error::WouldBlock) => (),
+                Result::Err(nb::Error::Other(_e)) => {
+                    rprintln!("usart1: error");
+                    gps.parse_clear();
+                }
+                Result::Ok(received) => gps.received(received, link),
+            }
+            link.send_next();
+        });
+    }
+
+    // A GPS setup step has had no answer in time. Starting a timer cancels
+    // the one before, but one that has already fired may still run, which
+    // Gps ignores.
+    #[task(shared=[gps, gps_link], capacity = 2)]
+    fn gps_timeout(cx: gps_timeout::Context, id: u32) {
+        (cx.shared.gps, cx.shared.gps_link).lock(|gps, link| gps.timeout(id, link));
+    }
+
+    // The bytes to send to the receiver, and the baud rate changes between
+    // them
+    enum GpsTx {
+        Byte(u8),
+        Baud(u32),
+    }
+
+    // Room for a few setup commands
+    const GPS_TX_LEN: usize = 256;
+
+    // The serial port to the receiver, for setting it up
+    pub struct GpsLink {
+        serial: Serial,
+        // The clock the baud rate is divided down from
+        pclk2_hz: u32,
+        // What is still to be sent, a byte per USART1 interrupt
+        tx: Deque<GpsTx, GPS_TX_LEN>,
+        // The setup step's timer
+        timer: Option<gps_timeout::SpawnHandle>,
+    }
+
+    impl GpsLink {
+        fn queue(&mut self, tx: GpsTx) {
+            if self.tx.push_back(tx).is_err() {
+                rprintln!("gps: send queue full");
+            }
+            self.listen_for_next();
+        }
+
+        // Send the next byte if the last has gone, or change the baud rate
+        // once everything before has been sent
+        fn send_next(&mut self) {
+            match self.tx.front() {
+                None => (),
+                Some(GpsTx::Byte(b)) => {
+                    if self.serial.write(*b).is_ok() {
+                        self.tx.pop_front();
+                    }
+                }
+                Some(GpsTx::Baud(baud)) => {
+                    let baud = *baud;
+                    if self.change_baud(baud) {
+                        self.tx.pop_front();
+                    }
+                }
+            }
+            self.listen_for_next();
+        }
+
+        // Interrupt when the port is ready for what's next to send: a byte
+        // once the last has been taken, or a baud rate change once it has
+        // gone
+        fn listen_for_next(&mut self) {
+            let (txe, tc) = match self.tx.front() {
+                None => (false, false),
+                Some(GpsTx::Byte(_)) => (true, false),
+                Some(GpsTx::Baud(_)) => (false, true),
+            };
+            // Safety: only the GPS serial port uses USART1, and this is
+            // only called with the link locked
+            let usart = unsafe { &*pac::USART1::ptr() };
+            usart.cr1.modify(|_, w| w.txeie().bit(txe).tcie().bit(tc));
+        }
+
+        // The HAL only sets the baud rate up once. Clearing UE cuts off a
+        // byte being sent, and the receiver ignores a message without its
+        // checksum, so this waits until the last one is out.
+        fn change_baud(&mut self, baud: u32) -> bool {
+            // Safety: only the GPS serial port uses USART1, and nothing else
+            // writes to it while the baud rate changes
+            let usart = unsafe { &*pac::USART1::ptr() };
+            if usart.sr.read().tc().bit_is_clear() {
+                return false;
+            }
+            usart.cr1.modify(|_, w| w.ue().clear_bit());
+            // With 16 times oversampling, BRR holds the divider in 1/16ths
+            usart
+                .brr
+                .write(|w| unsafe { w.bits((self.pclk2_hz + baud / 2) / baud) });
+            usart.cr1.modify(|_, w| w.ue().set_bit());
+            true
+        }
+    }
+
+    impl GpsPort for GpsLink {
+        fn send(&mut self, bytes: &[u8]) {
+            for b in bytes {
+                self.queue(GpsTx::Byte(*b));
+            }
+        }
+
+        fn set_baud(&mut self, baud: u32) {
+            self.queue(GpsTx::Baud(baud));
+        }
+
+        fn start_timer(&mut self, id: u32, ms: u32) {
+            if let Some(timer) = self.timer.take() {
+                let _ = timer.cancel();
+            }
+            // There's room, as only one timer is ever waiting, and at most
+            // one more has fired
+            self.timer = Some(gps_timeout::spawn_after(ms.millis(), id).unwrap());
+        }
+    }
+
+    // Waypoint uploads over the debug link, each answered with a line.
+    // This runs above link_reply, so that the upload keeps being read
+    // while a reply is sent.
+    #[task(binds = USART2, priority = 2, shared=[waypoints], local=[link_rx, waypoint_loader])]
+    fn usart2(mut cx: usart2::Context) {
+        let received = match cx.local.link_rx.read() {
+            Ok(received) => received,
+            Err(_) => return,
+        };
+        let reply = match cx.local.waypoint_loader.feed(received) {
+            Some(Ok(waypoints)) => {
+                let count = waypoints.len();
+                cx.shared.waypoints.lock(|w| *w = Some(waypoints));
+                Ok(count)
+            }
+            Some(Err(e)) => Err(e),
+            None => return,
+        };
+        if link_reply::spawn(reply).is_err() {
+            rprintln!("usart2: reply dropped");
+        }
+    }
+
+    // Answer a waypoint upload with the number loaded, or what was wrong
+    #[task(local=[link_tx])]
+    fn link_reply(cx: link_reply::Context, reply: Result<usize, LoadError>) {
+        let tx = cx.local.link_tx;
+        let _ = match reply {
+            Ok(count) => write!(tx, "ok: {} waypoints\r\n", count),
+            Err(LoadError::BadLine(line)) => write!(tx, "error: line {}\r\n", line),
+            Err(LoadError::TooMany) => {
+                write!(tx, "error: more than {} waypoints\r\n", MAX_WAYPOINTS)
             }
-            Result::Ok(received) => {
-                cx.shared.gps.lock(|gps| gps.parse_u8(received));
+        };
+    }
+
+    // Any change starts the inputs being polled, until they've settled
+    #[task(binds = EXTI0, shared=[exti])]
+    fn exti0(mut cx: exti0::Context) {
+        cx.shared
+            .exti
+            .lock(|exti| exti.pr.write(|w| unsafe { w.bits(1 << 0) }));
+        // Fails harmlessly if polling is already under way
+        let _ = poll_inputs::spawn();
+    }
+
+    #[task(binds = EXTI15_10, shared=[exti])]
+    fn exti15_10(mut cx: exti15_10::Context) {
+        cx.shared
+            .exti
+            .lock(|exti| exti.pr.write(|w| unsafe { w.bits(0xfc00) }));
+        let _ = poll_inputs::spawn();
+    }
+
+    #[task(local=[inputs, input_tx], shared=[uptime])]
+    fn poll_inputs(mut cx: poll_inputs::Context) {
+        // Input timings are short, so wrapping at 49 days is harmless
+        let now_ms = now_ms(&mut cx.shared.uptime) as u32;
+        if let Some(input) = cx.local.inputs.poll(now_ms) {
+            if cx.local.input_tx.enqueue(input).is_err() {
+                rprintln!("poll_inputs: input queue full");
             }
         }
+        if !cx.local.inputs.is_idle() {
+            poll_inputs::spawn_after(INPUT_POLL_MS.millis()).unwrap();
+        }
     }
 
-    #[idle(shared=[gps, vbat_mv], local=[key,display])]
+    #[idle(
+        shared=[gps, vbat_mv, waypoints, anchor_alarm, uptime],
+        local=[display, track_log, settings_store, input_rx]
+    )]
     fn idle(mut cx: idle::Context) -> ! {
         rprintln!("idle0: START");
+        let mut settings = cx.local.settings_store.load();
         let mut screens = screens::Screens::new();
-        let mut debounce = debouncer::Debouncer::new(2);
+        let mut logging = true;
 
-        screens.render(cx.local.display).unwrap();
+        screens
+            .set_inverted(settings.invert, cx.local.display)
+            .unwrap();
+        screens.set_page(settings.page, cx.local.display).unwrap();
         screens.update_vbat(0);
+        screens.set_waypoints(cx.local.settings_store.load_waypoints());
 
         cx.local.display.refresh();
 
         loop {
-            let key = cx.local.key.is_high();
-            match debounce.next(key) {
-                Option::Some(debouncer::Transition::ToHigh) => {
-                    screens.next_page(cx.local.display).unwrap();
-                    cx.local.display.refresh();
+            while let Some(input) = cx.local.input_rx.dequeue() {
+                if let Input::Logging(on) = input {
+                    if on && !logging {
+                        cx.local.track_log.start_session();
+                    }
+                    logging = on;
+                }
+                let action = screens.input(input, &settings, cx.local.display).unwrap();
+                match action {
+                    Some(Action::Exit(new)) if new != settings => {
+                        settings = new;
+                        cx.shared.gps.lock(|gps| gps.apply_settings(&settings));
+                        if cx.local.settings_store.save(&settings).is_err() {
+                            rprintln!("idle: settings write failed");
+                        }
+                    }
+                    Some(Action::ResetTrip) => {
+                        cx.shared.gps.lock(|gps| gps.reset_trip());
+                    }
+                    _ => {}
+                }
+                cx.local.display.refresh();
+            }
+
+            // Keep and navigate to newly uploaded waypoints
+            let owaypoints = cx.shared.waypoints.lock(|waypoints| waypoints.take());
+            if let Some(waypoints) = owaypoints {
+                if cx.local.settings_store.save_waypoints(&waypoints).is_err() {
+                    rprintln!("idle: waypoints write failed");
+                }
+                screens.set_waypoints(waypoints);
+            }
+
+            // Run the start countdown, which turns to the speed page at
+            // the gun, and flash the display for the anchor alarm
+            let now_ms = now_ms(&mut cx.shared.uptime);
+            screens.tick(now_ms, cx.local.display).unwrap();
+            let alarm = screens.alarm();
+            cx.shared.anchor_alarm.lock(|a| *a = alarm);
+
+            // Log the latest fix, if there is one and logging is on
+            let opoint = cx.shared.gps.lock(|gps| gps.take_track_point());
+            if let Some(point) = opoint {
+                if logging && cx.local.track_log.append(&point).is_err() {
+                    rprintln!("idle: track log write failed");
                 }
-                _ => {}
             }
 
             // Fetch the updated gps values, if present
@@ -248,17 +614,34 @@ mod app {
                 updated = true;
             }
 
+            // The battery is read four times a second, which keeps the
+            // display's VCOM toggling
             if updated {
                 screens.render(cx.local.display).unwrap();
+                cx.local.display.refresh();
             }
-            cx.local.display.refresh();
+
+            // Sleep until the next interrupt: gps data, the battery timer or
+            // an input. Anything that arrives between the checks above and
+            // here waits for the next one, which is at most 250ms away.
+            cortex_m::asm::wfi();
         }
     }
 
-    #[task(local=[adc,vbatin,led], shared=[vbat_mv])]
+    fn interrupt_on_change<P: ExtiPin>(mut pin: P, syscfg: &mut SysCfg, exti: &mut EXTI) -> P {
+        pin.make_interrupt_source(syscfg);
+        pin.trigger_on_edge(exti, Edge::RisingFalling);
+        pin.enable_interrupt(exti);
+        pin
+    }
+
+    #[task(local=[adc,vbatin], shared=[vbat_mv, anchor_alarm, uptime])]
     fn read_batv(mut cx: read_batv::Context) {
         rprintln!("read_batv");
 
+        // Read the clock often enough to see each wrap of the timer
+        now_ms(&mut cx.shared.uptime);
+
         // Read the pin voltage, and write it to the shared variable
         let sample = cx
             .local
@@ -267,7 +650,23 @@ mod app {
         let mv = cx.local.adc.sample_to_millivolts(sample) * 2;
         cx.shared.vbat_mv.lock(|vbat_mv| *vbat_mv = Some(mv));
 
-        cx.local.led.toggle();
+        // Toggle the led, and again halfway to the next time for the
+        // anchor alarm, to blink it twice as fast
+        let _ = toggle_led::spawn();
+        if cx.shared.anchor_alarm.lock(|a| *a) {
+            let _ = toggle_led::spawn_after(125.millis());
+        }
         read_batv::spawn_after(250.millis()).unwrap();
     }
+
+    #[task(local=[led], capacity = 2)]
+    fn toggle_led(cx: toggle_led::Context) {
+        cx.local.led.toggle();
+    }
+
+    // Milliseconds since start up. The timer is read inside the lock, so
+    // that the readings reach the clock in order.
+    fn now_ms(uptime: &mut impl rtic::Mutex<T = Uptime>) -> u64 {
+        uptime.lock(|uptime| uptime.now_ms(monotonics::now().ticks()))
+    }
 }
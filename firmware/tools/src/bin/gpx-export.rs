@@ -0,0 +1,92 @@
+//! Convert a dump of the track log flash region to GPX.
+//!
+//!     gpx-export [--sector-size bytes] [--session n] <dump>
+//!
+//! The dump is the raw LOG region from memory.x, eg from openocd with
+//! `dump_image log.bin 0x08040000 0x40000`. Sessions are numbered from
+//! 0, oldest first; by default every session is exported.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::process::exit;
+
+use gpstracker_core::gpx;
+use gpstracker_core::storage::RamFlash;
+use gpstracker_core::track_log::TrackLog;
+
+const DEFAULT_SECTOR_SIZE: usize = 128 * 1024;
+
+fn usage() -> ! {
+    eprintln!("usage: gpx-export [--sector-size bytes] [--session n] <dump>");
+    exit(1);
+}
+
+/// Adapts an io::Write for the fmt::Write based gpx writer
+struct FmtWriter<W: Write> {
+    out: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> fmt::Write for FmtWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.out.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+fn main() -> io::Result<()> {
+    let mut sector_size = DEFAULT_SECTOR_SIZE;
+    let mut session = None;
+    let mut path = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--sector-size" => sector_size = value().parse().unwrap_or_else(|_| usage()),
+            "--session" => session = Some(value().parse().unwrap_or_else(|_| usage())),
+            _ if path.is_none() => path = Some(arg),
+            _ => usage(),
+        }
+    }
+    let path = path.unwrap_or_else(|| usage());
+
+    let mut dump = std::fs::read(path)?;
+    if dump.is_empty() || dump.len() % sector_size != 0 {
+        eprintln!(
+            "gpx-export: dump size {} is not a multiple of the sector size {}",
+            dump.len(),
+            sector_size
+        );
+        exit(1);
+    }
+    let log = TrackLog::open(RamFlash::new(&mut dump, sector_size));
+
+    let stdout = io::stdout();
+    let mut out = FmtWriter {
+        out: io::BufWriter::new(stdout.lock()),
+        error: None,
+    };
+    let result = match session {
+        None => gpx::write_log(&mut out, &log),
+        Some(n) => match log.sessions().nth(n) {
+            Some(session) => gpx::write_header(&mut out)
+                .and_then(|_| gpx::write_session(&mut out, &session))
+                .and_then(|_| gpx::write_footer(&mut out)),
+            None => {
+                eprintln!(
+                    "gpx-export: no session {}, the log has {}",
+                    n,
+                    log.sessions().count()
+                );
+                exit(1);
+            }
+        },
+    };
+    if result.is_err() {
+        return Err(out.error.take().unwrap());
+    }
+    out.out.flush()
+}
@@ -0,0 +1,73 @@
+//! Render every screen to an image file, for reviewing layout changes
+//! without flashing the board.
+//!
+//!     render-screens [--format pbm|png] [--vbat mV] [--speed kt] [--capture file] <outdir>
+//!
+//! The screens show a fixed sample state, with the speed and battery
+//! voltage overridable. With `--capture`, they show the final state from
+//! replaying a raw serial capture instead.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::PathBuf;
+use std::process::exit;
+
+use gpstracker_core::screens::Page;
+use gpstracker_tools::image::{self, Format};
+use gpstracker_tools::{render, replay};
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: render-screens [--format pbm|png] [--vbat mV] [--speed kt] [--capture file] <outdir>"
+    );
+    exit(1);
+}
+
+fn main() -> io::Result<()> {
+    let mut format = Format::Png;
+    let mut vbat_mv = 3900;
+    let mut speed = None;
+    let mut capture = None;
+    let mut outdir = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--format" => format = Format::parse(&value()).unwrap_or_else(|| usage()),
+            "--vbat" => vbat_mv = value().parse().unwrap_or_else(|_| usage()),
+            "--speed" => speed = Some(value().parse().unwrap_or_else(|_| usage())),
+            "--capture" => capture = Some(value()),
+            _ if outdir.is_none() => outdir = Some(PathBuf::from(arg)),
+            _ => usage(),
+        }
+    }
+    let outdir = outdir.unwrap_or_else(|| usage());
+
+    let mut gps = match capture {
+        Some(path) => {
+            let snapshots = replay::replay(BufReader::new(File::open(path)?))?;
+            match snapshots.last() {
+                Some(data) => data.clone(),
+                None => {
+                    eprintln!("render-screens: no gps updates in capture");
+                    exit(1);
+                }
+            }
+        }
+        None => render::sample_gps_data(),
+    };
+    if let Some(speed) = speed {
+        gps.speed = speed;
+    }
+
+    std::fs::create_dir_all(&outdir)?;
+    for page in Page::ALL.iter() {
+        let fb = render::render_page(*page, &gps, vbat_mv);
+        let path = outdir.join(format!("{}.{}", page.name(), format.extension()));
+        let mut out = BufWriter::new(File::create(&path)?);
+        image::write(&mut out, format, &fb)?;
+        println!("{}", path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,40 @@
+//! Replay a raw serial capture from the gps receiver through the
+//! tracker's parser, printing each update as csv or json.
+//!
+//!     ubx-replay [--format csv|json] <capture>
+
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::process::exit;
+
+use gpstracker_tools::replay::{self, Format};
+
+fn usage() -> ! {
+    eprintln!("usage: ubx-replay [--format csv|json] <capture>");
+    exit(1);
+}
+
+fn main() -> io::Result<()> {
+    let mut format = Format::Csv;
+    let mut path = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = args
+                    .next()
+                    .and_then(|f| Format::parse(&f))
+                    .unwrap_or_else(|| usage());
+            }
+            _ if path.is_none() => path = Some(arg),
+            _ => usage(),
+        }
+    }
+    let path = path.unwrap_or_else(|| usage());
+
+    let capture = BufReader::new(File::open(path)?);
+    let snapshots = replay::replay(capture)?;
+    let stdout = io::stdout();
+    replay::write(&mut stdout.lock(), format, &snapshots)
+}
@@ -0,0 +1,126 @@
+//! Writing framebuffers out as image files, as the LCD would show them.
+use std::io::{self, Write};
+
+use gpstracker_core::framebuffer::FrameBuffer;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Pbm,
+    Png,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Option<Format> {
+        match s {
+            "pbm" => Some(Format::Pbm),
+            "png" => Some(Format::Png),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Pbm => "pbm",
+            Format::Png => "png",
+        }
+    }
+}
+
+pub fn write<W: Write, const N: usize, const H: usize>(
+    out: &mut W,
+    format: Format,
+    fb: &FrameBuffer<N, H>,
+) -> io::Result<()> {
+    match format {
+        Format::Pbm => write_pbm(out, fb),
+        Format::Png => write_png(out, fb),
+    }
+}
+
+/// Write a binary (P4) pbm. A set bit is black in a pbm, but white
+/// on the LCD, so the framebuffer is inverted.
+pub fn write_pbm<W: Write, const N: usize, const H: usize>(
+    out: &mut W,
+    fb: &FrameBuffer<N, H>,
+) -> io::Result<()> {
+    write!(out, "P4\n{} {}\n", fb.width(), fb.height())?;
+    let inverted: Vec<u8> = fb.as_bytes().iter().map(|b| !b).collect();
+    out.write_all(&inverted)
+}
+
+/// Write a 1 bit greyscale png. The image data is stored uncompressed,
+/// which keeps this dependency free at the cost of file size.
+pub fn write_png<W: Write, const N: usize, const H: usize>(
+    out: &mut W,
+    fb: &FrameBuffer<N, H>,
+) -> io::Result<()> {
+    out.write_all(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'])?;
+
+    let mut ihdr = Vec::new();
+    ihdr.extend(&(fb.width() as u32).to_be_bytes());
+    ihdr.extend(&(fb.height() as u32).to_be_bytes());
+    // bit depth 1, greyscale, deflate, no filtering, no interlace
+    ihdr.extend(&[1, 0, 0, 0, 0]);
+    write_png_chunk(out, b"IHDR", &ihdr)?;
+
+    // Each line is preceded by its filter type, 0 for none
+    let mut raw = Vec::new();
+    for line in fb.lines() {
+        raw.push(0);
+        raw.extend(line);
+    }
+    write_png_chunk(out, b"IDAT", &zlib_stored(&raw))?;
+
+    write_png_chunk(out, b"IEND", &[])
+}
+
+fn write_png_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data.iter()));
+    out.write_all(&crc.to_be_bytes())
+}
+
+/// A zlib stream holding `data` in uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 0xffff;
+
+    let mut z = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        z.extend(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        z.push(last as u8);
+        z.extend(&len.to_le_bytes());
+        z.extend(&(!len).to_le_bytes());
+        z.extend(block);
+    }
+    z.extend(&adler32(data).to_be_bytes());
+    z
+}
+
+fn crc32<'a, I: Iterator<Item = &'a u8>>(bytes: I) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for b in bytes {
+        crc ^= *b as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let mut a = 1u32;
+    let mut b = 0u32;
+    for d in data {
+        a = (a + *d as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
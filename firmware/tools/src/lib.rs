@@ -0,0 +1,4 @@
+//! Host side tools for working with data from the gps tracker.
+pub mod image;
+pub mod render;
+pub mod replay;
@@ -0,0 +1,92 @@
+//! Rendering the screens into memory rather than onto the LCD.
+use gpstracker_core::framebuffer::{self, FrameBuffer};
+use gpstracker_core::gps::{GpsData, GpsTime};
+use gpstracker_core::gps_setup::GpsStatus;
+use gpstracker_core::history::{SpeedHistory, TrackHistory};
+use gpstracker_core::manoeuvre::{Kind, Manoeuvre, ManoeuvreStats};
+use gpstracker_core::metrics::Rankings;
+use gpstracker_core::screens::{Page, Screens};
+use gpstracker_core::units::SpeedUnit;
+
+pub type Frame = FrameBuffer<12000, 240>;
+
+/// Render `page` as it is shown after receiving `gps` and `vbat_mv`.
+pub fn render_page(page: Page, gps: &GpsData, vbat_mv: u16) -> Frame {
+    let mut fb = framebuffer::new_ls027b7dh01();
+    let mut screens = Screens::new();
+    screens.set_page(page, &mut fb).unwrap();
+    screens.update_gps(gps);
+    screens.update_vbat(vbat_mv);
+    screens.render(&mut fb).unwrap();
+    fb
+}
+
+/// A plausible mid session state, exercising every field on the screens.
+pub fn sample_gps_data() -> GpsData {
+    GpsData {
+        sat_in_use: 9,
+        course: Some(237.0),
+        speed: 12.3,
+        max_speed: 21.7,
+        avg_speed: 11.8,
+        max_avg_speed: 19.4,
+        avg_window_s: 10,
+        distance_m: 14_816,
+        hdop: Some(0.9),
+        latitude: Some(-33.856_78),
+        longitude: Some(151.215_3),
+        position_raw: Some((-338_567_800, 1_512_153_000)),
+        time: Some(GpsTime {
+            year: 2022,
+            month: 6,
+            day: 19,
+            hour: 14,
+            min: 5,
+            sec: 32,
+        }),
+        rankings: Rankings {
+            peak_2s: Some(23.1),
+            avg_5x10s: Some(20.6),
+            nautical_mile: Some(16.2),
+            one_hour: None,
+            alpha_500: Some(17.9),
+        },
+        history: sample_history(),
+        track: sample_track(),
+        manoeuvres: ManoeuvreStats {
+            last: Some(Manoeuvre {
+                kind: Kind::Gybe,
+                turn_deg: 84.0,
+                speed_loss: 2.6,
+                recover_ms: Some(4_500),
+                distance_lost_m: 6.0,
+            }),
+            tacks: 11,
+            gybes: 6,
+        },
+        units: SpeedUnit::Knots,
+        status: GpsStatus::Ready,
+        rejected_samples: 3,
+    }
+}
+
+/// Ten minutes of speeds, rising and falling through the gusts
+fn sample_history() -> SpeedHistory {
+    let mut history = SpeedHistory::new(5);
+    for i in 0..1200 {
+        history.add(15.0 + 6.0 * (i as f32 / 80.0).sin(), 500);
+    }
+    history
+}
+
+/// A lap of a course, finishing at the sample position
+fn sample_track() -> TrackHistory {
+    let mut track = TrackHistory::new(10.0);
+    for i in 0..=180 {
+        let a = i as f32 * std::f32::consts::PI / 90.0;
+        let north = (3000.0 * a.sin()) as i32;
+        let east = (2500.0 * (1.0 - a.cos())) as i32;
+        track.add((-338_567_800 + north, 1_512_153_000 + east));
+    }
+    track
+}
@@ -0,0 +1,105 @@
+use std::io::{self, BufRead, Write};
+
+use gpstracker_core::gps::{Gps, GpsData, GpsTime};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Json,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Option<Format> {
+        match s {
+            "csv" => Some(Format::Csv),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Feed a raw serial capture through the parser a byte at a time,
+/// as the usart interrupt does, collecting every update it publishes.
+pub fn replay<R: BufRead>(capture: R) -> io::Result<Vec<GpsData>> {
+    let mut gps = Gps::new();
+    // A new Gps publishes its initial state, which isn't part of the capture
+    gps.take();
+
+    let mut snapshots = Vec::new();
+    for b in capture.bytes() {
+        gps.parse_u8(b?);
+        if let Some(data) = gps.take() {
+            snapshots.push(data);
+        }
+    }
+    Ok(snapshots)
+}
+
+pub fn write<W: Write>(out: &mut W, format: Format, snapshots: &[GpsData]) -> io::Result<()> {
+    match format {
+        Format::Csv => write_csv(out, snapshots),
+        Format::Json => write_json(out, snapshots),
+    }
+}
+
+const FIELDS: [&str; 11] = [
+    "time",
+    "sats",
+    "latitude",
+    "longitude",
+    "course",
+    "speed",
+    "max_speed",
+    "avg_speed",
+    "max_avg_speed",
+    "distance_m",
+    "hdop",
+];
+
+pub fn write_csv<W: Write>(out: &mut W, snapshots: &[GpsData]) -> io::Result<()> {
+    writeln!(out, "{}", FIELDS.join(","))?;
+    for data in snapshots {
+        writeln!(out, "{}", values(data, "").join(","))?;
+    }
+    Ok(())
+}
+
+pub fn write_json<W: Write>(out: &mut W, snapshots: &[GpsData]) -> io::Result<()> {
+    writeln!(out, "[")?;
+    for (i, data) in snapshots.iter().enumerate() {
+        let fields: Vec<String> = FIELDS
+            .iter()
+            .zip(values(data, "null"))
+            .map(|(name, value)| format!("\"{}\": {}", name, value))
+            .collect();
+        let sep = if i + 1 < snapshots.len() { "," } else { "" };
+        writeln!(out, "  {{{}}}{}", fields.join(", "), sep)?;
+    }
+    writeln!(out, "]")
+}
+
+/// The values of a snapshot in the order of `FIELDS`, with `missing`
+/// standing in for absent values.
+fn values(data: &GpsData, missing: &str) -> Vec<String> {
+    let opt = |v: Option<String>| v.unwrap_or_else(|| missing.to_string());
+    vec![
+        opt(data.time.as_ref().map(|t| format!("\"{}\"", iso8601(t)))),
+        data.sat_in_use.to_string(),
+        opt(data.latitude.map(|v| format!("{:.6}", v))),
+        opt(data.longitude.map(|v| format!("{:.6}", v))),
+        opt(data.course.map(|v| format!("{:.1}", v))),
+        format!("{:.3}", data.speed),
+        format!("{:.3}", data.max_speed),
+        format!("{:.3}", data.avg_speed),
+        format!("{:.3}", data.max_avg_speed),
+        data.distance_m.to_string(),
+        opt(data.hdop.map(|v| format!("{:.1}", v))),
+    ]
+}
+
+fn iso8601(t: &GpsTime) -> String {
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        t.year, t.month, t.day, t.hour, t.min, t.sec
+    )
+}
@@ -0,0 +1,71 @@
+use std::fs;
+use std::path::PathBuf;
+
+use gpstracker_core::screens::Page;
+use gpstracker_tools::image;
+use gpstracker_tools::render::{self, Frame};
+
+fn golden_path(page: Page) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.pbm", page.name()))
+}
+
+fn pbm(fb: &Frame) -> Vec<u8> {
+    let mut out = Vec::new();
+    image::write_pbm(&mut out, fb).unwrap();
+    out
+}
+
+/// Each screen, rendered from the sample state, must match its golden
+/// image. A missing golden image is a failure too, so that a checkout
+/// without them can't pass. With `UPDATE_GOLDEN` set, every image is
+/// written out instead, for review and commit.
+#[test]
+fn screens_match_golden_images() {
+    let gps = render::sample_gps_data();
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let mut mismatched = Vec::new();
+    let mut missing = Vec::new();
+
+    for page in Page::ALL.iter() {
+        let actual = pbm(&render::render_page(*page, &gps, 3900));
+        let path = golden_path(*page);
+        if update {
+            fs::write(&path, &actual).unwrap();
+            continue;
+        }
+        match fs::read(&path) {
+            Ok(expected) => {
+                if expected != actual {
+                    let failed = path.with_extension("actual.pbm");
+                    fs::write(&failed, &actual).unwrap();
+                    mismatched.push(failed);
+                }
+            }
+            Err(_) => missing.push(path),
+        }
+    }
+    assert!(
+        missing.is_empty(),
+        "no golden images, run with UPDATE_GOLDEN=1 to write them: {:?}",
+        missing
+    );
+    assert!(mismatched.is_empty(), "screens differ: {:?}", mismatched);
+}
+
+#[test]
+fn png_structure() {
+    let fb = render::render_page(Page::Speed, &render::sample_gps_data(), 3900);
+    let mut png = Vec::new();
+    image::write_png(&mut png, &fb).unwrap();
+
+    assert_eq!(&png[0..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&png[12..16], b"IHDR");
+    assert_eq!(&png[16..20], &400u32.to_be_bytes());
+    assert_eq!(&png[20..24], &240u32.to_be_bytes());
+    assert_eq!(&png[png.len() - 12..png.len() - 8], &[0, 0, 0, 0]);
+    assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+    // The well known crc of an empty IEND chunk
+    assert_eq!(&png[png.len() - 4..], &[0xae, 0x42, 0x60, 0x82]);
+}
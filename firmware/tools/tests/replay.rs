@@ -0,0 +1,69 @@
+// The UBX frame helpers are shared with the core tests
+#[path = "../../core/tests/common/mod.rs"]
+mod common;
+
+use common::{nav_odo, Pvt};
+use gpstracker_tools::replay::{self, Format};
+
+// A short session: a fix at 10 knots, then at 20 knots, with some
+// NMEA chatter from before the receiver was switched to UBX.
+fn capture() -> Vec<u8> {
+    let mut capture = b"$GPTXT,01,01,02,ANTSTATUS=OK*3B\r\n".to_vec();
+    let mut pvt = Pvt::new();
+    for (i, speed) in [5144, 5144, 10289, 10289].iter().enumerate() {
+        pvt.itow_ms = 36_000_000 + 500 * i as u32;
+        pvt.ground_speed_mm_s = *speed;
+        capture.extend(pvt.frame());
+        capture.extend(nav_odo(100 * i as u32));
+    }
+    capture
+}
+
+#[test]
+fn replay_snapshots() {
+    let snapshots = replay::replay(&capture()[..]).unwrap();
+    assert_eq!(snapshots.len(), 4);
+
+    let speeds: Vec<f32> = snapshots.iter().map(|d| d.speed).collect();
+    let expected = [5.0, 10.0, 15.0, 20.0];
+    for (s, e) in speeds.iter().zip(expected.iter()) {
+        assert!((s - e).abs() < 0.01, "{:?}", speeds);
+    }
+    let last = snapshots.last().unwrap();
+    assert!((last.max_speed - 20.0).abs() < 0.01);
+    assert_eq!(last.distance_m, 300);
+}
+
+#[test]
+fn csv_output() {
+    let snapshots = replay::replay(&capture()[..]).unwrap();
+    let mut out = Vec::new();
+    replay::write(&mut out, Format::Csv, &snapshots).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+
+    assert_eq!(lines.len(), 5);
+    assert_eq!(
+        lines[0],
+        "time,sats,latitude,longitude,course,speed,max_speed,avg_speed,max_avg_speed,distance_m,hdop"
+    );
+    assert!(lines[1].starts_with("\"2022-06-19T10:00:00Z\",8,"));
+    assert!(lines[1].contains(",0.0,5.000,5.000,"));
+    assert!(lines[4].ends_with(",300,"));
+}
+
+#[test]
+fn json_output() {
+    let snapshots = replay::replay(&capture()[..]).unwrap();
+    let mut out = Vec::new();
+    replay::write(&mut out, Format::Json, &snapshots).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+
+    assert_eq!(lines.len(), 6);
+    assert_eq!(lines[0], "[");
+    assert!(lines[1].contains("\"speed\": 5.000"));
+    assert!(lines[1].ends_with("\"hdop\": null},"));
+    assert!(lines[4].ends_with("}"));
+    assert_eq!(lines[5], "]");
+}